@@ -0,0 +1,11 @@
+#![no_main]
+
+use advent_of_code_2023_rust::day_16;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = day_16::day_16_part_1(text);
+        let _ = day_16::day_16_part_2(text);
+    }
+});
@@ -0,0 +1,623 @@
+/*
+    Animations of the simulation days, like the tilts of day 14 or
+    the beams of day 16, when the final count isn't enough to understand.
+
+    The days record frames with a Recorder while they simulate. The recorder
+    keeps at most a given number of frames: when it has too many, it drops
+    every other frame and records half as often, so it doesn't need to know
+    how long the simulation is in advance.
+
+    The frames can be played in the terminal, with commands typed on stdin
+    followed by enter, as there is no raw mode without a crate:
+        p or space  play / pause
+        n or enter  next frame
+        b           previous frame
+        + / -       faster / slower
+        q           quit
+
+    Or exported to an animated GIF, with a hand written LZW encoder.
+*/
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use ndarray::Array2;
+
+use crate::image::{Image, Palette};
+use crate::render::{Backend, Glyph, Overlay, Render, Rgb};
+
+pub const DEFAULT_MAX_FRAMES: usize = 200;
+// in hundredths of a second
+pub const DEFAULT_GIF_DELAY: u16 = 5;
+
+// A snapshot of a grid state.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    glyphs: Array2<Glyph>,
+    overlay: Overlay,
+}
+
+impl Frame {
+    pub fn capture<R: Render + ?Sized>(state: &R, overlay: Overlay) -> Frame {
+        Frame {
+            glyphs: Array2::from_shape_fn(state.dim(), |(row, col)| state.glyph(row, col)),
+            overlay,
+        }
+    }
+
+    pub fn overlay(&self) -> &Overlay {
+        &self.overlay
+    }
+}
+
+impl Render for Frame {
+    fn dim(&self) -> (usize, usize) {
+        self.glyphs.dim()
+    }
+
+    fn glyph(&self, row: usize, col: usize) -> Glyph {
+        self.glyphs[(row, col)]
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Animation {
+    pub frames: Vec<Frame>,
+}
+
+#[derive(Debug)]
+pub struct Recorder {
+    max_frames: usize,
+    every: usize,
+    nb_steps: usize,
+    frames: Vec<Frame>,
+}
+
+impl Recorder {
+    pub fn new(max_frames: usize) -> Recorder {
+        Recorder {
+            max_frames: max_frames.max(2),
+            every: 1,
+            nb_steps: 0,
+            frames: Vec::new(),
+        }
+    }
+
+    // The capture is only called for the steps that are kept.
+    pub fn step<F: FnOnce() -> Frame>(&mut self, capture: F) {
+        if self.nb_steps.is_multiple_of(self.every) {
+            self.frames.push(capture());
+            if self.frames.len() > self.max_frames {
+                self.frames = self.frames.drain(..).step_by(2).collect();
+                self.every *= 2;
+            }
+        }
+        self.nb_steps += 1;
+    }
+
+    // The last frame is always kept, so the animation ends on the result.
+    pub fn finish(mut self, last: Frame) -> Animation {
+        self.frames.push(last);
+        Animation {
+            frames: self.frames,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Command {
+    TogglePause,
+    Next,
+    Previous,
+    Faster,
+    Slower,
+    Quit,
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Option<Command> {
+        match line.trim_end_matches(['\r', '\n']) {
+            "p" | " " => Some(Command::TogglePause),
+            "n" | "" => Some(Command::Next),
+            "b" => Some(Command::Previous),
+            "+" => Some(Command::Faster),
+            "-" => Some(Command::Slower),
+            "q" => Some(Command::Quit),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Player {
+    pub frame: usize,
+    pub nb_frames: usize,
+    pub playing: bool,
+    pub delay: Duration,
+}
+
+impl Player {
+    pub fn new(nb_frames: usize) -> Player {
+        Player {
+            frame: 0,
+            nb_frames,
+            playing: true,
+            delay: Duration::from_millis(100),
+        }
+    }
+
+    // Returns false when the player should stop.
+    pub fn apply(&mut self, command: Command) -> bool {
+        match command {
+            Command::TogglePause => self.playing = !self.playing,
+            Command::Next => {
+                self.playing = false;
+                self.frame = (self.frame + 1).min(self.nb_frames.saturating_sub(1));
+            }
+            Command::Previous => {
+                self.playing = false;
+                self.frame = self.frame.saturating_sub(1);
+            }
+            Command::Faster => self.delay = (self.delay / 2).max(Duration::from_millis(10)),
+            Command::Slower => self.delay = (self.delay * 2).min(Duration::from_secs(5)),
+            Command::Quit => return false,
+        }
+        true
+    }
+
+    // Moves forward when playing, and pauses on the last frame.
+    pub fn tick(&mut self) {
+        if self.playing {
+            if self.frame + 1 < self.nb_frames {
+                self.frame += 1;
+            } else {
+                self.playing = false;
+            }
+        }
+    }
+
+    fn status(&self) -> String {
+        format!(
+            "frame {}/{}, {}, {} ms per frame   [p]lay/pause [n]ext [b]ack [+/-] speed [q]uit",
+            self.frame + 1,
+            self.nb_frames,
+            match self.playing {
+                true => "playing",
+                false => "paused",
+            },
+            self.delay.as_millis()
+        )
+    }
+}
+
+// Reads the commands on stdin in a thread, so the player isn't blocked.
+// There is only one thread for all the animations, or they would steal
+// the lines of each other.
+pub fn stdin_commands() -> &'static Mutex<Receiver<Command>> {
+    static COMMANDS: OnceLock<Mutex<Receiver<Command>>> = OnceLock::new();
+    COMMANDS.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                if let Some(command) = Command::parse(&line) {
+                    if sender.send(command).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        Mutex::new(receiver)
+    })
+}
+
+pub fn play<W: Write>(
+    animation: &Animation,
+    backend: Backend,
+    commands: &Receiver<Command>,
+    output: &mut W,
+) -> io::Result<()> {
+    if animation.frames.is_empty() {
+        return Ok(());
+    }
+    let mut player = Player::new(animation.frames.len());
+    let mut input_closed = false;
+
+    loop {
+        let frame = &animation.frames[player.frame];
+        // clear the screen and go to the top left corner
+        writeln!(
+            output,
+            "\x1b[2J\x1b[H{}{}",
+            frame.render_with_overlay(backend, frame.overlay()),
+            player.status()
+        )?;
+        output.flush()?;
+
+        if input_closed {
+            if !player.playing {
+                return Ok(());
+            }
+            std::thread::sleep(player.delay);
+            player.tick();
+            continue;
+        }
+
+        let timeout = match player.playing {
+            true => player.delay,
+            false => Duration::from_secs(3600),
+        };
+        match commands.recv_timeout(timeout) {
+            Ok(command) => {
+                if !player.apply(command) {
+                    return Ok(());
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => player.tick(),
+            Err(RecvTimeoutError::Disconnected) => input_closed = true,
+        }
+    }
+}
+
+// Up to 256 colours, the others get the closest one.
+fn colour_table(images: &[Image]) -> (Vec<Rgb>, HashMap<Rgb, u8>) {
+    let mut table: Vec<Rgb> = Vec::new();
+    let mut indices: HashMap<Rgb, u8> = HashMap::new();
+    let mut overflow: Vec<Rgb> = Vec::new();
+
+    for pixel in images.iter().flat_map(|image| image.pixels.iter()) {
+        if indices.contains_key(pixel) {
+            continue;
+        }
+        if table.len() < 256 {
+            indices.insert(*pixel, table.len() as u8);
+            table.push(*pixel);
+        } else if !overflow.contains(pixel) {
+            overflow.push(*pixel);
+        }
+    }
+
+    let distance = |Rgb(r1, g1, b1): Rgb, Rgb(r2, g2, b2): Rgb| {
+        let dr = r1 as i32 - r2 as i32;
+        let dg = g1 as i32 - g2 as i32;
+        let db = b1 as i32 - b2 as i32;
+        dr * dr + dg * dg + db * db
+    };
+    for pixel in overflow {
+        let closest = (0..table.len())
+            .min_by_key(|&index| distance(table[index], pixel))
+            .unwrap_or(0);
+        indices.insert(pixel, closest as u8);
+    }
+
+    (table, indices)
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    nb_bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u32) {
+        self.buffer |= (code as u32) << self.nb_bits;
+        self.nb_bits += size;
+        while self.nb_bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.nb_bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.nb_bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+// Variable width LZW, as GIF wants it.
+fn lzw_encode(indices: &[u8], min_code_size: u32) -> Vec<u8> {
+    let clear_code = 1_u16 << min_code_size;
+    let end_code = clear_code + 1;
+    let mut dictionary: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end_code + 1;
+    let mut code_size = min_code_size + 1;
+
+    let mut writer = BitWriter {
+        bytes: Vec::new(),
+        buffer: 0,
+        nb_bits: 0,
+    };
+    writer.write(clear_code, code_size);
+
+    let mut prefix: Option<u16> = None;
+    for &index in indices {
+        let Some(current) = prefix else {
+            prefix = Some(index as u16);
+            continue;
+        };
+        if let Some(&code) = dictionary.get(&(current, index)) {
+            prefix = Some(code);
+            continue;
+        }
+
+        writer.write(current, code_size);
+        if next_code < 4096 {
+            dictionary.insert((current, index), next_code);
+            next_code += 1;
+            // the decoder is one code late, hence the > instead of >=
+            if next_code > (1 << code_size) && code_size < 12 {
+                code_size += 1;
+            }
+        } else {
+            writer.write(clear_code, code_size);
+            dictionary.clear();
+            next_code = end_code + 1;
+            code_size = min_code_size + 1;
+        }
+        prefix = Some(index as u16);
+    }
+
+    if let Some(current) = prefix {
+        writer.write(current, code_size);
+    }
+    writer.write(end_code, code_size);
+    writer.finish()
+}
+
+impl Animation {
+    // delay is in hundredths of a second, the GIF unit.
+    pub fn to_gif(&self, palette: &Palette, scale: usize, delay: u16) -> Result<Vec<u8>, String> {
+        let images = self
+            .frames
+            .iter()
+            .map(|frame| Image::from_render(frame, frame.overlay(), palette, scale))
+            .collect::<Vec<_>>();
+        let (width, height) = images
+            .first()
+            .map_or((0, 0), |image| (image.width, image.height));
+        let (mut table, indices) = colour_table(&images);
+
+        // the table size is a power of two, at least 4 for the LZW code size
+        let mut table_bits = 2_u32;
+        while (1 << table_bits) < table.len() {
+            table_bits += 1;
+        }
+        table.resize(1 << table_bits, Rgb(0, 0, 0));
+
+        // the sizes are on 16 bits in a GIF
+        let dimension = |size: usize| {
+            u16::try_from(size)
+                .map(u16::to_le_bytes)
+                .map_err(|_| format!("The animation is too big for a GIF: {} pixels", size))
+        };
+
+        let mut output = b"GIF89a".to_vec();
+        output.extend(dimension(width)?);
+        output.extend(dimension(height)?);
+        // global colour table, 8 bits per channel, and its size
+        output.push(0xf0 | (table_bits as u8 - 1));
+        output.extend([0, 0]);
+        output.extend(table.iter().flat_map(|Rgb(r, g, b)| [*r, *g, *b]));
+
+        // loop forever
+        output.extend(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+        for image in images.iter() {
+            // graphic control extension, for the delay
+            output.extend([0x21, 0xf9, 0x04, 0x00]);
+            output.extend(delay.to_le_bytes());
+            output.extend([0x00, 0x00]);
+
+            // image descriptor, the whole canvas without local colour table
+            output.push(0x2c);
+            output.extend([0, 0, 0, 0]);
+            output.extend(dimension(image.width)?);
+            output.extend(dimension(image.height)?);
+            output.push(0);
+
+            let pixels = image
+                .pixels
+                .iter()
+                .map(|pixel| indices[pixel])
+                .collect::<Vec<_>>();
+            output.push(table_bits as u8);
+            for block in lzw_encode(&pixels, table_bits).chunks(255) {
+                output.push(block.len() as u8);
+                output.extend(block);
+            }
+            output.push(0);
+        }
+
+        output.push(0x3b);
+        Ok(output)
+    }
+
+    pub fn save_gif(
+        &self,
+        path: &Path,
+        palette: &Palette,
+        scale: usize,
+        delay: u16,
+    ) -> Result<(), String> {
+        let gif = self.to_gif(palette, scale, delay)?;
+        std::fs::write(path, gif).map_err(|error| error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ndarray::array;
+
+    // The decoder from the GIF specification, to check the encoder.
+    fn lzw_decode(bytes: &[u8], min_code_size: u32) -> Vec<u8> {
+        let clear_code = 1_usize << min_code_size;
+        let end_code = clear_code + 1;
+        let mut dictionary: Vec<Vec<u8>> = Vec::new();
+        let mut code_size = min_code_size + 1;
+        let mut previous: Option<usize> = None;
+        let mut output = Vec::new();
+        let (mut buffer, mut nb_bits, mut position) = (0_u32, 0_u32, 0_usize);
+
+        loop {
+            while nb_bits < code_size {
+                buffer |= (bytes[position] as u32) << nb_bits;
+                position += 1;
+                nb_bits += 8;
+            }
+            let code = (buffer & ((1 << code_size) - 1)) as usize;
+            buffer >>= code_size;
+            nb_bits -= code_size;
+
+            if code == clear_code {
+                dictionary = (0..clear_code).map(|index| vec![index as u8]).collect();
+                dictionary.push(Vec::new());
+                dictionary.push(Vec::new());
+                code_size = min_code_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == end_code {
+                return output;
+            }
+            let entry = match (dictionary.get(code), previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => {
+                    let mut entry = dictionary[previous].clone();
+                    entry.push(dictionary[previous][0]);
+                    entry
+                }
+                (None, None) => panic!("Invalid code"),
+            };
+            if let Some(previous) = previous {
+                if dictionary.len() < 4096 {
+                    let mut new_entry = dictionary[previous].clone();
+                    new_entry.push(entry[0]);
+                    dictionary.push(new_entry);
+                    if dictionary.len() == (1 << code_size) && code_size < 12 {
+                        code_size += 1;
+                    }
+                }
+            }
+            output.extend(&entry);
+            previous = Some(code);
+        }
+    }
+
+    #[test]
+    fn test_lzw_round_trip() {
+        let simple = vec![0, 1, 1, 1, 2, 3, 0, 0, 0, 0, 0, 1];
+        assert_eq!(lzw_decode(&lzw_encode(&simple, 2), 2), simple);
+
+        // long enough to fill the dictionary and clear it
+        let mut state = 12345_u32;
+        let noisy = (0..100_000)
+            .map(|_| {
+                state = state.wrapping_mul(1103515245).wrapping_add(12345);
+                ((state >> 16) % 7) as u8
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(lzw_decode(&lzw_encode(&noisy, 3), 3), noisy);
+
+        let flat = vec![5_u8; 50_000];
+        assert_eq!(lzw_decode(&lzw_encode(&flat, 8), 8), flat);
+    }
+
+    #[test]
+    fn test_recorder_keeps_max_frames() {
+        let mut recorder = Recorder::new(4);
+        for step in 0..20 {
+            recorder.step(|| Frame::capture(&array![[char::from(b'a' + step)]], Overlay::new()));
+        }
+        let animation = recorder.finish(Frame::capture(&array![['!']], Overlay::new()));
+
+        let symbols = animation
+            .frames
+            .iter()
+            .map(|frame| frame.glyph(0, 0).ascii)
+            .collect::<String>();
+        // every 8 steps, and the last frame
+        assert_eq!(symbols, "aiq!");
+    }
+
+    #[test]
+    fn test_player() {
+        let mut player = Player::new(3);
+        player.tick();
+        player.tick();
+        assert_eq!(player.frame, 2);
+        // pauses at the end
+        player.tick();
+        assert_eq!((player.frame, player.playing), (2, false));
+
+        assert!(player.apply(Command::Previous));
+        assert_eq!(player.frame, 1);
+        assert!(player.apply(Command::Next));
+        assert!(player.apply(Command::Next));
+        assert_eq!(player.frame, 2);
+        assert!(player.apply(Command::Faster));
+        assert_eq!(player.delay, Duration::from_millis(50));
+        assert!(player.apply(Command::TogglePause));
+        assert!(player.playing);
+        assert!(!player.apply(Command::Quit));
+
+        assert_eq!(Command::parse("+\n"), Some(Command::Faster));
+        assert_eq!(Command::parse(""), Some(Command::Next));
+        assert_eq!(Command::parse("x"), None);
+    }
+
+    #[test]
+    fn test_play_without_input() {
+        let mut recorder = Recorder::new(10);
+        recorder.step(|| Frame::capture(&array![['a']], Overlay::new()));
+        let animation = recorder.finish(Frame::capture(&array![['b']], Overlay::new()));
+
+        let (sender, receiver) = mpsc::channel();
+        sender.send(Command::Faster).unwrap();
+        sender.send(Command::Faster).unwrap();
+        sender.send(Command::Faster).unwrap();
+        drop(sender);
+
+        let mut output = Vec::new();
+        play(&animation, Backend::Ascii, &receiver, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("a\nframe 1/2"));
+        assert!(output.ends_with("b\nframe 2/2, paused, 12 ms per frame   [p]lay/pause [n]ext [b]ack [+/-] speed [q]uit\n"));
+    }
+
+    #[test]
+    fn test_gif() {
+        let mut recorder = Recorder::new(10);
+        recorder.step(|| Frame::capture(&array![[true, false]], Overlay::new()));
+        let animation = recorder.finish(Frame::capture(&array![[false, true]], Overlay::new()));
+
+        let palette = Palette::new(Rgb(0, 0, 0)).with('#', Rgb(255, 255, 255));
+        let gif = animation.to_gif(&palette, 2, 10).unwrap();
+
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(&gif[6..10], [4, 0, 2, 0]);
+        // 2 colours, in a table of 4
+        assert_eq!(gif[10], 0xf1);
+        assert_eq!(&gif[13..19], [255, 255, 255, 0, 0, 0]);
+        // the delay of the first frame, and its image descriptor
+        assert_eq!(&gif[44..50], [0x21, 0xf9, 0x04, 0x00, 10, 0]);
+        assert_eq!(gif[52], 0x2c);
+        assert_eq!(gif.last(), Some(&0x3b));
+
+        // the width would wrap around on 16 bits
+        let wide = Recorder::new(1).finish(Frame::capture(
+            &Array2::from_elem((1, 70_000), false),
+            Overlay::new(),
+        ));
+        assert!(wide.to_gif(&palette, 1, 10).is_err());
+    }
+}
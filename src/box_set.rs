@@ -0,0 +1,277 @@
+/*
+    Sets of axis-aligned integer boxes in D dimensions.
+
+    The boxes of a set are kept pairwise disjoint: inserting a box only adds
+    the parts that are not covered yet, and removing a box splits every box it
+    cuts into at most 2*D slabs. Because of that, the volume of the set is
+    just the sum of the volumes of its boxes, even if the boxes that were
+    inserted overlapped.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HyperRectangle<const D: usize> {
+    // Both bounds are inclusive.
+    pub min: [i64; D],
+    pub max: [i64; D],
+}
+
+impl<const D: usize> HyperRectangle<D> {
+    pub fn new(min: [i64; D], max: [i64; D]) -> Self {
+        HyperRectangle { min, max }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (0..D).any(|axis| self.min[axis] > self.max[axis])
+    }
+
+    pub fn volume(&self) -> u128 {
+        if self.is_empty() {
+            return 0;
+        }
+        (0..D)
+            .map(|axis| (self.max[axis] - self.min[axis] + 1) as u128)
+            .product()
+    }
+
+    pub fn contains_point(&self, point: &[i64; D]) -> bool {
+        (0..D).all(|axis| self.min[axis] <= point[axis] && point[axis] <= self.max[axis])
+    }
+
+    pub fn contains(&self, other: &HyperRectangle<D>) -> bool {
+        other.is_empty()
+            || (0..D)
+                .all(|axis| self.min[axis] <= other.min[axis] && other.max[axis] <= self.max[axis])
+    }
+
+    pub fn intersection(&self, other: &HyperRectangle<D>) -> Option<HyperRectangle<D>> {
+        let mut min = self.min;
+        let mut max = self.max;
+        for axis in 0..D {
+            min[axis] = min[axis].max(other.min[axis]);
+            max[axis] = max[axis].min(other.max[axis]);
+        }
+        let intersection = HyperRectangle { min, max };
+        match intersection.is_empty() {
+            true => None,
+            false => Some(intersection),
+        }
+    }
+
+    // Clamp the box along one axis, None if nothing is left.
+    pub fn restrict(&self, axis: usize, min: i64, max: i64) -> Option<HyperRectangle<D>> {
+        let mut restricted = *self;
+        restricted.min[axis] = restricted.min[axis].max(min);
+        restricted.max[axis] = restricted.max[axis].min(max);
+        match restricted.is_empty() {
+            true => None,
+            false => Some(restricted),
+        }
+    }
+
+    // The parts of self that are not in other, as disjoint boxes.
+    //
+    // We peel slabs off the box one axis at a time: below and above the
+    // intersection on the first axis, then on the second axis within what
+    // remains, and so on. What remains at the end is the intersection itself.
+    pub fn subtract(&self, other: &HyperRectangle<D>) -> Vec<HyperRectangle<D>> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        let intersection = match self.intersection(other) {
+            Some(intersection) => intersection,
+            None => return vec![*self],
+        };
+
+        let mut pieces = Vec::with_capacity(2 * D);
+        let mut remaining = *self;
+        for axis in 0..D {
+            if remaining.min[axis] < intersection.min[axis] {
+                let mut below = remaining;
+                below.max[axis] = intersection.min[axis] - 1;
+                pieces.push(below);
+            }
+            if intersection.max[axis] < remaining.max[axis] {
+                let mut above = remaining;
+                above.min[axis] = intersection.max[axis] + 1;
+                pieces.push(above);
+            }
+            remaining.min[axis] = intersection.min[axis];
+            remaining.max[axis] = intersection.max[axis];
+        }
+        pieces
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BoxSet<const D: usize> {
+    // Invariant: the boxes are non empty and pairwise disjoint.
+    boxes: Vec<HyperRectangle<D>>,
+}
+
+impl<const D: usize> BoxSet<D> {
+    pub fn new() -> Self {
+        BoxSet { boxes: Vec::new() }
+    }
+
+    pub fn boxes(&self) -> &[HyperRectangle<D>] {
+        &self.boxes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.boxes.is_empty()
+    }
+
+    pub fn insert(&mut self, new_box: HyperRectangle<D>) {
+        let mut pieces = vec![new_box];
+        for existing in self.boxes.iter() {
+            pieces = pieces
+                .iter()
+                .flat_map(|piece| piece.subtract(existing))
+                .collect();
+            if pieces.is_empty() {
+                return;
+            }
+        }
+        self.boxes
+            .extend(pieces.into_iter().filter(|piece| !piece.is_empty()));
+    }
+
+    pub fn remove(&mut self, removed_box: &HyperRectangle<D>) {
+        self.boxes = self
+            .boxes
+            .iter()
+            .flat_map(|existing| existing.subtract(removed_box))
+            .collect();
+    }
+
+    pub fn union(&self, other: &BoxSet<D>) -> BoxSet<D> {
+        let mut union = self.clone();
+        for other_box in other.boxes.iter() {
+            union.insert(*other_box);
+        }
+        union
+    }
+
+    pub fn subtract(&self, other: &BoxSet<D>) -> BoxSet<D> {
+        let mut difference = self.clone();
+        for other_box in other.boxes.iter() {
+            difference.remove(other_box);
+        }
+        difference
+    }
+
+    pub fn intersection(&self, other: &BoxSet<D>) -> BoxSet<D> {
+        // Both sides are disjoint, so the pairwise intersections are too.
+        BoxSet {
+            boxes: self
+                .boxes
+                .iter()
+                .flat_map(|a| other.boxes.iter().filter_map(|b| a.intersection(b)))
+                .collect(),
+        }
+    }
+
+    pub fn contains_point(&self, point: &[i64; D]) -> bool {
+        self.boxes.iter().any(|b| b.contains_point(point))
+    }
+
+    pub fn volume(&self) -> u128 {
+        self.boxes.iter().map(|b| b.volume()).sum()
+    }
+}
+
+impl<const D: usize> FromIterator<HyperRectangle<D>> for BoxSet<D> {
+    fn from_iter<I: IntoIterator<Item = HyperRectangle<D>>>(iter: I) -> Self {
+        let mut set = BoxSet::new();
+        for new_box in iter {
+            set.insert(new_box);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force_volume<const D: usize>(set: &BoxSet<D>, bounds: &HyperRectangle<D>) -> u128 {
+        // Only for tiny boxes, counts the points one by one.
+        let mut count = 0;
+        let mut point = bounds.min;
+        loop {
+            if set.contains_point(&point) {
+                count += 1;
+            }
+            let mut axis = 0;
+            loop {
+                if axis == D {
+                    return count;
+                }
+                if point[axis] < bounds.max[axis] {
+                    point[axis] += 1;
+                    break;
+                }
+                point[axis] = bounds.min[axis];
+                axis += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn test_hyper_rectangle_volume() {
+        assert_eq!(HyperRectangle::new([1, 1], [3, 4]).volume(), 12);
+        assert_eq!(HyperRectangle::new([1, 5], [3, 4]).volume(), 0);
+        assert_eq!(HyperRectangle::new([-2], [2]).volume(), 5);
+    }
+
+    #[test]
+    fn test_hyper_rectangle_subtract() {
+        let a = HyperRectangle::new([0, 0], [9, 9]);
+        let b = HyperRectangle::new([3, 3], [5, 5]);
+        let pieces = a.subtract(&b);
+        assert_eq!(pieces.len(), 4);
+        assert_eq!(pieces.iter().map(|p| p.volume()).sum::<u128>(), 100 - 9);
+        assert!(pieces.iter().all(|p| p.intersection(&b).is_none()));
+
+        // disjoint boxes are left untouched
+        let c = HyperRectangle::new([20, 20], [30, 30]);
+        assert_eq!(a.subtract(&c), vec![a]);
+
+        // fully covered boxes disappear
+        assert!(b.subtract(&a).is_empty());
+    }
+
+    #[test]
+    fn test_box_set_overlapping_union() {
+        let mut set = BoxSet::new();
+        set.insert(HyperRectangle::new([0, 0, 0], [3, 3, 3]));
+        set.insert(HyperRectangle::new([2, 2, 2], [5, 5, 5]));
+        set.insert(HyperRectangle::new([1, 1, 1], [2, 2, 2]));
+        // 64 + 64 - 8
+        assert_eq!(set.volume(), 120);
+        assert_eq!(
+            set.volume(),
+            brute_force_volume(&set, &HyperRectangle::new([0, 0, 0], [5, 5, 5]))
+        );
+    }
+
+    #[test]
+    fn test_box_set_operations() {
+        let a: BoxSet<2> = [
+            HyperRectangle::new([0, 0], [4, 4]),
+            HyperRectangle::new([3, 3], [7, 7]),
+        ]
+        .into_iter()
+        .collect();
+        let b: BoxSet<2> = [HyperRectangle::new([2, 2], [5, 5])].into_iter().collect();
+
+        assert_eq!(a.volume(), 25 + 25 - 4);
+        assert_eq!(a.intersection(&b).volume(), 16 - 2);
+        assert_eq!(a.subtract(&b).volume(), 46 - 14);
+        assert_eq!(a.union(&b).volume(), 46 + 2);
+
+        let mut c = a.clone();
+        c.remove(&HyperRectangle::new([0, 0], [7, 7]));
+        assert!(c.is_empty());
+    }
+}
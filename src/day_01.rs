@@ -1,6 +1,11 @@
-fn parse_input_data(input: &str) -> Vec<Vec<char>> {
-    return input.lines().map(|line| line.chars().collect()).collect();
-}
+use std::cmp::Reverse;
+use std::collections::{HashMap, VecDeque};
+use std::io::{ErrorKind, Read};
+
+use rayon::prelude::*;
+
+use crate::number_theory::checked_sum;
+use crate::render::Backend;
 
 fn two_digits_to_number(first_digit: char, last_digit: char) -> i64 {
     let number = format!("{}{}", first_digit, last_digit)
@@ -9,104 +14,531 @@ fn two_digits_to_number(first_digit: char, last_digit: char) -> i64 {
     return number;
 }
 
-pub fn day_1_part_1(data: &str) -> i64 {
-    let lines = parse_input_data(data);
+pub fn day_1_part_1(data: &str) -> Result<i64, String> {
+    Lexicon::digits().calibrate(data)
+}
 
-    let numbers = lines.iter().map(|line| {
-        let first_digit = line
-            .iter()
-            .find(|&c| c.is_digit(10))
-            .expect("The dataset doesn't contain any numbers");
-        let last_digit = line
-            .iter()
-            .rfind(|&c| c.is_digit(10))
-            .expect("The dataset doesn't contain any numbers");
+/*
+    Part 2 used to look for each word twice per line. The lexicon is now
+    an Aho-Corasick automaton: a trie of the words, with for each node a
+    link to the longest suffix that is also in the trie, so the line is
+    read once and every word is found, even when they overlap like in
+    "eightwo".
 
-        return two_digits_to_number(*first_digit, *last_digit);
-    });
+    The words can be anything, in any language, and stand for several
+    digits: the line reads as if each word was replaced by its digits.
+*/
 
-    return numbers.sum();
+#[derive(Debug, Clone, Default)]
+struct Node {
+    children: HashMap<char, usize>,
+    // the longest proper suffix of this node that is also a node
+    fail: usize,
+    // the words ending here, including the ones from the fail links
+    words: Vec<usize>,
 }
 
-fn find_first_substring(data: &Vec<char>, search: &Vec<char>) -> Option<usize> {
-    let result = data
-        .windows(search.len())
-        .position(|window| window == search);
-    return result;
+#[derive(Debug, Clone)]
+pub struct Lexicon {
+    words: Vec<(String, String)>,
+    nodes: Vec<Node>,
 }
 
-fn find_last_substring(data: &Vec<char>, search: &Vec<char>) -> Option<usize> {
-    let result = data
-        .windows(search.len())
-        .rposition(|window| window == search);
-    return result;
+// Where a word was found in a line, in bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub start: usize,
+    pub end: usize,
+    pub word: String,
+    pub digits: String,
 }
 
-pub fn day_1_part_2(data: &str) -> i64 {
-    let lines = parse_input_data(data);
+impl Lexicon {
+    // The words and the digits they stand for.
+    pub fn new(words: &[(&str, &str)]) -> Result<Lexicon, String> {
+        let mut lexicon = Lexicon {
+            words: Vec::new(),
+            nodes: vec![Node::default()],
+        };
+        for &(word, digits) in words {
+            if word.is_empty() {
+                return Err("A word of the lexicon is empty".to_string());
+            }
+            if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+                return Err(format!("{} doesn't stand for digits: {}", word, digits));
+            }
+            lexicon.insert(word, digits);
+        }
+        lexicon.link();
+        Ok(lexicon)
+    }
 
-    let full_text_data: Vec<(Vec<char>, char)> = vec![
-        (vec!['o', 'n', 'e'], '1'),
-        (vec!['t', 'w', 'o'], '2'),
-        (vec!['t', 'h', 'r', 'e', 'e'], '3'),
-        (vec!['f', 'o', 'u', 'r'], '4'),
-        (vec!['f', 'i', 'v', 'e'], '5'),
-        (vec!['s', 'i', 'x'], '6'),
-        (vec!['s', 'e', 'v', 'e', 'n'], '7'),
-        (vec!['e', 'i', 'g', 'h', 't'], '8'),
-        (vec!['n', 'i', 'n', 'e'], '9'),
-    ];
+    pub fn digits() -> Lexicon {
+        Lexicon::new(&DIGITS).expect("Invalid digits lexicon")
+    }
 
-    let numbers = lines.iter().map(|line| {
-        let (mut index_first_digit, first_digit) = line
+    pub fn english() -> Lexicon {
+        let words = DIGITS
             .iter()
-            .enumerate()
-            .find(|(_, c)| c.is_digit(10))
-            .unwrap_or((usize::MAX, &'0'));
+            .chain(ENGLISH_WORDS.iter())
+            .copied()
+            .collect::<Vec<_>>();
+        Lexicon::new(&words).expect("Invalid english lexicon")
+    }
+
+    fn insert(&mut self, word: &str, digits: &str) {
+        let mut node = 0;
+        for c in word.chars() {
+            node = match self.nodes[node].children.get(&c) {
+                Some(&child) => child,
+                None => {
+                    self.nodes.push(Node::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.insert(c, child);
+                    child
+                }
+            };
+        }
+        self.nodes[node].words.push(self.words.len());
+        self.words.push((word.to_string(), digits.to_string()));
+    }
+
+    // Breadth first, so the fail link of a node is ready before its children.
+    fn link(&mut self) {
+        let mut queue = VecDeque::from([0]);
+        while let Some(node) = queue.pop_front() {
+            let children = self.nodes[node]
+                .children
+                .iter()
+                .map(|(&c, &child)| (c, child))
+                .collect::<Vec<_>>();
+            for (c, child) in children {
+                let fail = match node {
+                    0 => 0,
+                    _ => self.next(self.nodes[node].fail, c),
+                };
+                self.nodes[child].fail = fail;
+                let inherited = self.nodes[fail].words.clone();
+                self.nodes[child].words.extend(inherited);
+                queue.push_back(child);
+            }
+        }
+    }
 
-        let (mut index_last_digit, last_digit) = line
+    // Follows the fail links until a node can read c, the root reads everything.
+    fn next(&self, mut node: usize, c: char) -> usize {
+        loop {
+            if let Some(&child) = self.nodes[node].children.get(&c) {
+                return child;
+            }
+            if node == 0 {
+                return 0;
+            }
+            node = self.nodes[node].fail;
+        }
+    }
+
+    // Every word in the line, overlapping or not, sorted by start.
+    pub fn matches(&self, line: &str) -> Vec<Match> {
+        let mut matches = Vec::new();
+        let mut node = 0;
+        for (index, c) in line.char_indices() {
+            node = self.next(node, c);
+            let end = index + c.len_utf8();
+            for &word in self.nodes[node].words.iter() {
+                let (word, digits) = &self.words[word];
+                matches.push(Match {
+                    start: end - word.len(),
+                    end,
+                    word: word.clone(),
+                    digits: digits.clone(),
+                });
+            }
+        }
+        matches.sort_by_key(|found| (found.start, found.end));
+        matches
+    }
+
+    // The first digit of the first word and the last digit of the last word.
+    // Two words starting at the same place, like "seven" and "seventeen",
+    // are read as the longest one.
+    pub fn calibration_value(&self, line: &str) -> Option<i64> {
+        let matches = self.matches(line);
+        let first = matches
+            .iter()
+            .min_by_key(|found| (found.start, Reverse(found.end)))?;
+        let last = matches
             .iter()
-            .enumerate()
-            .rfind(|(_, c)| c.is_digit(10))
-            .unwrap_or((0, &'0'));
-
-        let mut first_digit = *first_digit;
-        let mut last_digit = *last_digit;
-
-        for (search, replacement) in &full_text_data {
-            let search_first = find_first_substring(line, &search);
-            match search_first {
-                Some(index) => {
-                    if index < index_first_digit {
-                        index_first_digit = index;
-                        first_digit = *replacement;
+            .max_by_key(|found| (found.start, found.end))?;
+        Some(two_digits_to_number(
+            first.digits.chars().next()?,
+            last.digits.chars().last()?,
+        ))
+    }
+
+    pub fn calibrate(&self, data: &str) -> Result<i64, String> {
+        checked_sum(
+            data.lines()
+                .map(|line| {
+                    self.calibration_value(line)
+                        .ok_or("The dataset doesn't contain any numbers")
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        )
+    }
+}
+
+const DIGITS: [(&str, &str); 10] = [
+    ("0", "0"),
+    ("1", "1"),
+    ("2", "2"),
+    ("3", "3"),
+    ("4", "4"),
+    ("5", "5"),
+    ("6", "6"),
+    ("7", "7"),
+    ("8", "8"),
+    ("9", "9"),
+];
+
+const ENGLISH_WORDS: [(&str, &str); 9] = [
+    ("one", "1"),
+    ("two", "2"),
+    ("three", "3"),
+    ("four", "4"),
+    ("five", "5"),
+    ("six", "6"),
+    ("seven", "7"),
+    ("eight", "8"),
+    ("nine", "9"),
+];
+
+pub fn day_1_part_2(data: &str) -> Result<i64, String> {
+    Lexicon::english().calibrate(data)
+}
+
+// The words found on each line, to see why a line gives its value.
+pub fn day_1_matches(data: &str, lexicon: &Lexicon) -> Vec<Vec<Match>> {
+    data.lines().map(|line| lexicon.matches(line)).collect()
+}
+
+// Each line with its part 2 words and their positions, then its value.
+pub fn day_1_show(data: &str, _backend: Backend) -> String {
+    let english = Lexicon::english();
+    data.lines()
+        .zip(day_1_matches(data, &english))
+        .map(|(line, matches)| {
+            let words = matches
+                .iter()
+                .map(|found| format!("{}@{}", found.word, found.start))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let value = english
+                .calibration_value(line)
+                .map_or("none".to_string(), |value| value.to_string());
+            format!("{}: {} => {}\n", line, words, value)
+        })
+        .collect()
+}
+
+/*
+    Streaming version, for the generated documents that don't fit in
+    memory as strings. The input is read in blocks that end on a new
+    line, and the blocks are decoded in parallel.
+
+    The lexicon becomes a table of transitions on bytes, the words are
+    UTF-8 so they can't match in the middle of a character. When the
+    lexicon is only the digits, the digits are found 32 bytes at a time
+    with masks, a loop the compiler turns into SIMD instructions.
+*/
+
+const BLOCK_SIZE: usize = 1 << 20;
+const LANES: usize = 32;
+
+// The word found at a state that starts the earliest, and the one that
+// starts the latest, with the digit that counts for each.
+#[derive(Debug, Clone, Copy, Default)]
+struct StateWords {
+    longest: Option<(usize, u8)>,
+    shortest: Option<(usize, u8)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ByteDecoder {
+    transitions: Vec<[u32; 256]>,
+    words: Vec<StateWords>,
+    only_digits: bool,
+}
+
+// One bit per byte of the chunk that matches, LANES at a time.
+#[inline]
+fn mask<F: Fn(u8) -> bool>(chunk: &[u8], matches: F) -> u32 {
+    let mut mask = 0_u32;
+    for (index, &byte) in chunk.iter().enumerate().take(LANES) {
+        mask |= (matches(byte) as u32) << index;
+    }
+    mask
+}
+
+#[inline]
+fn is_digit(byte: u8) -> bool {
+    byte.wrapping_sub(b'0') < 10
+}
+
+fn first_digit(line: &[u8]) -> Option<u8> {
+    let mut chunks = line.chunks_exact(LANES);
+    for (index, chunk) in chunks.by_ref().enumerate() {
+        let digits = mask(chunk, is_digit);
+        if digits != 0 {
+            return Some(line[index * LANES + digits.trailing_zeros() as usize]);
+        }
+    }
+    chunks
+        .remainder()
+        .iter()
+        .copied()
+        .find(|&byte| is_digit(byte))
+}
+
+fn last_digit(line: &[u8]) -> Option<u8> {
+    let mut chunks = line.rchunks_exact(LANES);
+    for (index, chunk) in chunks.by_ref().enumerate() {
+        let digits = mask(chunk, is_digit);
+        if digits != 0 {
+            let offset = line.len() - (index + 1) * LANES;
+            return Some(line[offset + LANES - 1 - digits.leading_zeros() as usize]);
+        }
+    }
+    chunks
+        .remainder()
+        .iter()
+        .copied()
+        .rfind(|&byte| is_digit(byte))
+}
+
+fn find_new_line(bytes: &[u8]) -> Option<usize> {
+    let mut chunks = bytes.chunks_exact(LANES);
+    for (index, chunk) in chunks.by_ref().enumerate() {
+        let new_lines = mask(chunk, |byte| byte == b'\n');
+        if new_lines != 0 {
+            return Some(index * LANES + new_lines.trailing_zeros() as usize);
+        }
+    }
+    let offset = bytes.len() - chunks.remainder().len();
+    chunks
+        .remainder()
+        .iter()
+        .position(|&byte| byte == b'\n')
+        .map(|index| offset + index)
+}
+
+impl ByteDecoder {
+    pub fn new(lexicon: &Lexicon) -> ByteDecoder {
+        // the same trie and fail links, on the bytes of the words
+        let mut children: Vec<[Option<u32>; 256]> = vec![[None; 256]];
+        let mut ends: Vec<Vec<usize>> = vec![Vec::new()];
+        for (index, (word, _)) in lexicon.words.iter().enumerate() {
+            let mut node = 0;
+            for &byte in word.as_bytes() {
+                node = match children[node][byte as usize] {
+                    Some(child) => child as usize,
+                    None => {
+                        children.push([None; 256]);
+                        ends.push(Vec::new());
+                        let child = children.len() - 1;
+                        children[node][byte as usize] = Some(child as u32);
+                        child
                     }
+                };
+            }
+            ends[node].push(index);
+        }
+
+        // breadth first, missing transitions go where the fail link goes
+        let mut transitions = vec![[0_u32; 256]; children.len()];
+        let mut fails = vec![0_usize; children.len()];
+        let mut queue = VecDeque::from([0]);
+        while let Some(node) = queue.pop_front() {
+            let inherited = ends[fails[node]].clone();
+            if node != 0 {
+                ends[node].extend(inherited);
+            }
+            for byte in 0..256 {
+                match children[node][byte] {
+                    Some(child) => {
+                        let child = child as usize;
+                        fails[child] = match node {
+                            0 => 0,
+                            _ => transitions[fails[node]][byte] as usize,
+                        };
+                        transitions[node][byte] = child as u32;
+                        queue.push_back(child);
+                    }
+                    None => {
+                        transitions[node][byte] = match node {
+                            0 => 0,
+                            _ => transitions[fails[node]][byte],
+                        }
+                    }
+                }
+            }
+        }
+
+        let words = ends
+            .iter()
+            .map(|ends| {
+                let word = |index: &usize| &lexicon.words[*index];
+                let longest = ends.iter().map(word).max_by_key(|(word, _)| word.len());
+                let shortest = ends.iter().map(word).min_by_key(|(word, _)| word.len());
+                StateWords {
+                    longest: longest.map(|(word, digits)| (word.len(), digits.as_bytes()[0])),
+                    shortest: shortest
+                        .map(|(word, digits)| (word.len(), digits.as_bytes()[digits.len() - 1])),
+                }
+            })
+            .collect();
+
+        let only_digits = lexicon.words.len() == DIGITS.len()
+            && DIGITS.iter().all(|&(word, digits)| {
+                lexicon
+                    .words
+                    .contains(&(word.to_string(), digits.to_string()))
+            });
+
+        ByteDecoder {
+            transitions,
+            words,
+            only_digits,
+        }
+    }
+
+    // Same rules as Lexicon::calibration_value, in one pass over the bytes.
+    fn calibration_value(&self, line: &[u8]) -> Option<i64> {
+        if self.only_digits {
+            let value = (first_digit(line)? - b'0') * 10 + last_digit(line)? - b'0';
+            return Some(value as i64);
+        }
+
+        // (start, end, digit) of the first and last words
+        let mut first: Option<(usize, usize, u8)> = None;
+        let mut last: Option<(usize, usize, u8)> = None;
+        let mut state = 0;
+        for (index, &byte) in line.iter().enumerate() {
+            state = self.transitions[state][byte as usize] as usize;
+            let end = index + 1;
+            let StateWords { longest, shortest } = self.words[state];
+            // the ends only grow, so a tie on the start goes to the new word
+            if let Some((length, digit)) = longest {
+                if first.is_none_or(|(start, _, _)| end - length <= start) {
+                    first = Some((end - length, end, digit));
                 }
-                None => {}
             }
+            if let Some((length, digit)) = shortest {
+                if last.is_none_or(|(start, _, _)| end - length >= start) {
+                    last = Some((end - length, end, digit));
+                }
+            }
+        }
+        let (_, _, first_digit) = first?;
+        let (_, _, last_digit) = last?;
+        Some(((first_digit - b'0') * 10 + last_digit - b'0') as i64)
+    }
 
-            let search_last = find_last_substring(line, &search);
-            match search_last {
-                Some(index) => {
-                    if index > index_last_digit {
-                        index_last_digit = index;
-                        last_digit = *replacement;
+    // A block of whole lines, the last one can miss its new line.
+    fn calibrate_block(&self, block: &[u8]) -> Result<i64, String> {
+        let mut sum = 0_i64;
+        let mut rest = block;
+        while !rest.is_empty() {
+            let (line, next) = match find_new_line(rest) {
+                Some(index) => (&rest[..index], &rest[index + 1..]),
+                None => (rest, &rest[rest.len()..]),
+            };
+            // like str::lines
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            let value = self
+                .calibration_value(line)
+                .ok_or("The dataset doesn't contain any numbers")?;
+            sum = sum.checked_add(value).ok_or("The sum overflows")?;
+            rest = next;
+        }
+        Ok(sum)
+    }
+
+    fn calibrate_blocks(&self, blocks: &[Vec<u8>]) -> Result<i64, String> {
+        let sums = blocks
+            .par_iter()
+            .map(|block| self.calibrate_block(block))
+            .collect::<Result<Vec<_>, _>>()?;
+        checked_sum(sums)
+    }
+
+    pub fn calibrate<R: Read>(&self, reader: R) -> Result<i64, String> {
+        self.calibrate_with_block_size(reader, BLOCK_SIZE)
+    }
+
+    // Reads a block per thread, cuts them after their last new line,
+    // and carries what's after to the next block.
+    fn calibrate_with_block_size<R: Read>(
+        &self,
+        mut reader: R,
+        block_size: usize,
+    ) -> Result<i64, String> {
+        let nb_blocks = rayon::current_num_threads().max(1);
+        let mut sum = 0_i64;
+        let mut carry: Vec<u8> = Vec::new();
+        let mut end_of_input = false;
+        while !end_of_input {
+            let mut blocks = Vec::with_capacity(nb_blocks);
+            while blocks.len() < nb_blocks && !end_of_input {
+                let mut block = std::mem::take(&mut carry);
+                let start = block.len();
+                block.resize(start + block_size, 0);
+                let mut filled = start;
+                while filled < block.len() {
+                    match reader.read(&mut block[filled..]) {
+                        Ok(0) => {
+                            end_of_input = true;
+                            break;
+                        }
+                        Ok(nb_bytes) => filled += nb_bytes,
+                        Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                        Err(error) => return Err(error.to_string()),
                     }
                 }
-                None => {}
+                block.truncate(filled);
+                if !end_of_input {
+                    // a line longer than the block waits for the next read
+                    match block.iter().rposition(|&byte| byte == b'\n') {
+                        Some(index) => carry = block.split_off(index + 1),
+                        None => {
+                            carry = block;
+                            continue;
+                        }
+                    }
+                }
+                blocks.push(block);
             }
+            let blocks_sum = self.calibrate_blocks(&blocks)?;
+            sum = sum.checked_add(blocks_sum).ok_or("The sum overflows")?;
         }
+        Ok(sum)
+    }
+}
 
-        return two_digits_to_number(first_digit, last_digit);
-    });
+pub fn day_1_part_1_stream<R: Read>(reader: R) -> Result<i64, String> {
+    ByteDecoder::new(&Lexicon::digits()).calibrate(reader)
+}
 
-    return numbers.sum();
+pub fn day_1_part_2_stream<R: Read>(reader: R) -> Result<i64, String> {
+    ByteDecoder::new(&Lexicon::english()).calibrate(reader)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::generate;
+
     const EXAMPLE_PART_1: &str = "1abc2
 pqr3stu8vwx
 a1b2c3d4e5f
@@ -122,12 +554,116 @@ zoneight234
 
     #[test]
     fn test_day_1_part_1() {
-        assert_eq!(day_1_part_1(EXAMPLE_PART_1), 142);
+        assert_eq!(day_1_part_1(EXAMPLE_PART_1), Ok(142));
+        assert!(day_1_part_1("abc").is_err());
     }
 
     #[test]
     fn test_day_1_part_2() {
-        assert_eq!(day_1_part_2(EXAMPLE_PART_1), 142);
-        assert_eq!(day_1_part_2(EXAMPLE_PART_2), 281);
+        assert_eq!(day_1_part_2(EXAMPLE_PART_1), Ok(142));
+        assert_eq!(day_1_part_2(EXAMPLE_PART_2), Ok(281));
+        // a single word is both the first and the last digit
+        assert_eq!(day_1_part_2("oneabc"), Ok(11));
+        assert!(day_1_part_2("abc").is_err());
+    }
+
+    #[test]
+    fn test_lexicon() {
+        let english = Lexicon::english();
+        let words = |line: &str| {
+            english
+                .matches(line)
+                .into_iter()
+                .map(|found| (found.start, found.word))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            words("eightwo"),
+            vec![(0, "eight".to_string()), (4, "two".to_string())]
+        );
+        assert_eq!(
+            words("oneight3"),
+            vec![
+                (0, "one".to_string()),
+                (2, "eight".to_string()),
+                (7, "3".to_string())
+            ]
+        );
+        assert_eq!(english.calibration_value("xtwone3four"), Some(24));
+        assert_eq!(english.calibration_value("eightwo"), Some(82));
+
+        // other languages, and words for several digits
+        let french = Lexicon::new(&[("un", "1"), ("deux", "2"), ("douze", "12")]).unwrap();
+        assert_eq!(french.calibration_value("deuxun"), Some(21));
+        assert_eq!(french.calibration_value("douze"), Some(12));
+        assert_eq!(french.calibrate("undeux\ndouze"), Ok(24));
+        let longest = Lexicon::new(&[("seven", "7"), ("seventeen", "17")]).unwrap();
+        assert_eq!(longest.calibration_value("seventeen"), Some(17));
+        // positions are in bytes
+        let greek = Lexicon::new(&[("ένα", "1"), ("δύο", "2")]).unwrap();
+        assert_eq!(greek.matches("ένα δύο")[1].start, "ένα ".len());
+        assert_eq!(greek.calibration_value("ένα δύο"), Some(12));
+
+        assert!(Lexicon::new(&[("", "1")]).is_err());
+        assert!(Lexicon::new(&[("one", "I")]).is_err());
+        assert_eq!(day_1_matches("1two\nx", &english)[0].len(), 2);
+        assert_eq!(
+            day_1_show("eightwo\nx", Backend::Ascii),
+            "eightwo: eight@0 two@4 => 82\nx:  => none\n"
+        );
+    }
+
+    #[test]
+    fn test_byte_decoder() {
+        assert_eq!(first_digit(b"ab3cd4"), Some(b'3'));
+        assert_eq!(last_digit(b"ab3cd4e"), Some(b'4'));
+        let long = [b"x".repeat(70), b"7".to_vec(), b"y".repeat(40)].concat();
+        assert_eq!(first_digit(&long), Some(b'7'));
+        assert_eq!(last_digit(&long), Some(b'7'));
+        assert_eq!(first_digit(b"abc"), None);
+        assert_eq!(
+            find_new_line(&[b"a".repeat(40), b"\n".to_vec()].concat()),
+            Some(40)
+        );
+
+        let part_1 = |input: &str| day_1_part_1_stream(input.as_bytes());
+        let part_2 = |input: &str| day_1_part_2_stream(input.as_bytes());
+        assert_eq!(part_1(EXAMPLE_PART_1), Ok(142));
+        assert_eq!(part_2(EXAMPLE_PART_2), Ok(281));
+        assert_eq!(part_2("eightwo\r\noneabc\n"), Ok(82 + 11));
+        assert_eq!(part_1(""), Ok(0));
+        assert!(part_1("1\n\n2").is_err());
+
+        let french = Lexicon::new(&[("un", "1"), ("deux", "2"), ("douze", "12")]).unwrap();
+        let decoder = ByteDecoder::new(&french);
+        assert_eq!(decoder.calibrate("undeux\ndouze".as_bytes()), Ok(24));
+        let longest = Lexicon::new(&[("seven", "7"), ("seventeen", "17")]).unwrap();
+        assert_eq!(
+            ByteDecoder::new(&longest).calibrate("seventeen".as_bytes()),
+            Ok(17)
+        );
+    }
+
+    #[test]
+    fn test_byte_decoder_same_as_parts() {
+        for seed in 0..5 {
+            let input = generate::generate(2023, 1, Some(200), seed).unwrap();
+            for (lexicon, part) in [
+                (
+                    Lexicon::digits(),
+                    day_1_part_1 as fn(&str) -> Result<i64, String>,
+                ),
+                (Lexicon::english(), day_1_part_2),
+            ] {
+                let decoder = ByteDecoder::new(&lexicon);
+                // small blocks, so the lines are cut between reads
+                for block_size in [7, 64, BLOCK_SIZE] {
+                    assert_eq!(
+                        decoder.calibrate_with_block_size(input.as_bytes(), block_size),
+                        part(&input)
+                    );
+                }
+            }
+        }
     }
 }
@@ -1,114 +1,233 @@
-#[derive(Debug)]
-struct GameReveal {
-    red: u8,
-    green: u8,
-    blue: u8,
+/*
+    The games are parsed with nom into cubes keyed by colour, so any
+    colour works and the parse errors point at the problem. The bag of
+    part 1 is a parameter, and part 2 multiplies every colour of the record.
+*/
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use nom::{
+    character::complete::{alpha1, char, space0, space1, u32, u64},
+    combinator::{cut, map, map_opt},
+    multi::separated_list1,
+    sequence::{delimited, preceded, separated_pair, tuple},
+};
+
+use crate::number_theory::{checked_product, checked_sum};
+use crate::parsing::{lines, parse_all, tag, IResult};
+
+// Number of cubes per colour, a reveal or a bag.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cubes(BTreeMap<String, u32>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    pub id: u64,
+    pub reveals: Vec<Cubes>,
+}
+
+impl Cubes {
+    pub fn new(cubes: &[(&str, u32)]) -> Cubes {
+        Cubes(
+            cubes
+                .iter()
+                .map(|&(colour, count)| (colour.to_string(), count))
+                .collect(),
+        )
+    }
+
+    // 0 for the colours that are not there.
+    pub fn count(&self, colour: &str) -> u32 {
+        self.0.get(colour).copied().unwrap_or(0)
+    }
+
+    pub fn colours(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+
+    pub fn fits_in(&self, bag: &Cubes) -> bool {
+        self.0
+            .iter()
+            .all(|(colour, &count)| count <= bag.count(colour))
+    }
+
+    // The colours with more cubes than in the bag, with both counts.
+    pub fn excess_over(&self, bag: &Cubes) -> Vec<Excess> {
+        self.0
+            .iter()
+            .filter(|(colour, &count)| count > bag.count(colour))
+            .map(|(colour, &count)| Excess {
+                colour: colour.clone(),
+                shown: count,
+                in_bag: bag.count(colour),
+            })
+            .collect()
+    }
+
+    // The smallest bag that holds both.
+    pub fn union(&self, other: &Cubes) -> Cubes {
+        let mut union = self.clone();
+        for (colour, &count) in other.0.iter() {
+            let entry = union.0.entry(colour.clone()).or_insert(0);
+            *entry = (*entry).max(count);
+        }
+        union
+    }
 }
 
-#[derive(Debug)]
-struct Game {
-    id: i64,
-    reveals: Vec<GameReveal>,
+// A colour of a reveal that doesn't fit in the bag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Excess {
+    pub colour: String,
+    pub shown: u32,
+    pub in_bag: u32,
 }
 
-type Games = Vec<Game>;
+// Why a game is not possible with a bag: its first reveal that doesn't fit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Infeasibility {
+    pub reveal: usize,
+    pub excess: Vec<Excess>,
+}
 
-fn parse_input_data(input: &str) -> Games {
-    return input
-        .lines()
-        .map(|line| {
-            // return error if line doesn't start with "Game "
-            if !line.starts_with("Game ") {
-                panic!("Invalid input data, game doesn't start with game");
-            }
-            // Find the location of the :
-            let colon_index = line
-                .find(':')
-                .expect("Invalid input data, can't find game separator");
-            // Get the game id
-            let game_id = line[5..colon_index]
-                .parse::<i64>()
-                .expect("Invalid input data, can't find game id");
-
-            let reveals = line[colon_index + 1..]
-                .split(';')
-                .map(|reveal| {
-                    let mut game_reveal = GameReveal {
-                        red: 0,
-                        green: 0,
-                        blue: 0,
-                    };
-                    for nb_dices_per_colour in reveal.split(',') {
-                        if nb_dices_per_colour.ends_with("red") {
-                            game_reveal.red = nb_dices_per_colour[1..nb_dices_per_colour.len() - 4]
-                                .parse::<u8>()
-                                .expect("Invalid input data, can't parse red dice");
-                        } else if nb_dices_per_colour.ends_with("green") {
-                            game_reveal.green = nb_dices_per_colour
-                                [1..nb_dices_per_colour.len() - 6]
-                                .parse::<u8>()
-                                .expect("Invalid input data, can't parse green dice");
-                        } else if nb_dices_per_colour.ends_with("blue") {
-                            game_reveal.blue = nb_dices_per_colour
-                                [1..nb_dices_per_colour.len() - 5]
-                                .parse::<u8>()
-                                .expect("Invalid input data, can't parse blue dice");
-                        } else {
-                            panic!("Invalid input data, can't parse dice");
-                        }
-                    }
-                    return game_reveal;
-                })
-                .collect();
-
-            return Game {
-                id: game_id,
-                reveals: reveals,
-            };
-        })
-        .collect();
+impl Game {
+    pub fn is_possible_with(&self, bag: &Cubes) -> bool {
+        self.reveals.iter().all(|reveal| reveal.fits_in(bag))
+    }
+
+    // None if the game is possible.
+    pub fn infeasibility(&self, bag: &Cubes) -> Option<Infeasibility> {
+        self.reveals
+            .iter()
+            .position(|reveal| !reveal.fits_in(bag))
+            .map(|reveal| Infeasibility {
+                reveal,
+                excess: self.reveals[reveal].excess_over(bag),
+            })
+    }
+
+    pub fn minimal_bag(&self) -> Cubes {
+        self.reveals
+            .iter()
+            .fold(Cubes::default(), |bag, reveal| bag.union(reveal))
+    }
 }
 
-pub fn day_2_part_1(data: &str) -> i64 {
-    let games = parse_input_data(data);
+// "3 blue", any number of spaces around the count.
+fn parse_colour_count(data: &str) -> IResult<&str, (u32, &str)> {
+    preceded(space0, separated_pair(u32, space1, alpha1))(data)
+}
 
-    games
-        .iter()
-        .map(|game| {
-            for reveal in &game.reveals {
-                // only 12 red cubes, 13 green cubes, and 14 blue cubes?
-                if reveal.red > 12 || reveal.green > 13 || reveal.blue > 14 {
-                    return 0;
+// "3 blue, 4 red", a colour twice in the same reveal is an error.
+// cut points the errors at what follows the comma, not at the comma.
+fn parse_reveal(data: &str) -> IResult<&str, Cubes> {
+    map_opt(
+        separated_list1(char(','), cut(parse_colour_count)),
+        |counts| {
+            let mut cubes = BTreeMap::new();
+            for (count, colour) in counts {
+                if cubes.insert(colour.to_string(), count).is_some() {
+                    return None;
                 }
             }
-            return game.id;
+            Some(Cubes(cubes))
+        },
+    )(data)
+}
+
+fn parse_game(data: &str) -> IResult<&str, Game> {
+    map(
+        tuple((
+            delimited(
+                tuple((tag("Game"), space1)),
+                u64,
+                tuple((space0, char(':'))),
+            ),
+            separated_list1(char(';'), cut(parse_reveal)),
+        )),
+        |(id, reveals)| Game { id, reveals },
+    )(data)
+}
+
+fn parse_input_data(data: &str) -> IResult<&str, Vec<Game>> {
+    lines(parse_game)(data)
+}
+
+// The questions to ask the games, beyond the two parts.
+pub struct Record {
+    pub games: Vec<Game>,
+}
+
+impl Record {
+    pub fn parse(data: &str) -> Result<Record, String> {
+        Ok(Record {
+            games: parse_all(parse_input_data, data)?,
+        })
+    }
+
+    pub fn game(&self, id: u64) -> Result<&Game, String> {
+        self.games
+            .iter()
+            .find(|game| game.id == id)
+            .ok_or_else(|| format!("No game {}", id))
+    }
+
+    pub fn feasible_games<'a>(&'a self, bag: &'a Cubes) -> impl Iterator<Item = &'a Game> {
+        self.games
+            .iter()
+            .filter(move |game| game.is_possible_with(bag))
+    }
+
+    // The smallest bag with which all these games are possible.
+    pub fn minimal_bag(&self, ids: &[u64]) -> Result<Cubes, String> {
+        ids.iter().try_fold(Cubes::default(), |bag, &id| {
+            Ok(bag.union(&self.game(id)?.minimal_bag()))
         })
-        .sum()
+    }
+
+    pub fn infeasibility(&self, id: u64, bag: &Cubes) -> Result<Option<Infeasibility>, String> {
+        Ok(self.game(id)?.infeasibility(bag))
+    }
 }
 
-pub fn day_2_part_2(data: &str) -> i64 {
-    let games = parse_input_data(data);
+// Sum of the ids of the games that fit in the bag.
+pub fn sum_of_possible_games(record: &Record, bag: &Cubes) -> Result<i64, String> {
+    checked_sum(
+        record
+            .feasible_games(bag)
+            .map(|game| i64::try_from(game.id).map_err(|_| "The game id is too big"))
+            .collect::<Result<Vec<_>, _>>()?,
+    )
+}
 
-    games
+// The colours missing from a game count as 0 cubes, like the puzzle expects
+// with games that don't show any blue cube.
+pub fn sum_of_powers(record: &Record) -> Result<i64, String> {
+    let games = &record.games;
+    let colours = games
         .iter()
-        .map(|game| {
-            let mut max_red: u8 = 0;
-            let mut max_green: u8 = 0;
-            let mut max_blue: u8 = 0;
-            for reveal in &game.reveals {
-                if reveal.red > max_red {
-                    max_red = reveal.red;
-                }
-                if reveal.green > max_green {
-                    max_green = reveal.green;
-                }
-                if reveal.blue > max_blue {
-                    max_blue = reveal.blue;
-                }
-            }
-            return max_red as i64 * max_green as i64 * max_blue as i64;
-        })
-        .sum()
+        .flat_map(|game| game.reveals.iter().flat_map(Cubes::colours))
+        .collect::<BTreeSet<_>>();
+    checked_sum(
+        games
+            .iter()
+            .map(|game| {
+                let bag = game.minimal_bag();
+                checked_product(colours.iter().map(|colour| bag.count(colour) as i64))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+    )
+}
+
+pub fn day_2_part_1(data: &str) -> Result<i64, String> {
+    let record = Record::parse(data)?;
+    // only 12 red cubes, 13 green cubes, and 14 blue cubes?
+    let bag = Cubes::new(&[("red", 12), ("green", 13), ("blue", 14)]);
+    sum_of_possible_games(&record, &bag)
+}
+
+pub fn day_2_part_2(data: &str) -> Result<i64, String> {
+    sum_of_powers(&Record::parse(data)?)
 }
 
 #[cfg(test)]
@@ -123,11 +242,88 @@ Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
 
     #[test]
     fn test_day_2_part_1() {
-        assert_eq!(day_2_part_1(EXAMPLE), 8);
+        assert_eq!(day_2_part_1(EXAMPLE), Ok(8));
+        // the colour used to be sliced at a fixed offset
+        assert!(day_2_part_1("Game 1: red").is_err());
+        assert!(day_2_part_1("Game 1: 3 blue; 1000 red").is_ok());
+        assert!(day_2_part_1("Game 1: 3 blue; 5000000000 red").is_err());
+        assert!(day_2_part_1("Gmae 1: 3 blue")
+            .unwrap_err()
+            .contains("expected \"Game\", found \"Gmae\""));
+        // double spaces and other colours
+        assert_eq!(day_2_part_1("Game  7:  3  blue;  2 purple"), Ok(0));
+        // a colour twice in the same reveal
+        assert!(day_2_part_1("Game 7: 3 blue, 2 blue").is_err());
     }
 
     #[test]
     fn test_day_2_part_2() {
-        assert_eq!(day_2_part_2(EXAMPLE), 2286);
+        assert_eq!(day_2_part_2(EXAMPLE), Ok(2286));
+        // no blue in game 2, so its power is 0
+        assert_eq!(
+            day_2_part_2("Game 1: 2 red, 3 blue\nGame 2: 4 red; 5 red"),
+            Ok(6)
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error =
+            parse_all(parse_input_data, "Game 1: 3 blue\nGame 2: 3 blue, x red").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 17));
+    }
+
+    #[test]
+    fn test_bag() {
+        let record = Record::parse(EXAMPLE).unwrap();
+        assert_eq!(
+            record.games[0].minimal_bag(),
+            Cubes::new(&[("red", 4), ("green", 2), ("blue", 6)])
+        );
+        let small_bag = Cubes::new(&[("red", 20), ("green", 13), ("blue", 6)]);
+        assert_eq!(
+            sum_of_possible_games(&record, &small_bag),
+            Ok(1 + 2 + 3 + 5)
+        );
+        assert_eq!(sum_of_possible_games(&record, &Cubes::default()), Ok(0));
+    }
+
+    #[test]
+    fn test_queries() {
+        let record = Record::parse(EXAMPLE).unwrap();
+        let bag = Cubes::new(&[("red", 12), ("green", 13), ("blue", 14)]);
+        let ids = record
+            .feasible_games(&bag)
+            .map(|game| game.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![1, 2, 5]);
+
+        assert_eq!(
+            record.minimal_bag(&[1, 2, 5]),
+            Ok(Cubes::new(&[("red", 6), ("green", 3), ("blue", 6)]))
+        );
+        assert_eq!(record.minimal_bag(&[]), Ok(Cubes::default()));
+        assert!(record.minimal_bag(&[42]).is_err());
+        // the minimal bag of all the games makes them all possible
+        let all = record.games.iter().map(|game| game.id).collect::<Vec<_>>();
+        let bag_for_all = record.minimal_bag(&all).unwrap();
+        assert_eq!(record.feasible_games(&bag_for_all).count(), 5);
+
+        // game 3 shows 20 red cubes in its first reveal
+        assert_eq!(
+            record.infeasibility(3, &bag),
+            Ok(Some(Infeasibility {
+                reveal: 0,
+                excess: vec![Excess {
+                    colour: "red".to_string(),
+                    shown: 20,
+                    in_bag: 12
+                }]
+            }))
+        );
+        assert_eq!(record.infeasibility(1, &bag), Ok(None));
+        let infeasibility = record.infeasibility(4, &bag).unwrap().unwrap();
+        assert_eq!(infeasibility.reveal, 2);
+        assert_eq!(infeasibility.excess[0].colour, "blue");
     }
 }
@@ -1,183 +1,324 @@
-use std::collections::HashSet;
+/*
+    The schematic is a bipartite graph between the symbols and the numbers
+    around them. The numbers are identified by their position, not their
+    value, so a gear between two equal numbers is still a gear.
+
+    The streaming engine doesn't build the graph: it reads one row at a
+    time and only keeps three rows, so the memory depends on the width
+    of the schematic and not on its height.
+*/
+
+use std::collections::VecDeque;
+use std::io::BufRead;
+use std::ops::Range;
 
 use ndarray::Array2;
 
-fn parse_input_data(data: &str) -> Array2<char> {
-    let numbers = data
-        .chars()
-        .filter(|c| !c.is_ascii_whitespace())
-        .collect::<Vec<char>>();
+use crate::number_theory::{checked_product, checked_sum};
+
+fn parse_input_data(data: &str) -> Result<Array2<char>, String> {
+    let rows = data
+        .lines()
+        .map(|line| line.trim_end().chars().collect::<Vec<char>>())
+        .filter(|row| !row.is_empty())
+        .collect::<Vec<_>>();
 
-    // compute sqrt of the number of elements, as it's a square
-    let sqrt = (numbers.len() as f64).sqrt() as usize;
+    // it's a square in the puzzle, but any rectangle works
+    let nb_cols = rows.first().map_or(0, |row| row.len());
+    if nb_cols == 0 || rows.iter().any(|row| row.len() != nb_cols) {
+        return Err("Failed to parse input data, the rows must have the same length".to_string());
+    }
 
-    return Array2::from_shape_vec((sqrt, sqrt), numbers).expect("Failed to parse input data");
+    Array2::from_shape_vec((rows.len(), nb_cols), rows.concat()).map_err(|error| error.to_string())
 }
 
 #[inline]
 fn is_symbol(c: char) -> bool {
-    return !c.is_digit(10) && c != '.';
+    !c.is_ascii_digit() && c != '.'
+}
+
+// A number of the schematic, its value is not its identity:
+// two 12 next to the same gear are two different numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Number {
+    pub value: i64,
+    pub row: usize,
+    pub cols: Range<usize>,
 }
 
-fn compute_nb_of_adjacent_symbols(data: &Array2<char>) -> Array2<usize> {
-    let dim = data.dim();
-    let (rows, cols) = dim;
-    // Create a zero matrix of the same size as the input data
-    let mut result = Array2::<usize>::zeros(dim);
-
-    for i in 0..rows {
-        for j in 0..cols {
-            let mut sum = 0;
-            for di in 0.max(i as isize - 1) as usize..=(rows - 1).min(i + 1) {
-                for dj in 0.max(j as isize - 1) as usize..=(cols - 1).min(j + 1) {
-                    if is_symbol(data[(di, dj)]) {
-                        sum += 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub symbol: char,
+    pub row: usize,
+    pub col: usize,
+}
+
+// The numbers and the symbols are identified by their index,
+// and the adjacency graph between them is kept in both directions.
+#[derive(Debug)]
+pub struct Schematic {
+    pub numbers: Vec<Number>,
+    pub symbols: Vec<Symbol>,
+    numbers_of_symbol: Vec<Vec<usize>>,
+    symbols_of_number: Vec<Vec<usize>>,
+}
+
+fn find_numbers(data: &Array2<char>) -> Result<(Vec<Number>, Array2<Option<usize>>), String> {
+    let mut numbers = Vec::<Number>::new();
+    let mut number_ids = Array2::<Option<usize>>::from_elem(data.dim(), None);
+
+    for (i, row) in data.rows().into_iter().enumerate() {
+        let mut start: Option<usize> = None;
+        // an extra non digit closes the numbers at the end of the rows
+        for (j, c) in row.iter().copied().chain(std::iter::once('.')).enumerate() {
+            match (c.is_ascii_digit(), start) {
+                (true, None) => start = Some(j),
+                (false, Some(col_start)) => {
+                    let value = row
+                        .slice(ndarray::s![col_start..j])
+                        .iter()
+                        .filter_map(|c| c.to_digit(10))
+                        .try_fold(0_i64, |acc, x| acc.checked_mul(10)?.checked_add(x as i64))
+                        .ok_or_else(|| "A part number is too big".to_string())?;
+                    for col in col_start..j {
+                        number_ids[(i, col)] = Some(numbers.len());
                     }
+                    numbers.push(Number {
+                        value,
+                        row: i,
+                        cols: col_start..j,
+                    });
+                    start = None;
                 }
+                _ => {}
             }
-            result[(i, j)] = sum;
         }
     }
 
-    return result;
+    Ok((numbers, number_ids))
 }
 
-#[inline]
-fn compute_number_from_matrix(
-    data: &Array2<char>,
-    row: usize,
-    col_start: usize,
-    length: usize,
-) -> i64 {
-    data.slice(ndarray::s![row, col_start..col_start + length])
-        .iter()
-        .map(|c| c.to_digit(10).unwrap() as i64)
-        .fold(0, |acc, x| acc * 10 + x)
-}
+impl Schematic {
+    pub fn parse(data: &str) -> Result<Schematic, String> {
+        let data = parse_input_data(data)?;
+        let (rows, cols) = data.dim();
+        let (numbers, number_ids) = find_numbers(&data)?;
 
-#[inline]
-fn fill_matrix_with_number(
-    matrix: &mut Array2<i64>,
-    row: usize,
-    col_start: usize,
-    length: usize,
-    number: i64,
-) {
-    for i in col_start..col_start + length {
-        matrix[(row, i)] = number;
+        let mut symbols = Vec::<Symbol>::new();
+        let mut numbers_of_symbol = Vec::<Vec<usize>>::new();
+        let mut symbols_of_number = vec![Vec::<usize>::new(); numbers.len()];
+
+        for ((row, col), &symbol) in data.indexed_iter() {
+            if !is_symbol(symbol) {
+                continue;
+            }
+            let symbol_id = symbols.len();
+            let mut adjacent_numbers = Vec::<usize>::new();
+            for di in row.saturating_sub(1)..=(rows - 1).min(row + 1) {
+                for dj in col.saturating_sub(1)..=(cols - 1).min(col + 1) {
+                    if let Some(number_id) = number_ids[(di, dj)] {
+                        adjacent_numbers.push(number_id);
+                    }
+                }
+            }
+            // a number can touch the symbol with several digits
+            adjacent_numbers.sort();
+            adjacent_numbers.dedup();
+            for &number_id in adjacent_numbers.iter() {
+                symbols_of_number[number_id].push(symbol_id);
+            }
+            symbols.push(Symbol { symbol, row, col });
+            numbers_of_symbol.push(adjacent_numbers);
+        }
+
+        Ok(Schematic {
+            numbers,
+            symbols,
+            numbers_of_symbol,
+            symbols_of_number,
+        })
+    }
+
+    pub fn numbers_adjacent_to(&self, symbol_id: usize) -> impl Iterator<Item = &Number> {
+        self.numbers_of_symbol
+            .get(symbol_id)
+            .into_iter()
+            .flatten()
+            .map(|&number_id| &self.numbers[number_id])
+    }
+
+    pub fn symbols_touching(&self, number_id: usize) -> impl Iterator<Item = &Symbol> {
+        self.symbols_of_number
+            .get(number_id)
+            .into_iter()
+            .flatten()
+            .map(|&symbol_id| &self.symbols[symbol_id])
+    }
+
+    // The part numbers are the numbers touching at least one symbol.
+    pub fn part_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers
+            .iter()
+            .zip(self.symbols_of_number.iter())
+            .filter(|(_, symbols)| !symbols.is_empty())
+            .map(|(number, _)| number)
     }
-}
 
-fn identify_part_numbers(data: &Array2<char>) -> (Vec<i64>, Array2<i64>) {
-    let dim = data.dim();
-    let (rows, cols) = dim;
+    // The ids of the symbols with exactly k adjacent numbers.
+    pub fn gears(&self, symbol: char, k: usize) -> impl Iterator<Item = usize> + '_ {
+        self.symbols
+            .iter()
+            .enumerate()
+            .filter(move |(symbol_id, candidate)| {
+                candidate.symbol == symbol && self.numbers_of_symbol[*symbol_id].len() == k
+            })
+            .map(|(symbol_id, _)| symbol_id)
+    }
+}
 
-    let adjacent_symbols_matrix = compute_nb_of_adjacent_symbols(&data);
-    //println!("{:?}", adjacent_symbols_matrix);
+pub fn day_3_part_1(data: &str) -> Result<i64, String> {
+    let schematic = Schematic::parse(data)?;
+    checked_sum(schematic.part_numbers().map(|number| number.value))
+}
 
-    let mut part_numbers = Vec::<i64>::new();
+pub fn day_3_part_2(data: &str) -> Result<i64, String> {
+    let schematic = Schematic::parse(data)?;
+    checked_sum(
+        schematic
+            .gears('*', 2)
+            .map(|gear| {
+                checked_product(
+                    schematic
+                        .numbers_adjacent_to(gear)
+                        .map(|number| number.value),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+    )
+}
 
-    let mut part_numbers_matrix = Array2::<i64>::zeros(dim);
+// The numbers and the symbols of a row, without the dots.
+#[derive(Debug, Default)]
+struct StreamedRow {
+    numbers: Vec<(Range<usize>, i64)>,
+    symbols: Vec<(usize, char)>,
+}
 
-    // for each row
-    for i in 0..rows {
-        let mut start_text_j: isize = -1;
-        let mut has_seen_symbol = false;
-        // for each column
-        for j in 0..cols {
-            let c = data[(i, j)];
-            let is_digit = c.is_digit(10);
-            if is_digit {
-                if start_text_j == -1 {
-                    start_text_j = j as isize;
+impl StreamedRow {
+    fn parse(line: &str) -> Result<StreamedRow, String> {
+        let mut row = StreamedRow::default();
+        let mut number: Option<(usize, i64)> = None;
+        for (col, c) in line.chars().chain(std::iter::once('.')).enumerate() {
+            match (c.to_digit(10), number) {
+                (Some(digit), Some((start, value))) => {
+                    let value = value
+                        .checked_mul(10)
+                        .and_then(|value| value.checked_add(digit as i64))
+                        .ok_or("A part number is too big")?;
+                    number = Some((start, value));
                 }
-                if !has_seen_symbol {
-                    let nb_adjacent_symbols = adjacent_symbols_matrix[(i, j)];
-                    if nb_adjacent_symbols > 0 {
-                        has_seen_symbol = true;
+                (Some(digit), None) => number = Some((col, digit as i64)),
+                (None, _) => {
+                    if let Some((start, value)) = number.take() {
+                        row.numbers.push((start..col, value));
+                    }
+                    if is_symbol(c) {
+                        row.symbols.push((col, c));
                     }
                 }
-            } else {
-                if start_text_j != -1 && has_seen_symbol {
-                    let end_text_j = j as isize - 1;
-                    let text_len = end_text_j - start_text_j + 1;
-                    let number = compute_number_from_matrix(
-                        &data,
-                        i,
-                        start_text_j as usize,
-                        text_len as usize,
-                    );
-                    fill_matrix_with_number(
-                        &mut part_numbers_matrix,
-                        i,
-                        start_text_j as usize,
-                        text_len as usize,
-                        number,
-                    );
-                    //println!("Found text: {:?}", number);
-                    part_numbers.push(number);
-                }
-                start_text_j = -1;
-                has_seen_symbol = false;
             }
         }
-        if start_text_j != -1 && has_seen_symbol {
-            let end_text_j = cols as isize - 1;
-            let text_len = end_text_j - start_text_j + 1;
-            let number =
-                compute_number_from_matrix(&data, i, start_text_j as usize, text_len as usize);
-            //println!("Found text: {:?}", number);
-            fill_matrix_with_number(
-                &mut part_numbers_matrix,
-                i,
-                start_text_j as usize,
-                text_len as usize,
-                number,
-            );
-            part_numbers.push(number);
-        }
+        Ok(row)
     }
 
-    return (part_numbers, part_numbers_matrix);
-}
+    fn numbers_touching(&self, col: usize) -> impl Iterator<Item = i64> + '_ {
+        self.numbers
+            .iter()
+            .filter(move |(cols, _)| cols.start <= col + 1 && col <= cols.end)
+            .map(|(_, value)| *value)
+    }
 
-pub fn day_3_part_1(data: &str) -> i64 {
-    let data = parse_input_data(data);
-    let (part_numbers, _) = identify_part_numbers(&data);
-    return part_numbers.iter().sum();
+    fn has_symbol_around(&self, cols: &Range<usize>) -> bool {
+        self.symbols
+            .iter()
+            .any(|&(col, _)| col + 1 >= cols.start && col <= cols.end)
+    }
 }
 
-pub fn day_3_part_2(data: &str) -> i64 {
-    let data = parse_input_data(data);
-    let (_, part_numbers_matrix) = identify_part_numbers(&data);
+// The sums of both parts, reading the rows one by one.
+pub fn stream_schematic<R: BufRead>(reader: R) -> Result<(i64, i64), String> {
+    let mut sum_of_part_numbers = 0_i64;
+    let mut sum_of_gear_ratios = 0_i64;
+    let mut width: Option<usize> = None;
+    // the rows before, at, and after the one being summed up
+    let mut window: VecDeque<StreamedRow> = VecDeque::with_capacity(3);
+    window.push_back(StreamedRow::default());
 
-    let mut sum: i64 = 0;
-
-    for ((row, col), symbol) in data.indexed_iter() {
-        if *symbol == '*' {
-            let mut adjacent_part_numbers = HashSet::<i64>::new();
-            for di in 0.max(row as isize - 1) as usize..=(data.dim().0 - 1).min(row + 1) {
-                for dj in 0.max(col as isize - 1) as usize..=(data.dim().1 - 1).min(col + 1) {
-                    let adjacent_part_number = part_numbers_matrix[(di, dj)];
-                    if adjacent_part_number > 0 {
-                        adjacent_part_numbers.insert(adjacent_part_number);
-                    }
-                }
+    let mut sum_middle_row = |window: &VecDeque<StreamedRow>| -> Result<(), String> {
+        let row = &window[1];
+        for (cols, value) in row.numbers.iter() {
+            if window.iter().any(|other| other.has_symbol_around(cols)) {
+                sum_of_part_numbers = checked_sum([sum_of_part_numbers, *value])?;
             }
-
-            if adjacent_part_numbers.len() == 2 {
-                sum += adjacent_part_numbers.iter().product::<i64>();
+        }
+        for &(col, symbol) in row.symbols.iter() {
+            if symbol != '*' {
+                continue;
+            }
+            let numbers = window
+                .iter()
+                .flat_map(|other| other.numbers_touching(col))
+                .collect::<Vec<_>>();
+            if numbers.len() == 2 {
+                sum_of_gear_ratios = checked_sum([sum_of_gear_ratios, checked_product(numbers)?])?;
             }
         }
+        Ok(())
+    };
+
+    for line in reader.lines() {
+        let line = line.map_err(|error| error.to_string())?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        let line_width = line.chars().count();
+        if *width.get_or_insert(line_width) != line_width {
+            return Err(
+                "Failed to parse input data, the rows must have the same length".to_string(),
+            );
+        }
+        window.push_back(StreamedRow::parse(line)?);
+        if window.len() == 3 {
+            sum_middle_row(&window)?;
+            window.pop_front();
+        }
     }
+    if width.is_none() {
+        return Err("Failed to parse input data, the rows must have the same length".to_string());
+    }
+    window.push_back(StreamedRow::default());
+    sum_middle_row(&window)?;
+
+    Ok((sum_of_part_numbers, sum_of_gear_ratios))
+}
+
+pub fn day_3_part_1_stream<R: BufRead>(reader: R) -> Result<i64, String> {
+    stream_schematic(reader).map(|(part_1, _)| part_1)
+}
 
-    return sum;
+pub fn day_3_part_2_stream<R: BufRead>(reader: R) -> Result<i64, String> {
+    stream_schematic(reader).map(|(_, part_2)| part_2)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use std::io::Read;
+
+    use crate::generate;
+
     const EXAMPLE: &str = "467..114..
 ...*......
 ..35..633.
@@ -202,13 +343,120 @@ mod tests {
 
     #[test]
     fn test_day_3_part_1() {
-        assert_eq!(day_3_part_1(EXAMPLE), 4361);
-        assert_eq!(day_3_part_1(EXAMPLE_B), 4361);
+        assert_eq!(day_3_part_1(EXAMPLE), Ok(4361));
+        assert_eq!(day_3_part_1(EXAMPLE_B), Ok(4361));
         // 528799
     }
 
     #[test]
     fn test_day_3_part_2() {
-        assert_eq!(day_3_part_2(EXAMPLE), 467835);
+        assert_eq!(day_3_part_2(EXAMPLE), Ok(467835));
+        assert!(day_3_part_2("12*\n3").is_err());
+        assert!(day_3_part_1("99999999999999999999*").is_err());
+        // two different numbers with the same value around a gear
+        assert_eq!(day_3_part_2("12*12"), Ok(144));
+        assert_eq!(day_3_part_2("12*..\n...12"), Ok(144));
+    }
+
+    #[test]
+    fn test_schematic() {
+        let schematic = Schematic::parse(EXAMPLE).unwrap();
+        assert_eq!(schematic.numbers.len(), 10);
+        assert_eq!(schematic.symbols.len(), 6);
+        assert_eq!(
+            schematic.numbers[0],
+            Number {
+                value: 467,
+                row: 0,
+                cols: 0..3
+            }
+        );
+
+        let gears = schematic.gears('*', 2).collect::<Vec<_>>();
+        assert_eq!(gears.len(), 2);
+        let values = schematic
+            .numbers_adjacent_to(gears[0])
+            .map(|number| number.value)
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![467, 35]);
+        // the * next to 617 only
+        assert_eq!(schematic.gears('*', 1).count(), 1);
+        assert_eq!(schematic.gears('#', 1).count(), 1);
+
+        // 114 and 58 touch nothing
+        let lonely = schematic
+            .numbers
+            .iter()
+            .enumerate()
+            .filter(|(id, _)| schematic.symbols_touching(*id).count() == 0)
+            .map(|(_, number)| number.value)
+            .collect::<Vec<_>>();
+        assert_eq!(lonely, vec![114, 58]);
+        let symbols = schematic.symbols_touching(2).collect::<Vec<_>>();
+        assert_eq!(
+            symbols,
+            vec![&Symbol {
+                symbol: '*',
+                row: 1,
+                col: 3
+            }]
+        );
+        assert_eq!(schematic.numbers_adjacent_to(42).count(), 0);
+    }
+
+    #[test]
+    fn test_stream_schematic() {
+        assert_eq!(stream_schematic(EXAMPLE.as_bytes()), Ok((4361, 467835)));
+        assert_eq!(day_3_part_2_stream("12*12".as_bytes()), Ok(144));
+        assert!(day_3_part_2_stream("12*\n3".as_bytes()).is_err());
+        assert!(day_3_part_1_stream("".as_bytes()).is_err());
+        assert!(day_3_part_1_stream("99999999999999999999*".as_bytes()).is_err());
+
+        for seed in 0..20 {
+            let mut random = generate::Random::new(seed);
+            let input = generate::day_03(&mut random, 30);
+            assert_eq!(
+                stream_schematic(input.as_bytes()),
+                Ok((day_3_part_1(&input).unwrap(), day_3_part_2(&input).unwrap())),
+                "{}",
+                input
+            );
+        }
+
+        // a tall schematic, from a reader that doesn't hold it all
+        let nb_pairs = 100_000;
+        let reader = std::io::BufReader::new(Read::chain(
+            EXAMPLE.as_bytes(),
+            Read::take(Pattern::new("\n..........\n1*1......."), nb_pairs * 22),
+        ));
+        assert_eq!(
+            stream_schematic(reader),
+            Ok((4361 + nb_pairs as i64 * 2, 467835 + nb_pairs as i64))
+        );
+    }
+
+    // Repeats the same bytes forever.
+    struct Pattern {
+        pattern: &'static [u8],
+        position: usize,
+    }
+
+    impl Pattern {
+        fn new(pattern: &'static str) -> Pattern {
+            Pattern {
+                pattern: pattern.as_bytes(),
+                position: 0,
+            }
+        }
+    }
+
+    impl Read for Pattern {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            for byte in buf.iter_mut() {
+                *byte = self.pattern[self.position];
+                self.position = (self.position + 1) % self.pattern.len();
+            }
+            Ok(buf.len())
+        }
     }
 }
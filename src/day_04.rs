@@ -1,15 +1,20 @@
 // First time using nom, let's see.
+//
+// Part 2 is a cascade of copies with the rules of the puzzle,
+// but the distance, the multiplier and what happens past the end
+// of the table can be changed.
+use std::collections::HashSet;
+
 use nom::{
     self,
-    bytes::complete::tag,
-    character::complete::{line_ending, space1},
+    character::complete::space1,
     combinator::map,
-    combinator::opt,
-    multi::{many0, separated_list1},
-    sequence::{terminated, tuple},
-    IResult,
+    sequence::{separated_pair, tuple},
 };
 
+use crate::number_theory::checked_sum;
+use crate::parsing::{key_value, lines, parse_all, spaced_list, tag, IResult};
+
 fn parse_card_title(data: &str) -> IResult<&str, u64> {
     map(
         tuple((tag("Card"), space1, nom::character::complete::u64)),
@@ -17,72 +22,208 @@ fn parse_card_title(data: &str) -> IResult<&str, u64> {
     )(data)
 }
 
-fn parse_card(data: &str) -> IResult<&str, (u64, Vec<u8>, Vec<u8>)> {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Card {
+    pub number: u64,
+    pub winning_numbers: Vec<u64>,
+    pub card_numbers: Vec<u64>,
+}
+
+impl Card {
+    // A winning number written twice counts twice, like before.
+    pub fn nb_of_matches(&self) -> usize {
+        let card_numbers = self.card_numbers.iter().collect::<HashSet<_>>();
+        self.winning_numbers
+            .iter()
+            .filter(|number| card_numbers.contains(number))
+            .count()
+    }
+}
+
+fn parse_card(data: &str) -> IResult<&str, Card> {
     map(
-        tuple((
+        key_value(
             parse_card_title,
-            tag(":"),
-            space1,
-            separated_list1(space1, nom::character::complete::u8),
-            space1,
-            tag("|"),
-            space1,
-            separated_list1(space1, nom::character::complete::u8),
-        )),
-        |(card_number, _, _, winning_numbers, _, _, _, card_numbers)| {
-            (card_number, winning_numbers, card_numbers)
+            separated_pair(
+                spaced_list(nom::character::complete::u64),
+                tuple((space1, tag("|"), space1)),
+                spaced_list(nom::character::complete::u64),
+            ),
+        ),
+        |(number, (winning_numbers, card_numbers))| Card {
+            number,
+            winning_numbers,
+            card_numbers,
         },
     )(data)
 }
 
-fn parse_input_data(data: &str) -> IResult<&str, Vec<(u64, Vec<u8>, Vec<u8>)>> {
-    many0(terminated(parse_card, opt(line_ending)))(data)
+fn parse_input_data(data: &str) -> IResult<&str, Vec<Card>> {
+    lines(parse_card)(data)
 }
 
-fn compute_nb_of_intersection(winning_numbers: &Vec<u8>, card_numbers: &Vec<u8>) -> i64 {
-    // slow algorithm because the inputs are small and not worth
-    // using fanciers algorithms with a bigger overhead like sets.
-    let mut nb_of_intersection = 0;
-    for winning_number in winning_numbers {
-        if card_numbers.contains(winning_number) {
-            nb_of_intersection += 1;
-        }
-    }
-    return nb_of_intersection;
-}
+pub fn day_4_part_1(data: &str) -> Result<i64, String> {
+    let cards = parse_all(parse_input_data, data)?;
 
-pub fn day_4_part_1(data: &str) -> i64 {
-    let (_, cards) = parse_input_data(data).expect("Unable to parse input data");
-
-    cards
+    let points = cards
         .iter()
-        .map(|(_, winning_numbers, card_numbers)| {
-            let nb = compute_nb_of_intersection(winning_numbers, card_numbers);
+        .map(|card| {
+            let nb = card.nb_of_matches();
             if nb > 0 {
-                return 2_i64.pow(nb as u32 - 1);
+                return u32::try_from(nb - 1)
+                    .ok()
+                    .and_then(|exponent| 2_i64.checked_pow(exponent))
+                    .ok_or_else(|| "Too many winning numbers".to_string());
             }
-            return 0;
+            return Ok(0);
         })
-        .sum()
+        .collect::<Result<Vec<_>, String>>()?;
+    checked_sum(points)
 }
 
-pub fn day_4_part_2(data: &str) -> i64 {
-    let (_, cards) = parse_input_data(data).expect("Unable to parse input data");
+// What happens to the copies of the cards past the end of the table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PastTheEnd {
+    Error,
+    Ignore,
+    // They go back to the first cards. Those were already scratched,
+    // so their copies are counted but don't win anything.
+    WrapAround,
+}
 
-    // create an array of numbers of cards filled with 1
-    let mut collection = vec![1_i64; cards.len()];
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CopyRules {
+    // 1 copies the next cards, 2 skips one card, etc.
+    pub distance: usize,
+    // How many copies of each card a match wins.
+    pub multiplier: u32,
+    pub past_the_end: PastTheEnd,
+}
 
-    cards
-        .iter()
-        .enumerate()
-        .for_each(|(card_number, (_, winning_numbers, card_numbers))| {
-            let nb = compute_nb_of_intersection(winning_numbers, card_numbers);
-            for i in card_number + 1..card_number + nb as usize + 1 {
-                collection[i] += collection[card_number];
+impl Default for CopyRules {
+    // The rules of the puzzle.
+    fn default() -> CopyRules {
+        CopyRules {
+            distance: 1,
+            multiplier: 1,
+            past_the_end: PastTheEnd::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cascade {
+    // The number of instances of each card, the original included.
+    pub instances: Vec<i64>,
+    // How many original cards have at least one match.
+    pub nb_of_winning_cards: usize,
+}
+
+impl Cascade {
+    pub fn total(&self) -> Result<i64, String> {
+        checked_sum(self.instances.iter().copied())
+    }
+}
+
+// Adds amount to the cards of [start, end), start < end <= cards.len().
+// The cards after the current one go in the difference array, the cards
+// before were already scratched and go in another one.
+fn add_copies(
+    ahead: &mut [i64],
+    behind: &mut [i64],
+    current: usize,
+    start: usize,
+    end: usize,
+    amount: i64,
+) -> Result<(), String> {
+    let add = |diff: &mut [i64], start: usize, end: usize| -> Result<(), String> {
+        diff[start] = diff[start].checked_add(amount).ok_or("Too many copies")?;
+        diff[end] = diff[end].checked_sub(amount).ok_or("Too many copies")?;
+        Ok(())
+    };
+    let split = (current + 1).clamp(start, end);
+    if start < split {
+        add(behind, start, split)?;
+    }
+    if split < end {
+        add(ahead, split, end)?;
+    }
+    Ok(())
+}
+
+// Each card adds its copies to a range of cards, so a difference array
+// sums them up in one pass instead of adding to every card of the range.
+pub fn cascade(cards: &[Card], rules: &CopyRules) -> Result<Cascade, String> {
+    let nb_cards = cards.len();
+    let mut ahead = vec![0_i64; nb_cards + 1];
+    let mut behind = vec![0_i64; nb_cards + 1];
+    let mut instances = vec![0_i64; nb_cards];
+    let mut nb_of_winning_cards = 0;
+    let mut copies = 0_i64;
+
+    for (index, card) in cards.iter().enumerate() {
+        copies = copies.checked_add(ahead[index]).ok_or("Too many copies")?;
+        instances[index] = copies.checked_add(1).ok_or("Too many copies")?;
+        let nb = card.nb_of_matches();
+        if nb == 0 {
+            continue;
+        }
+        nb_of_winning_cards += 1;
+
+        let amount = instances[index]
+            .checked_mul(i64::from(rules.multiplier))
+            .ok_or("Too many copies")?;
+        let start = index.saturating_add(rules.distance);
+        let end = start.saturating_add(nb);
+        match rules.past_the_end {
+            PastTheEnd::Error if end > nb_cards => {
+                return Err(format!(
+                    "Card {} wins cards past the end of the table",
+                    card.number
+                ));
+            }
+            PastTheEnd::Error | PastTheEnd::Ignore => {
+                if start < nb_cards {
+                    let end = end.min(nb_cards);
+                    add_copies(&mut ahead, &mut behind, index, start, end, amount)?;
+                }
+            }
+            PastTheEnd::WrapAround => {
+                // every full turn of the table copies every card
+                let nb_turns = i64::try_from(nb / nb_cards).map_err(|_| "Too many copies")?;
+                if nb_turns > 0 {
+                    let amount = amount.checked_mul(nb_turns).ok_or("Too many copies")?;
+                    add_copies(&mut ahead, &mut behind, index, 0, nb_cards, amount)?;
+                }
+                let start = start % nb_cards;
+                let end = start + nb % nb_cards;
+                if end > nb_cards {
+                    add_copies(&mut ahead, &mut behind, index, start, nb_cards, amount)?;
+                    add_copies(&mut ahead, &mut behind, index, 0, end - nb_cards, amount)?;
+                } else if start < end {
+                    add_copies(&mut ahead, &mut behind, index, start, end, amount)?;
+                }
             }
-        });
+        }
+    }
+
+    let mut late_copies = 0_i64;
+    for (instances, diff) in instances.iter_mut().zip(behind.iter()) {
+        late_copies = late_copies.checked_add(*diff).ok_or("Too many copies")?;
+        *instances = instances
+            .checked_add(late_copies)
+            .ok_or("Too many copies")?;
+    }
+
+    Ok(Cascade {
+        instances,
+        nb_of_winning_cards,
+    })
+}
 
-    return collection.iter().sum();
+pub fn day_4_part_2(data: &str) -> Result<i64, String> {
+    let cards = parse_all(parse_input_data, data)?;
+    cascade(&cards, &CopyRules::default())?.total()
 }
 
 #[cfg(test)]
@@ -98,11 +239,68 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 
     #[test]
     fn test_day_4_part_1() {
-        assert_eq!(day_4_part_1(EXAMPLE), 13);
+        assert_eq!(day_4_part_1(EXAMPLE), Ok(13));
     }
 
     #[test]
     fn test_day_4_part_2() {
-        assert_eq!(day_4_part_2(EXAMPLE), 30);
+        assert_eq!(day_4_part_2(EXAMPLE), Ok(30));
+        assert!(day_4_part_2("Card 1: 1 2 | 1 2").is_err());
+        // the numbers are not limited to a u8 anymore
+        assert_eq!(day_4_part_2("Card 1: 1000 | 1000\nCard 2: 3 | 4"), Ok(3));
+    }
+
+    #[test]
+    fn test_cascade() {
+        let cards = parse_all(parse_input_data, EXAMPLE).unwrap();
+        let cascade_with = |rules: CopyRules| cascade(&cards, &rules);
+
+        let puzzle = cascade_with(CopyRules::default()).unwrap();
+        assert_eq!(puzzle.instances, vec![1, 2, 4, 8, 14, 1]);
+        assert_eq!(puzzle.nb_of_winning_cards, 4);
+
+        let doubled = cascade_with(CopyRules {
+            multiplier: 2,
+            ..CopyRules::default()
+        })
+        .unwrap();
+        assert_eq!(doubled.instances, vec![1, 3, 9, 27, 75, 1]);
+
+        // card 1 wins 4 cards from card 4, one past the end
+        assert!(cascade_with(CopyRules {
+            distance: 3,
+            ..CopyRules::default()
+        })
+        .is_err());
+        let ignored = cascade_with(CopyRules {
+            distance: 3,
+            past_the_end: PastTheEnd::Ignore,
+            ..CopyRules::default()
+        })
+        .unwrap();
+        assert_eq!(ignored.instances, vec![1, 1, 1, 2, 3, 4]);
+
+        // cards 1, 3 and 4 all win copies of card 1
+        let wrapped = cascade_with(CopyRules {
+            distance: 3,
+            multiplier: 1,
+            past_the_end: PastTheEnd::WrapAround,
+        })
+        .unwrap();
+        assert_eq!(wrapped.instances, vec![5, 1, 1, 2, 3, 4]);
+
+        // more matches than cards
+        let cards = parse_all(parse_input_data, "Card 1: 1 2 3 | 1 2 3\nCard 2: 4 | 5").unwrap();
+        let wrapped = cascade(
+            &cards,
+            &CopyRules {
+                past_the_end: PastTheEnd::WrapAround,
+                ..CopyRules::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(wrapped.instances, vec![2, 3]);
+        assert_eq!(wrapped.nb_of_winning_cards, 1);
+        assert_eq!(wrapped.total(), Ok(5));
     }
 }
@@ -29,24 +29,33 @@
 
     I then check every location from the lowest to the highest and browse through everything in reverse order.
     I sort first to save some little CPU time.
+
+    Later, the maps became piecewise-linear functions instead. The gaps are filled
+    with pieces that don't move the values, like before, but the seven functions are
+    composed into a single seed to location function. Each of its pieces is increasing,
+    so the lowest location of a range of seeds is at the start of the range or at the
+    start of a piece, and a single sweep over the sorted ranges finds it.
+
+    The pieces can also be followed backwards, to find which seeds lead to some locations.
 */
 
-use std::{collections::HashMap, vec};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
 
 // I'm not sure nom makes me save time,
 // but I keep practicing it.
 use nom::{
     self,
-    bytes::complete::tag,
     character::complete::{line_ending, none_of, space1},
     combinator::{map, recognize},
-    combinator::{map_res, opt},
-    error::ErrorKind,
+    combinator::{map_opt, map_res, opt},
     multi::{many0, many1, separated_list1},
     sequence::{terminated, tuple},
-    IResult,
 };
 
+use crate::parsing::{parse_all, tag, IResult};
+use crate::trace::{span, trace_event, Level};
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 struct Map {
     destination_range_start: u64,
@@ -54,16 +63,20 @@ struct Map {
     range_length: u64,
 }
 
+// The maps from one category to another, like seed-to-soil.
+#[derive(Debug)]
+struct CategoryMaps {
+    source: String,
+    destination: String,
+    maps: Vec<Map>,
+}
+
+// The categories and their maps form a graph, the maps can be in any order
+// and the categories don't have to be the ones of the puzzle.
 #[derive(Debug)]
-struct InputData {
-    seeds: Vec<u64>,
-    seed_to_soil_maps: Vec<Map>,
-    soil_to_fertilizer_maps: Vec<Map>,
-    fertilizer_to_water_maps: Vec<Map>,
-    water_to_light_maps: Vec<Map>,
-    light_to_temperature_maps: Vec<Map>,
-    temperature_to_humidity_maps: Vec<Map>,
-    humidity_to_location_maps: Vec<Map>,
+pub struct Almanac {
+    pub seeds: Vec<u64>,
+    category_maps: Vec<CategoryMaps>,
 }
 
 fn parse_seeds(data: &str) -> IResult<&str, Vec<u64>> {
@@ -98,324 +111,451 @@ fn parse_maps(data: &str) -> IResult<&str, Vec<Map>> {
     many0(terminated(parse_map, opt(line_ending)))(data)
 }
 
-fn parse_maps_with_header(data: &str) -> IResult<&str, (&str, Vec<Map>)> {
+// "seed-to-soil"
+fn parse_header(data: &str) -> IResult<&str, (&str, &str)> {
+    map_opt(recognize(many1(none_of(" \r\n"))), |header: &str| {
+        header
+            .split_once("-to-")
+            .filter(|(source, destination)| !source.is_empty() && !destination.is_empty())
+    })(data)
+}
+
+fn parse_maps_with_header(data: &str) -> IResult<&str, CategoryMaps> {
     map(
-        tuple((
-            recognize(many1(none_of(" "))),
-            space1,
-            tag("map:"),
-            line_ending,
-            parse_maps,
-        )),
-        |(header, _, _, _, maps)| (header, maps),
+        tuple((parse_header, space1, tag("map:"), line_ending, parse_maps)),
+        |((source, destination), _, _, _, maps)| CategoryMaps {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            maps,
+        },
     )(data)
 }
 
-fn parse_input_data(data: &str) -> IResult<&str, InputData> {
+fn parse_input_data(data: &str) -> IResult<&str, Almanac> {
     map_res(
         tuple((
             parse_seeds,
             many1(line_ending),
             many0(terminated(parse_maps_with_header, opt(line_ending))),
         )),
-        |(seeds, _, maps_and_headers)| {
-            let mut input_data = InputData {
-                seeds,
-                seed_to_soil_maps: Vec::new(),
-                soil_to_fertilizer_maps: Vec::new(),
-                fertilizer_to_water_maps: Vec::new(),
-                water_to_light_maps: Vec::new(),
-                light_to_temperature_maps: Vec::new(),
-                temperature_to_humidity_maps: Vec::new(),
-                humidity_to_location_maps: Vec::new(),
-            };
-
-            for (header, maps) in maps_and_headers {
-                match header {
-                    "seed-to-soil" => input_data.seed_to_soil_maps = maps,
-                    "soil-to-fertilizer" => input_data.soil_to_fertilizer_maps = maps,
-                    "fertilizer-to-water" => input_data.fertilizer_to_water_maps = maps,
-                    "water-to-light" => input_data.water_to_light_maps = maps,
-                    "light-to-temperature" => input_data.light_to_temperature_maps = maps,
-                    "temperature-to-humidity" => input_data.temperature_to_humidity_maps = maps,
-                    "humidity-to-location" => input_data.humidity_to_location_maps = maps,
-                    _ => {
-                        return Err(nom::Err::Error(nom::error::Error::new(
-                            header,
-                            ErrorKind::Tag,
-                        )));
-                    }
+        |(seeds, _, category_maps)| {
+            let mut seen = HashSet::new();
+            for maps in category_maps.iter() {
+                if !seen.insert((&maps.source, &maps.destination)) {
+                    return Err(format!(
+                        "The {}-to-{} maps are there twice",
+                        maps.source, maps.destination
+                    ));
                 }
             }
-
-            return Ok(input_data);
+            Ok(Almanac {
+                seeds,
+                category_maps,
+            })
         },
     )(data)
 }
 
-fn find_location(current_location: u64, maps: &Vec<Map>) -> u64 {
-    for map in maps {
-        if current_location >= map.source_range_start
-            && current_location < map.source_range_start + map.range_length
+impl Almanac {
+    pub fn parse(data: &str) -> Result<Almanac, String> {
+        let almanac = parse_all(parse_input_data, data)?;
+        almanac.check()?;
+        Ok(almanac)
+    }
+
+    // The puzzle promises ranges that fit in a u64, and maps whose
+    // sources don't overlap. The algorithms below rely on it.
+    fn check(&self) -> Result<(), String> {
+        for category_maps in self.category_maps.iter() {
+            let maps = &category_maps.maps;
+            for map in maps.iter() {
+                if map
+                    .source_range_start
+                    .max(map.destination_range_start)
+                    .checked_add(map.range_length)
+                    .is_none()
+                {
+                    return Err(format!("The map {:?} doesn't fit in a u64", map));
+                }
+            }
+            let mut sorted_maps = maps.to_vec();
+            sorted_maps.sort_by_key(|map| map.source_range_start);
+            if sorted_maps.windows(2).any(|window| {
+                window[0].source_range_start + window[0].range_length > window[1].source_range_start
+            }) {
+                return Err("Two maps have overlapping sources".to_string());
+            }
+        }
+        if self
+            .seeds
+            .chunks_exact(2)
+            .any(|seed| seed[0].checked_add(seed[1]).is_none())
         {
-            let offset = current_location - map.source_range_start;
-            return map.destination_range_start + offset;
+            return Err("A range of seeds doesn't fit in a u64".to_string());
         }
+        Ok(())
     }
-    return current_location;
-}
-
-pub fn day_5_part_1(data: &str) -> i64 {
-    let (_, data) = parse_input_data(data).expect("Unable to parse input data");
 
-    data.seeds
-        .iter()
-        .map(|seed| {
-            let seed_location = *seed;
-            let soil_location = find_location(seed_location, &data.seed_to_soil_maps);
-            let fertilizer_location = find_location(soil_location, &data.soil_to_fertilizer_maps);
-            let water_location = find_location(fertilizer_location, &data.fertilizer_to_water_maps);
-            let light_location = find_location(water_location, &data.water_to_light_maps);
-            let temperature_location =
-                find_location(light_location, &data.light_to_temperature_maps);
-            let humidity_location =
-                find_location(temperature_location, &data.temperature_to_humidity_maps);
-            let location = find_location(humidity_location, &data.humidity_to_location_maps);
-            return location;
-        })
-        .min()
-        .unwrap_or(0) as i64
-}
+    pub fn categories(&self) -> Vec<&str> {
+        let mut categories = self
+            .category_maps
+            .iter()
+            .flat_map(|maps| [maps.source.as_str(), maps.destination.as_str()])
+            .collect::<Vec<_>>();
+        categories.sort_unstable();
+        categories.dedup();
+        categories
+    }
 
-// Fills the gaps in the maps, but apparently there is no gap
-// unless at the beginning or at the end.
-// still, I keep it.
-fn expand_maps(maps: &Vec<Map>) -> Vec<Map> {
-    // created a sorted copy of the maps
-    let mut sorted_maps = maps.clone();
-    sorted_maps.sort_by(|a, b| a.source_range_start.cmp(&b.source_range_start));
-
-    // println!("{:?}", sorted_maps);
-
-    let mut expanded_maps = Vec::new();
-
-    let mut current = 0;
-    for map in sorted_maps {
-        if map.source_range_start > current {
-            expanded_maps.push(Map {
-                destination_range_start: current,
-                source_range_start: current,
-                range_length: map.source_range_start - current,
-            });
+    // The shortest chain of maps from a category to another,
+    // found with a breadth-first search.
+    fn chain(&self, from: &str, to: &str) -> Result<Vec<&CategoryMaps>, String> {
+        let mut previous: HashMap<&str, Option<&CategoryMaps>> = HashMap::from([(from, None)]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(category) = queue.pop_front() {
+            if category == to {
+                let mut chain = Vec::new();
+                let mut current = to;
+                while let Some(Some(maps)) = previous.get(current) {
+                    chain.push(*maps);
+                    current = &maps.source;
+                }
+                chain.reverse();
+                return Ok(chain);
+            }
+            for maps in self
+                .category_maps
+                .iter()
+                .filter(|maps| maps.source == category)
+            {
+                if !previous.contains_key(maps.destination.as_str()) {
+                    previous.insert(&maps.destination, Some(maps));
+                    queue.push_back(&maps.destination);
+                }
+            }
         }
-        expanded_maps.push(map);
-        current = map.source_range_start + map.range_length;
+        Err(format!("No maps go from {} to {}", from, to))
     }
 
-    // fill the last gap
-    let max = u64::max_value();
-    if current < max {
-        expanded_maps.push(Map {
-            destination_range_start: current,
-            source_range_start: current,
-            range_length: max - current,
-        });
+    // The maps from a category to another as one function.
+    pub fn function(&self, from: &str, to: &str) -> Result<PiecewiseLinear, String> {
+        let _span = span(Level::Info, "map composition");
+        let composed = self
+            .chain(from, to)?
+            .into_iter()
+            .fold(PiecewiseLinear::identity(), |composed, maps| {
+                composed.then(&PiecewiseLinear::from_maps(&maps.maps))
+            });
+        trace_event!(
+            Level::Debug,
+            "composed the almanac from {} to {} into {} pieces",
+            from,
+            to,
+            composed.pieces().len()
+        );
+        trace_event!(Level::Trace, "{} to {}:\n{}", from, to, composed);
+        Ok(composed)
     }
 
-    // nicer to read
-    expanded_maps.sort_by(|a, b| a.source_range_start.cmp(&b.source_range_start));
-    return expanded_maps;
-}
+    pub fn translate(&self, from: &str, to: &str, value: u64) -> Result<u64, String> {
+        Ok(self.function(from, to)?.apply(value))
+    }
 
-// compute the maps that needs to be used to go from a source to a destination
-// returns from and to
-fn compute_useful_maps(from: &Vec<Map>, to: &Vec<Map>) -> (Vec<Map>, Vec<Map>) {
-    // The concept of the algorithm is to go through the from and to map, and fill
-    // two new lists of maps so there is always no gap.
+    // The values the range goes to, as sorted and disjoint inclusive ranges.
+    pub fn translate_range(
+        &self,
+        from: &str,
+        to: &str,
+        range: (u64, u64),
+    ) -> Result<Vec<(u64, u64)>, String> {
+        Ok(self.function(from, to)?.image(range))
+    }
 
-    // sort from by destination and as a deque
-    let mut sorted_from = from.clone();
-    sorted_from.sort_by(|a, b| a.destination_range_start.cmp(&b.destination_range_start));
-    // sort to by source
-    let mut sorted_to = to.clone();
-    sorted_to.sort_by(|a, b| a.source_range_start.cmp(&b.source_range_start));
+    // The values of the from category that end up in the range of
+    // the to category, like the seeds of some locations.
+    pub fn preimage(
+        &self,
+        from: &str,
+        to: &str,
+        range: (u64, u64),
+    ) -> Result<Vec<(u64, u64)>, String> {
+        Ok(self.function(from, to)?.preimage(range))
+    }
 
-    let mut useful_from = Vec::new();
-    let mut useful_to = Vec::new();
+    pub fn preimage_of(&self, from: &str, to: &str, value: u64) -> Result<Vec<(u64, u64)>, String> {
+        self.preimage(from, to, (value, value))
+    }
 
-    for current_from_map in &sorted_from {
-        let from_destination_start = current_from_map.destination_range_start;
-        let from_destination_end =
-            current_from_map.destination_range_start + current_from_map.range_length;
+    // Goes forward from the value, then back from where it landed:
+    // the value must be among the values that lead there.
+    pub fn round_trips(&self, from: &str, to: &str, value: u64) -> Result<bool, String> {
+        let function = self.function(from, to)?;
+        let destination = function.apply(value);
+        Ok(ranges_contain(
+            &function.preimage((destination, destination)),
+            value,
+        ))
+    }
+}
 
-        let mut splits_to = vec![from_destination_start];
+pub fn day_5_part_1(data: &str) -> Result<i64, String> {
+    let almanac = Almanac::parse(data)?;
+    let seed_to_location = almanac.function("seed", "location")?;
 
-        for current_to_map in &sorted_to {
-            let to_source_start = current_to_map.source_range_start;
+    let lowest = almanac
+        .seeds
+        .iter()
+        .map(|&seed| seed_to_location.apply(seed))
+        .min()
+        .unwrap_or(0);
+    i64::try_from(lowest).map_err(|_| "The location is too big".to_string())
+}
 
-            if to_source_start >= from_destination_start && to_source_start < from_destination_end {
-                splits_to.push(to_source_start);
-            }
-        }
+// A piece of a piecewise-linear function over the u64 values. It goes from
+// its start to the start of the next piece, or to u64::MAX for the last one,
+// and moves the values to its destination without changing their order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Piece {
+    pub start: u64,
+    pub destination: u64,
+}
 
-        splits_to.push(from_destination_end);
+// The maps of the almanac as a function defined everywhere, the values
+// outside the maps going to themselves. Its pieces are sorted, the first
+// one starts at 0, and a piece never just continues the previous one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiecewiseLinear {
+    pieces: Vec<Piece>,
+}
 
-        let mut current_source_start = current_from_map.source_range_start;
+impl PiecewiseLinear {
+    pub fn identity() -> PiecewiseLinear {
+        PiecewiseLinear {
+            pieces: vec![Piece {
+                start: 0,
+                destination: 0,
+            }],
+        }
+    }
 
-        splits_to.windows(2).for_each(|window| {
-            let a = window[0];
-            let b = window[1];
-            if a == b {
-                // ignore duplicates
-                return;
+    // The maps must have been checked before, they don't overlap
+    // and fit in a u64.
+    fn from_maps(maps: &[Map]) -> PiecewiseLinear {
+        let mut sorted_maps = maps.to_vec();
+        sorted_maps.sort_by_key(|map| map.source_range_start);
+
+        let mut pieces = Vec::new();
+        let mut current = 0;
+        for map in sorted_maps.iter().filter(|map| map.range_length > 0) {
+            if map.source_range_start > current || pieces.is_empty() {
+                pieces.push(Piece {
+                    start: current,
+                    destination: current,
+                });
             }
-            let range_length = b - a;
-            useful_from.push(Map {
-                destination_range_start: a,
-                source_range_start: current_source_start,
-                range_length,
+            pieces.push(Piece {
+                start: map.source_range_start,
+                destination: map.destination_range_start,
             });
-            current_source_start += range_length;
+            current = map.source_range_start + map.range_length;
+        }
+        // the values after the last map go to themselves, and a map
+        // at 0 replaces the empty gap before it when merging
+        pieces.push(Piece {
+            start: current,
+            destination: current,
         });
+        PiecewiseLinear::merge(pieces)
     }
 
-    for current_to_map in &sorted_to {
-        let to_source_start = current_to_map.source_range_start;
-        let to_source_end = current_to_map.source_range_start + current_to_map.range_length;
-
-        let mut splits_from = vec![to_source_start];
-
-        for current_from_map in &sorted_from {
-            let from_destination_start = current_from_map.destination_range_start;
-
-            if from_destination_start >= to_source_start && from_destination_start < to_source_end {
-                splits_from.push(from_destination_start);
+    // Merges the pieces that continue the previous one,
+    // and keeps the last of the pieces starting at the same value.
+    fn merge(pieces: Vec<Piece>) -> PiecewiseLinear {
+        let mut merged: Vec<Piece> = Vec::with_capacity(pieces.len());
+        for piece in pieces {
+            match merged.last_mut() {
+                Some(last) if last.start == piece.start => *last = piece,
+                Some(last)
+                    if last.destination.checked_add(piece.start - last.start)
+                        == Some(piece.destination) => {}
+                _ => merged.push(piece),
             }
         }
+        PiecewiseLinear { pieces: merged }
+    }
 
-        splits_from.push(to_source_end);
+    pub fn pieces(&self) -> &[Piece] {
+        &self.pieces
+    }
 
-        let mut current_destination_start = current_to_map.destination_range_start;
+    // The last value of the piece.
+    fn end(&self, index: usize) -> u64 {
+        match self.pieces.get(index + 1) {
+            Some(next) => next.start - 1,
+            None => u64::MAX,
+        }
+    }
 
-        splits_from.windows(2).for_each(|window| {
-            let a = window[0];
-            let b = window[1];
-            if a == b {
-                // ignore duplicates
-                return;
-            }
-            let range_length = b - a;
-            useful_to.push(Map {
-                destination_range_start: current_destination_start,
-                source_range_start: a,
-                range_length,
-            });
-            current_destination_start += range_length;
-        });
+    pub fn apply(&self, value: u64) -> u64 {
+        let index = self.pieces.partition_point(|piece| piece.start <= value) - 1;
+        self.apply_in(index, value)
     }
 
-    return (useful_from, useful_to);
-}
+    // The function that applies self, then next.
+    pub fn then(&self, next: &PiecewiseLinear) -> PiecewiseLinear {
+        let mut pieces = Vec::new();
+        for (index, piece) in self.pieces.iter().enumerate() {
+            let image_start = piece.destination;
+            let image_end = piece.destination + (self.end(index) - piece.start);
+            // the pieces of next that the image of the piece goes through
+            let first = next
+                .pieces
+                .partition_point(|other| other.start <= image_start)
+                - 1;
+            for other in next.pieces[first..]
+                .iter()
+                .take_while(|other| other.start <= image_end)
+            {
+                let from = other.start.max(image_start);
+                pieces.push(Piece {
+                    start: piece.start + (from - image_start),
+                    destination: other.destination + (from - other.start),
+                });
+            }
+        }
+        PiecewiseLinear::merge(pieces)
+    }
 
-fn compute_useful_maps_for_all_couples(couples: &Vec<Vec<Map>>) -> Vec<Vec<Map>> {
-    let mut couples = couples.clone();
-    loop {
-        let mut has_a_change = false;
-        for i in 0..couples.len() - 1 {
-            let from = couples[i].clone();
-            let to = couples[i + 1].clone();
-            let (new_from, new_to) = compute_useful_maps(&from, &to);
-            if new_from.len() != from.len() {
-                couples[i] = new_from;
-                has_a_change = true;
+    // The smallest value of the function over the ranges, inclusive.
+    // Each piece is increasing, so only the first value of the ranges
+    // in a piece can be the smallest. The ranges are sorted and swept
+    // along the pieces.
+    pub fn lowest_over(&self, ranges: &[(u64, u64)]) -> Option<u64> {
+        let mut ranges = ranges.to_vec();
+        ranges.sort_unstable();
+        let mut index = 0;
+        let mut lowest: Option<u64> = None;
+        for (start, end) in ranges {
+            // the values before the current piece were in a previous range
+            let start = start.max(self.pieces[index].start);
+            if start > end {
+                continue;
             }
-            if new_to.len() != to.len() {
-                couples[i + 1] = new_to;
-                has_a_change = true;
+            while self.end(index) < start {
+                index += 1;
+            }
+            let mut value = start;
+            loop {
+                let candidate = self.apply_in(index, value);
+                lowest = Some(lowest.map_or(candidate, |lowest| lowest.min(candidate)));
+                if self.end(index) >= end {
+                    break;
+                }
+                index += 1;
+                value = self.pieces[index].start;
             }
         }
-        if !has_a_change {
-            break;
+        lowest
+    }
+
+    // The values of the inclusive range go to these sorted and disjoint
+    // inclusive ranges.
+    pub fn image(&self, (start, end): (u64, u64)) -> Vec<(u64, u64)> {
+        if start > end {
+            return Vec::new();
         }
+        let first = self.pieces.partition_point(|piece| piece.start <= start) - 1;
+        let image = (first..self.pieces.len())
+            .take_while(|&index| self.pieces[index].start <= end)
+            .map(|index| {
+                let from = start.max(self.pieces[index].start);
+                let to = end.min(self.end(index));
+                (self.apply_in(index, from), self.apply_in(index, to))
+            })
+            .collect::<Vec<_>>();
+        merge_ranges(image)
+    }
+
+    // The values that go into the inclusive range, as sorted and disjoint
+    // inclusive ranges. The destinations of the maps may overlap,
+    // so several pieces can go to the same values.
+    pub fn preimage(&self, (start, end): (u64, u64)) -> Vec<(u64, u64)> {
+        let preimage = (0..self.pieces.len())
+            .filter_map(|index| {
+                let piece = self.pieces[index];
+                let image_end = piece.destination + (self.end(index) - piece.start);
+                let from = start.max(piece.destination);
+                let to = end.min(image_end);
+                (from <= to).then(|| {
+                    (
+                        piece.start + (from - piece.destination),
+                        piece.start + (to - piece.destination),
+                    )
+                })
+            })
+            .collect();
+        merge_ranges(preimage)
+    }
+
+    fn apply_in(&self, index: usize, value: u64) -> u64 {
+        let piece = self.pieces[index];
+        piece.destination + (value - piece.start)
     }
-    return couples;
 }
 
-fn has_road(start_place: u64, destinations: &Vec<HashMap<u64, u64>>) -> bool {
-    let mut current_place = start_place;
-    for destination in destinations.iter().rev() {
-        if let Some(next_place) = destination.get(&current_place) {
-            current_place = *next_place;
-        } else {
-            return false;
+// Sorts the inclusive ranges and merges those that overlap or touch.
+fn merge_ranges(mut ranges: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if last.1.checked_add(1).is_none_or(|next| next >= start) => {
+                last.1 = last.1.max(end);
+            }
+            _ => merged.push((start, end)),
         }
     }
-    return true;
+    merged
 }
 
-fn find_lowest_location(destinations: &Vec<HashMap<u64, u64>>) -> Result<u64, &str> {
-    let last_destination = destinations.last();
-    if last_destination.is_none() {
-        return Err("No destination");
-    }
-    let mut last_destinations_vec: Vec<u64> = last_destination.unwrap().keys().cloned().collect();
-    // sort to save CPU time
-    last_destinations_vec.sort();
-    for destination in last_destinations_vec {
-        if has_road(destination, destinations) {
-            return Ok(destination);
+fn ranges_contain(ranges: &[(u64, u64)], value: u64) -> bool {
+    let index = ranges.partition_point(|&(_, end)| end < value);
+    ranges.get(index).is_some_and(|&(start, _)| start <= value)
+}
+
+impl fmt::Display for PiecewiseLinear {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, piece) in self.pieces.iter().enumerate() {
+            let end = self.end(index);
+            writeln!(
+                f,
+                "[{}, {}] -> [{}, {}]",
+                piece.start,
+                end,
+                piece.destination,
+                piece.destination + (end - piece.start)
+            )?;
         }
+        Ok(())
     }
-    return Err("No destination");
 }
 
-pub fn day_5_part_2(data: &str) -> i64 {
-    let (_, data) = parse_input_data(data).expect("Unable to parse input data");
+pub fn day_5_part_2(data: &str) -> Result<i64, String> {
+    let almanac = Almanac::parse(data)?;
 
-    let seeds_as_maps = data
+    // the ranges of seeds are checked, they don't overflow
+    let seed_ranges = almanac
         .seeds
         .chunks_exact(2)
-        .map(|seed| Map {
-            destination_range_start: seed[0],
-            source_range_start: 0xdeadbeef,
-            range_length: seed[1],
-        })
-        .collect::<Vec<Map>>();
-
-    let maps = vec![
-        data.seed_to_soil_maps,
-        data.soil_to_fertilizer_maps,
-        data.fertilizer_to_water_maps,
-        data.water_to_light_maps,
-        data.light_to_temperature_maps,
-        data.temperature_to_humidity_maps,
-        data.humidity_to_location_maps,
-    ];
-
-    let expanded_maps = maps
-        .iter()
-        .map(|maps| expand_maps(maps))
-        .collect::<Vec<Vec<Map>>>();
-
-    let mut couples = vec![
-        // the seeds are not expanded
-        seeds_as_maps,
-    ];
-    couples.extend(expanded_maps.into_iter());
-
-    let new_couples = compute_useful_maps_for_all_couples(&couples);
-
-    let destinations_as_dictionary = new_couples
-        .iter()
-        .map(|maps| {
-            maps.iter()
-                .map(|map| (map.destination_range_start, map.source_range_start))
-                .collect::<HashMap<u64, u64>>()
-        })
-        .collect::<Vec<HashMap<u64, u64>>>();
-
-    let smallest = find_lowest_location(&destinations_as_dictionary);
-    return smallest.unwrap() as i64;
+        .filter(|seed| seed[1] > 0)
+        .map(|seed| (seed[0], seed[0] + seed[1] - 1))
+        .collect::<Vec<_>>();
+
+    let lowest = almanac
+        .function("seed", "location")?
+        .lowest_over(&seed_ranges)
+        .ok_or("No destination")?;
+    i64::try_from(lowest).map_err(|_| "The location is too big".to_string())
 }
 
 #[cfg(test)]
@@ -456,96 +596,174 @@ humidity-to-location map:
 60 56 37
 56 93 4";
 
-    /*fn pretty_print_maps(label: &str, maps: &Vec<Map>) {
-        println!("{} map:", label);
-        for map in maps {
-            println!(
-                "{} {} {}",
-                map.destination_range_start, map.source_range_start, map.range_length
-            );
-        }
-    }*/
-
     #[test]
     fn test_day_5_part_1() {
-        assert_eq!(day_5_part_1(EXAMPLE), 35);
+        assert_eq!(day_5_part_1(EXAMPLE), Ok(35));
+        assert_eq!(
+            day_5_part_1("seeds: 9223372036854775808\n\nseed-to-location map:\n1 2 3"),
+            Err("The location is too big".to_string())
+        );
     }
 
     #[test]
     fn test_day_5_part_2() {
-        assert_eq!(day_5_part_2(EXAMPLE), 46);
+        assert_eq!(day_5_part_2(EXAMPLE), Ok(46));
+        assert!(day_5_part_2("seeds: 1 2\n\nseed-to-soil map:\n0 1 18446744073709551615").is_err());
+        assert!(day_5_part_2("seeds: 1 2\n\nseed-to-soil map:\n0 1 5\n10 3 2").is_err());
     }
 
     #[test]
-    fn test_expand_maps_1() {
-        let expanded = expand_maps(&vec![
-            Map {
-                destination_range_start: 60,
-                source_range_start: 56,
-                range_length: 37,
-            },
-            Map {
-                destination_range_start: 56,
-                source_range_start: 93,
-                range_length: 4,
-            },
-        ]);
-
-        assert!(expanded.iter().eq(&vec![
-            Map {
-                destination_range_start: 0,
-                source_range_start: 0,
-                range_length: 56
-            },
-            Map {
-                destination_range_start: 60,
-                source_range_start: 56,
-                range_length: 37
-            },
-            Map {
-                destination_range_start: 56,
-                source_range_start: 93,
-                range_length: 4
-            },
-            Map {
-                destination_range_start: 97,
-                source_range_start: 97,
-                range_length: 18446744073709551518
-            },
-        ]));
+    fn test_piecewise_linear() {
+        let almanac = Almanac::parse(EXAMPLE).unwrap();
+        let humidity_to_location = almanac.function("humidity", "location").unwrap();
+        assert_eq!(
+            humidity_to_location.to_string(),
+            "[0, 55] -> [0, 55]
+[56, 92] -> [60, 96]
+[93, 96] -> [56, 59]
+[97, 18446744073709551615] -> [97, 18446744073709551615]
+"
+        );
+        // the map at 0 replaces the empty gap before it
+        let temperature_to_humidity = almanac.function("temperature", "humidity").unwrap();
+        assert_eq!(temperature_to_humidity.pieces().len(), 3);
+        assert_eq!(temperature_to_humidity.apply(69), 0);
+        assert_eq!(temperature_to_humidity.apply(68), 69);
+        assert_eq!(temperature_to_humidity.apply(70), 70);
+
+        let seed_to_location = almanac.function("seed", "location").unwrap();
+        for (seed, location) in [(79, 82), (14, 43), (55, 86), (13, 35)] {
+            assert_eq!(seed_to_location.apply(seed), location);
+        }
+        // the composed function gives the same answers as the maps one by one
+        for seed in 0..200 {
+            let location = almanac.category_maps.iter().fold(seed, |value, maps| {
+                maps.maps
+                    .iter()
+                    .find(|map| {
+                        map.source_range_start <= value
+                            && value - map.source_range_start < map.range_length
+                    })
+                    .map_or(value, |map| {
+                        map.destination_range_start + (value - map.source_range_start)
+                    })
+            });
+            assert_eq!(seed_to_location.apply(seed), location);
+        }
+        assert_eq!(seed_to_location.apply(u64::MAX), u64::MAX);
+        assert_eq!(
+            seed_to_location.lowest_over(&[(79, 92), (55, 67)]),
+            Some(46)
+        );
+        assert_eq!(seed_to_location.lowest_over(&[(0, u64::MAX)]), Some(0));
+        assert_eq!(seed_to_location.lowest_over(&[]), None);
+        assert_eq!(
+            seed_to_location.lowest_over(&[(10, 100), (20, 30), (50, 60)]),
+            seed_to_location.lowest_over(&[(10, 100)])
+        );
+
+        assert_eq!(PiecewiseLinear::from_maps(&[]), PiecewiseLinear::identity());
+        let at_zero = PiecewiseLinear::from_maps(&[Map {
+            destination_range_start: 5,
+            source_range_start: 0,
+            range_length: 2,
+        }]);
+        assert_eq!(
+            at_zero.pieces(),
+            &[
+                Piece {
+                    start: 0,
+                    destination: 5
+                },
+                Piece {
+                    start: 2,
+                    destination: 2
+                }
+            ]
+        );
     }
 
     #[test]
-    fn test_expand_maps_2() {
-        let expanded = expand_maps(&vec![
-            Map {
-                destination_range_start: 0,
-                source_range_start: 69,
-                range_length: 1,
-            },
-            Map {
-                destination_range_start: 1,
-                source_range_start: 0,
-                range_length: 69,
-            },
-        ]);
-
-        assert!(expanded.iter().eq(&vec![
-            Map {
-                destination_range_start: 1,
-                source_range_start: 0,
-                range_length: 69
-            },
-            Map {
-                destination_range_start: 0,
-                source_range_start: 69,
-                range_length: 1
-            },
-            Map {
-                destination_range_start: 70,
-                source_range_start: 70,
-                range_length: 18446744073709551545
-            },
-        ]));
+    fn test_almanac() {
+        // the maps in any order, and other categories
+        let shuffled = "seeds: 1 2
+
+water-to-light map:
+10 0 5
+
+seed-to-water map:
+3 0 2
+0 2 3
+
+seed-to-colour map:
+0 0 1";
+        let almanac = Almanac::parse(shuffled).unwrap();
+        assert_eq!(
+            almanac.categories(),
+            vec!["colour", "light", "seed", "water"]
+        );
+        assert_eq!(almanac.translate("seed", "light", 0), Ok(13));
+        assert_eq!(almanac.translate("seed", "light", 2), Ok(10));
+        assert_eq!(almanac.translate("seed", "light", 5), Ok(5));
+        assert_eq!(almanac.translate("water", "water", 7), Ok(7));
+        assert!(almanac.translate("light", "seed", 0).is_err());
+        assert!(almanac.translate("seed", "location", 0).is_err());
+        assert!(day_5_part_1(shuffled).is_err());
+        assert_eq!(
+            almanac.translate_range("seed", "light", (0, 6)),
+            Ok(vec![(5, 6), (10, 14)])
+        );
+
+        let almanac = Almanac::parse(EXAMPLE).unwrap();
+        assert_eq!(almanac.translate("soil", "humidity", 81), Ok(78));
+        assert_eq!(
+            almanac.translate_range("seed", "soil", (79, 92)),
+            Ok(vec![(81, 94)])
+        );
+        assert_eq!(
+            almanac.translate_range("seed", "soil", (95, 101)),
+            Ok(vec![(50, 51), (97, 101)])
+        );
+
+        assert!(Almanac::parse("seeds: 1\n\na-to-b map:\n1 2 3\n\na-to-b map:\n4 5 6").is_err());
+        assert!(Almanac::parse("seeds: 1\n\nsoil map:\n1 2 3").is_err());
+    }
+
+    #[test]
+    fn test_preimage() {
+        let almanac = Almanac::parse(EXAMPLE).unwrap();
+        // the seed of the lowest location of part 2
+        assert_eq!(
+            almanac.preimage_of("seed", "location", 46),
+            Ok(vec![(82, 82)])
+        );
+        assert_eq!(almanac.preimage_of("seed", "soil", 50), Ok(vec![(98, 98)]));
+        assert_eq!(almanac.preimage_of("seed", "soil", 98), Ok(vec![(96, 96)]));
+        assert!(almanac.preimage_of("location", "seed", 46).is_err());
+
+        let seeds = almanac.preimage("seed", "location", (0, 45)).unwrap();
+        let seed_to_location = almanac.function("seed", "location").unwrap();
+        for seed in 0..200 {
+            let location = seed_to_location.apply(seed);
+            assert_eq!(ranges_contain(&seeds, seed), location <= 45);
+            assert_eq!(almanac.round_trips("seed", "location", seed), Ok(true));
+        }
+        assert_eq!(almanac.round_trips("seed", "location", u64::MAX), Ok(true));
+        // the preimage of everything is everything
+        assert_eq!(
+            almanac.preimage("seed", "location", (0, u64::MAX)),
+            Ok(vec![(0, u64::MAX)])
+        );
+
+        // two maps going to the same values
+        let function = PiecewiseLinear::from_maps(&[Map {
+            destination_range_start: 0,
+            source_range_start: 10,
+            range_length: 5,
+        }]);
+        assert_eq!(function.preimage((2, 3)), vec![(2, 3), (12, 13)]);
+        assert_eq!(function.preimage((3, 11)), vec![(3, 9), (13, 14)]);
+        // nothing goes where the map used to be
+        assert_eq!(function.preimage((10, 14)), vec![]);
     }
 }
@@ -22,50 +22,54 @@
 */
 
 use nom::{
-    self,
-    bytes::complete::tag,
     character::complete::{digit1, line_ending, space1},
-    combinator::opt,
-    combinator::{map, map_res},
+    combinator::map_res,
     multi::separated_list1,
-    sequence::tuple,
-    IResult,
+    sequence::separated_pair,
 };
 
+use crate::number_theory::checked_product;
+use crate::parsing::{header, parse_all, spaced_u64s, IResult};
+
 fn parse_input_data_part_1(data: &str) -> IResult<&str, (Vec<u64>, Vec<u64>)> {
-    map(
-        tuple((
-            tag("Time:"),
-            space1,
-            separated_list1(space1, nom::character::complete::u64),
-            line_ending,
-            tag("Distance:"),
-            space1,
-            separated_list1(space1, nom::character::complete::u64),
-            opt(line_ending),
-        )),
-        |(_, _, times, _, _, _, distances, _)| (times, distances),
+    separated_pair(
+        header("Time", spaced_u64s),
+        line_ending,
+        header("Distance", spaced_u64s),
     )(data)
 }
 
 #[inline]
 fn compute_many_ways_to_win(time: u64, distance: u64) -> u64 {
+    // the square root below doesn't exist when the record can't be beaten
+    let best_distance = (time / 2) as u128 * (time - time / 2) as u128;
+    if best_distance <= distance as u128 {
+        return 0;
+    }
     let time = time as f64;
     let distance = distance as f64;
     let common_sqrt = (time * time - 4_f64 * distance).sqrt();
     let first = ((time - common_sqrt) / 2_f64 + f64::EPSILON).floor() as u64;
     let second = ((time + common_sqrt) / 2_f64 - f64::EPSILON).ceil() as u64;
-    return second - first - 1;
+    // the epsilons don't help much with the big numbers
+    return second.saturating_sub(first).saturating_sub(1);
 }
 
-pub fn day_6_part_1(data: &str) -> i64 {
-    let (_, data) = parse_input_data_part_1(data).expect("Unable to parse input data");
+pub fn day_6_part_1(data: &str) -> Result<i64, String> {
+    let data = parse_all(parse_input_data_part_1, data)?;
     let (times, distances) = data;
-    times
+    if times.len() != distances.len() {
+        return Err("There must be as many times as distances".to_string());
+    }
+    let ways = times
         .into_iter()
         .zip(distances.into_iter())
-        .map(|(time, distance)| compute_many_ways_to_win(time, distance))
-        .fold(1, |acc, x| acc * x) as i64
+        .map(|(time, distance)| {
+            i64::try_from(compute_many_ways_to_win(time, distance))
+                .map_err(|_| "Too many ways to win".to_string())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    checked_product(ways)
 }
 
 fn parse_number_with_random_spaces(input: &str) -> IResult<&str, u64> {
@@ -75,25 +79,18 @@ fn parse_number_with_random_spaces(input: &str) -> IResult<&str, u64> {
 }
 
 fn parse_input_data_part_2(data: &str) -> IResult<&str, (u64, u64)> {
-    map(
-        tuple((
-            tag("Time:"),
-            space1,
-            parse_number_with_random_spaces,
-            line_ending,
-            tag("Distance:"),
-            space1,
-            parse_number_with_random_spaces,
-            opt(line_ending),
-        )),
-        |(_, _, time, _, _, _, distance, _)| (time, distance),
+    separated_pair(
+        header("Time", parse_number_with_random_spaces),
+        line_ending,
+        header("Distance", parse_number_with_random_spaces),
     )(data)
 }
 
-pub fn day_6_part_2(data: &str) -> i64 {
-    let (_, (time, distance)) = parse_input_data_part_2(data).expect("Unable to parse input data");
+pub fn day_6_part_2(data: &str) -> Result<i64, String> {
+    let (time, distance) = parse_all(parse_input_data_part_2, data)?;
 
-    compute_many_ways_to_win(time, distance) as i64
+    i64::try_from(compute_many_ways_to_win(time, distance))
+        .map_err(|_| "Too many ways to win".to_string())
 }
 
 #[cfg(test)]
@@ -105,11 +102,17 @@ Distance:  9  40  200";
 
     #[test]
     fn test_day_6_part_1() {
-        assert_eq!(day_6_part_1(EXAMPLE), 288);
+        assert_eq!(day_6_part_1(EXAMPLE), Ok(288));
+        // a record that can't be beaten
+        assert_eq!(day_6_part_1("Time: 4\nDistance: 4"), Ok(0));
+        // the number of ways doesn't fit in an i64
+        assert!(day_6_part_1("Time: 18446744073709551615\nDistance: 1").is_err());
+        assert!(day_6_part_1("Time: 1 2\nDistance: 0").is_err());
     }
 
     #[test]
     fn test_day_6_part_2() {
-        assert_eq!(day_6_part_2(EXAMPLE), 71503);
+        assert_eq!(day_6_part_2(EXAMPLE), Ok(71503));
+        assert!(day_6_part_2("Time: 18446744073709551615\nDistance: 1").is_err());
     }
 }
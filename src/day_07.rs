@@ -7,9 +7,11 @@ use nom::{
     combinator::{map, map_res},
     multi::{count, separated_list0},
     sequence::tuple,
-    IResult,
 };
 
+use crate::number_theory::checked_sum;
+use crate::parsing::{parse_all, IResult};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 enum HandType {
     HighCard = 0,
@@ -290,23 +292,26 @@ fn parse_input_data(data: &str) -> IResult<&str, Vec<(Hand, u64)>> {
     separated_list0(line_ending, parse_hand_and_bid)(data)
 }
 
-fn compute_total_winnings(mut hands: Vec<(Hand, u64)>) -> i64 {
+fn compute_total_winnings(mut hands: Vec<(Hand, u64)>) -> Result<i64, String> {
     hands.sort_by(|(hand, _), (other_hand, _)| hand.cmp(other_hand));
 
-    hands
+    let winnings = hands
         .iter()
         .enumerate()
         .map(|(index, (_, bid))| {
             let rank = index + 1;
-            return rank as i64 * *bid as i64;
+            return i64::try_from(*bid)
+                .ok()
+                .and_then(|bid| bid.checked_mul(rank as i64))
+                .ok_or_else(|| format!("The bid {} is too big", bid));
         })
-        .sum()
+        .collect::<Result<Vec<_>, String>>()?;
+    checked_sum(winnings)
 }
 
-pub fn day_7_part_1(data: &str) -> i64 {
-    let data = parse_input_data(data).expect("Unable to parse input data");
+pub fn day_7_part_1(data: &str) -> Result<i64, String> {
+    let mut hands = parse_all(parse_input_data, data)?;
 
-    let mut hands = data.1.clone();
     for (hand, _) in hands.iter_mut() {
         hand.compute_hand_type_part_1();
     }
@@ -314,10 +319,9 @@ pub fn day_7_part_1(data: &str) -> i64 {
     compute_total_winnings(hands)
 }
 
-pub fn day_7_part_2(data: &str) -> i64 {
-    let data = parse_input_data(data).expect("Unable to parse input data");
+pub fn day_7_part_2(data: &str) -> Result<i64, String> {
+    let mut hands = parse_all(parse_input_data, data)?;
 
-    let mut hands = data.1.clone();
     for (hand, _) in hands.iter_mut() {
         hand.to_part_2();
         hand.compute_hand_type_part_2();
@@ -344,12 +348,12 @@ QQQJA 483";
 
     #[test]
     fn test_day_7_part_1() {
-        assert_eq!(day_7_part_1(EXAMPLE), 6440);
+        assert_eq!(day_7_part_1(EXAMPLE), Ok(6440));
     }
 
     #[test]
     fn test_day_7_part_2() {
-        assert_eq!(day_7_part_2(EXAMPLE), 5905);
+        assert_eq!(day_7_part_2(EXAMPLE), Ok(5905));
     }
 
     #[test]
@@ -394,37 +398,37 @@ AAKQJ 31
 22345 37
 AKQJT 41
 23456 43";
-        assert_eq!(day_7_part_1(EXTRA), 1343);
-        assert_eq!(day_7_part_2(EXTRA), 1369);
+        assert_eq!(day_7_part_1(EXTRA), Ok(1343));
+        assert_eq!(day_7_part_2(EXTRA), Ok(1369));
     }
 
     #[test]
     fn test_broken_case() {
         const BROKEN: &str = "22334 2\nAAQKJ 1";
-        assert_eq!(day_7_part_2(BROKEN), 4);
+        assert_eq!(day_7_part_2(BROKEN), Ok(4));
     }
 
     #[test]
     fn test_other_broken_case() {
         const BROKEN: &str = "23456 2\nAKQJT 1";
-        assert_eq!(day_7_part_2(BROKEN), 4);
+        assert_eq!(day_7_part_2(BROKEN), Ok(4));
     }
 
     #[test]
     fn test_jjjjj() {
         const BROKEN: &str = "23456 2\nJJJJJ 1";
-        assert_eq!(day_7_part_2(BROKEN), 4);
+        assert_eq!(day_7_part_2(BROKEN), Ok(4));
     }
 
     #[test]
     fn test_qqjja() {
         const BROKEN: &str = "QQQQ3 2\nQQJJA 1";
-        assert_eq!(day_7_part_2(BROKEN), 5);
+        assert_eq!(day_7_part_2(BROKEN), Ok(5));
     }
 
     #[test]
     fn test_jjjja() {
         const BROKEN: &str = "QQQQ3 2\nJJJJA 1";
-        assert_eq!(day_7_part_2(BROKEN), 4);
+        assert_eq!(day_7_part_2(BROKEN), Ok(4));
     }
 }
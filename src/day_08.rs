@@ -6,28 +6,34 @@
     The 3 characters letters will be converted to a number because it's going to be faster.
 */
 
+use std::collections::{BTreeSet, HashMap};
+
 use nom::{
-    bytes::complete::tag,
     character::complete::{line_ending, one_of, satisfy},
-    combinator::{map, map_res},
+    combinator::{map, map_opt, map_res},
     multi::{count, many_till, separated_list0},
     sequence::{preceded, tuple},
-    IResult,
 };
 
+use crate::dot::{DotGraph, ToDot, ACCEPT_COLOUR, START_COLOUR};
+use crate::number_theory;
+use crate::parsing::{parse_all, tag, IResult};
+
 type NodeName = u16;
 
-fn letters_to_number(letters: [char; 3]) -> NodeName {
+// None if a letter isn't between A and Z.
+fn letters_to_number(letters: [char; 3]) -> Option<NodeName> {
     let mut number = 0;
     for letter in letters.iter() {
         number <<= 5;
-        number |= *letter as u16 - 65;
+        number |= (*letter as u16)
+            .checked_sub(65)
+            .filter(|&letter| letter < 26)?;
     }
-    number
+    Some(number)
 }
 
 // For debugging purposes
-#[allow(dead_code)]
 fn number_to_letters(number: NodeName) -> [char; 3] {
     let mut letters = ['A'; 3];
     let mut number = number;
@@ -39,7 +45,7 @@ fn number_to_letters(number: NodeName) -> [char; 3] {
 }
 
 fn parse_node_name(data: &str) -> IResult<&str, NodeName> {
-    map(
+    map_opt(
         count(satisfy(|c: char| c.is_ascii_uppercase()), 3),
         |letters: Vec<char>| letters_to_number([letters[0], letters[1], letters[2]]),
     )(data)
@@ -114,13 +120,17 @@ fn compute_number_of_iterations(
     directions: &[Direction],
     start: NodeName,
     ending_nodes: &[bool; MAX_NODES],
-) -> i64 {
+    max_iterations: i64,
+) -> Result<i64, String> {
     let mut current_node = start;
     let mut nb_iterations = 0_i64;
     let mut directions_index = 0_usize;
     let directions_len = directions.len();
     loop {
         nb_iterations += 1;
+        if nb_iterations > max_iterations {
+            return Err("The path never reaches the end".to_string());
+        }
         let direction = &directions[directions_index];
 
         // Loop on the directions
@@ -136,12 +146,20 @@ fn compute_number_of_iterations(
         }
     }
 
-    nb_iterations
+    Ok(nb_iterations)
 }
 
-pub fn day_8_part_1(data: &str) -> i64 {
-    let (_, data) = parse_input_data(data).expect("Failed to parse input data");
-    let (directions, nodes) = data;
+// The directions can't be empty, we would go nowhere.
+fn parse_and_check(data: &str) -> Result<(Vec<Direction>, Vec<Node>), String> {
+    let (directions, nodes) = parse_all(parse_input_data, data)?;
+    if directions.is_empty() {
+        return Err("There are no directions".to_string());
+    }
+    Ok((directions, nodes))
+}
+
+pub fn day_8_part_1(data: &str) -> Result<i64, String> {
+    let (directions, nodes) = parse_and_check(data)?;
     let dictionary = build_dictionary(&nodes);
 
     // Build a dictionary of ending nodes
@@ -149,7 +167,19 @@ pub fn day_8_part_1(data: &str) -> i64 {
     let mut ending_nodes: [bool; MAX_NODES] = [false; MAX_NODES];
     ending_nodes[LAST_NODE as usize] = true;
 
-    compute_number_of_iterations(&dictionary, &directions, FIRST_NODE, &ending_nodes)
+    // Past this, we are walking in circles: there are at most 3 node names
+    // per node, the unknown ones lead to AAA, and as many positions in the directions.
+    let max_iterations = (nodes.len() as i64)
+        .saturating_mul(3)
+        .saturating_add(1)
+        .saturating_mul(directions.len() as i64);
+    compute_number_of_iterations(
+        &dictionary,
+        &directions,
+        FIRST_NODE,
+        &ending_nodes,
+        max_iterations,
+    )
 }
 
 /**
@@ -177,31 +207,130 @@ fn node_ends_with_z(node: NodeName) -> bool {
     return node & 31 == 25;
 }
 
-fn gcd(mut a: u64, mut b: u64) -> u64 {
-    while b != 0 {
-        let t = b;
-        b = a % b;
-        a = t;
+/*
+    The lcm only works because the input is crafted so that each ghost reaches
+    its Z node for the first time after exactly one cycle length. In general,
+    a ghost walks a prefix and then loops forever, as there is a finite number
+    of (node, position in the directions) states, and it can meet several
+    Z nodes on the way. So we find the cycles, and solve the congruences
+    with the Chinese remainder theorem.
+*/
+#[derive(Debug)]
+struct GhostCycle {
+    // Steps on an ending node before the cycle starts.
+    prefix_hits: Vec<u64>,
+    // Steps on an ending node during the first turn of the cycle.
+    cycle_hits: Vec<u64>,
+    cycle_start: u64,
+    cycle_length: u64,
+}
+
+impl GhostCycle {
+    fn is_on_ending_node(&self, step: u64) -> bool {
+        if step < self.cycle_start {
+            return self.prefix_hits.contains(&step);
+        }
+        // compared from the start of the cycle, so nothing is added up
+        let offset = (step - self.cycle_start) % self.cycle_length;
+        self.cycle_hits
+            .iter()
+            .any(|&hit| hit - self.cycle_start == offset)
     }
-    a
 }
 
-#[inline]
-fn lcm(a: u64, b: u64) -> u64 {
-    a / gcd(a, b) * b
+fn find_ghost_cycle(
+    dictionary: &[(NodeName, NodeName); MAX_NODES],
+    directions: &[Direction],
+    start: NodeName,
+    ending_nodes: &[bool; MAX_NODES],
+) -> GhostCycle {
+    let mut first_seen: HashMap<(NodeName, usize), u64> = HashMap::new();
+    let mut hits = Vec::new();
+    let mut current_node = start;
+    let mut step = 0_u64;
+    let directions_len = directions.len();
+
+    loop {
+        let directions_index = (step % directions_len as u64) as usize;
+        if let Some(&cycle_start) = first_seen.get(&(current_node, directions_index)) {
+            let (prefix_hits, cycle_hits) = hits.iter().partition(|&&hit| hit < cycle_start);
+            return GhostCycle {
+                prefix_hits,
+                cycle_hits,
+                cycle_start,
+                cycle_length: step - cycle_start,
+            };
+        }
+        first_seen.insert((current_node, directions_index), step);
+
+        // The starting node doesn't count, like in part 1
+        if step > 0 && ending_nodes[current_node as usize] {
+            hits.push(step);
+        }
+
+        let (left, right) = dictionary[current_node as usize];
+        current_node = match directions[directions_index] {
+            Direction::Left => left,
+            Direction::Right => right,
+        };
+        step += 1;
+    }
 }
 
-fn lcm_list(numbers: &[u64]) -> u64 {
-    numbers
+fn first_step_all_on_ending_nodes(cycles: &[GhostCycle]) -> Result<u64, String> {
+    // Before every ghost is in its cycle, one of them must be on a prefix hit.
+    let mut best = cycles
+        .iter()
+        .flat_map(|cycle| cycle.prefix_hits.iter().cloned())
+        .filter(|&step| cycles.iter().all(|cycle| cycle.is_on_ending_node(step)))
+        .min();
+
+    // After, the cycle hits are congruences, merged one ghost at a time.
+    // The contradictions are dropped right away, and as the solutions so far
+    // share the same modulus, the same residue is only kept once.
+    let all_in_cycle = cycles
         .iter()
-        .cloned()
-        .reduce(|a, b| lcm(a, b))
-        .unwrap_or(1)
+        .map(|cycle| cycle.cycle_start)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    let mut solutions: BTreeSet<(u64, u64)> = BTreeSet::from([(0, 1)]);
+    for cycle in cycles.iter() {
+        solutions = solutions
+            .iter()
+            .flat_map(|&solution| {
+                cycle.cycle_hits.iter().filter_map(move |&hit| {
+                    number_theory::chinese_remainder(&[
+                        solution,
+                        (hit % cycle.cycle_length, cycle.cycle_length),
+                    ])
+                })
+            })
+            .collect();
+    }
+
+    for (residue, modulus) in solutions {
+        // smallest solution once all the ghosts are in their cycle
+        let step = if residue >= all_in_cycle {
+            Some(residue)
+        } else {
+            (all_in_cycle - residue)
+                .div_ceil(modulus)
+                .checked_mul(modulus)
+                .and_then(|offset| offset.checked_add(residue))
+        };
+        // too far away to be the answer anyway
+        if let Some(step) = step {
+            best = Some(best.map_or(step, |best: u64| best.min(step)));
+        }
+    }
+
+    best.ok_or_else(|| "The ghosts never meet on ending nodes".to_string())
 }
 
-pub fn day_8_part_2(data: &str) -> i64 {
-    let (_, data) = parse_input_data(data).expect("Failed to parse input data");
-    let (directions, nodes) = data;
+pub fn day_8_part_2(data: &str) -> Result<i64, String> {
+    let (directions, nodes) = parse_and_check(data)?;
     let dictionary = build_dictionary(&nodes);
 
     // find out all the starting points
@@ -219,19 +348,49 @@ pub fn day_8_part_2(data: &str) -> i64 {
         }
     }
 
-    // Compute all the number of iterations required
-    let nb_iterations_for_all = current_nodes
+    let cycles = current_nodes
         .iter()
-        .map(|node| compute_number_of_iterations(&dictionary, &directions, *node, &ending_nodes))
-        .collect::<Vec<i64>>();
+        .map(|node| find_ghost_cycle(&dictionary, &directions, *node, &ending_nodes))
+        .collect::<Vec<GhostCycle>>();
 
-    // Compute the least common multiple
-    lcm_list(
-        &nb_iterations_for_all
-            .iter()
-            .map(|nb| *nb as u64)
-            .collect::<Vec<u64>>(),
-    ) as i64
+    if cycles.is_empty() {
+        return Err("There are no ghosts".to_string());
+    }
+    let step = first_step_all_on_ending_nodes(&cycles)?;
+    i64::try_from(step).map_err(|_| "The ghosts meet too far away".to_string())
+}
+
+fn node_label(node: NodeName) -> String {
+    number_to_letters(node).iter().collect()
+}
+
+// The starting nodes of the ghosts in blue, their ending nodes in green.
+impl ToDot for [Node] {
+    fn to_dot(&self) -> String {
+        let mut graph = DotGraph::new("day_08");
+        for node in self.iter() {
+            let name = node_label(node.name);
+            if node_ends_with_a(node.name) {
+                graph.highlighted_node(&name, START_COLOUR);
+            } else if node_ends_with_z(node.name) {
+                graph.highlighted_node(&name, ACCEPT_COLOUR);
+            }
+
+            match node.left == node.right {
+                true => graph.edge(&name, &node_label(node.left), Some("L/R")),
+                false => {
+                    graph.edge(&name, &node_label(node.left), Some("L"));
+                    graph.edge(&name, &node_label(node.right), Some("R"));
+                }
+            }
+        }
+        graph.to_dot()
+    }
+}
+
+pub fn day_8_dot(data: &str) -> Result<String, String> {
+    let (_, nodes) = parse_all(parse_input_data, data)?;
+    Ok(nodes.to_dot())
 }
 
 #[cfg(test)]
@@ -268,33 +427,41 @@ XXX = (XXX, XXX)";
 
     #[test]
     fn test_numbers_letters_conversions() {
-        assert_eq!(letters_to_number(['A', 'A', 'A']), 0);
+        assert_eq!(letters_to_number(['A', 'A', 'A']), Some(0));
+        assert_eq!(letters_to_number(['A', '1', 'A']), None);
         assert_eq!(
-            number_to_letters(letters_to_number(['Z', 'Z', 'Z'])),
+            number_to_letters(letters_to_number(['Z', 'Z', 'Z']).unwrap()),
             ['Z', 'Z', 'Z']
         );
         assert_eq!(
-            number_to_letters(letters_to_number(['M', 'H', 'A'])),
+            number_to_letters(letters_to_number(['M', 'H', 'A']).unwrap()),
             ['M', 'H', 'A']
         );
         assert_eq!(
-            number_to_letters(letters_to_number(['V', 'Q', 'D'])),
+            number_to_letters(letters_to_number(['V', 'Q', 'D']).unwrap()),
             ['V', 'Q', 'D']
         );
     }
 
     #[test]
     fn test_day_8_part_1() {
-        assert_eq!(day_8_part_1(FIRST_EXAMPLE), 2);
-        assert_eq!(day_8_part_1(SECOND_EXAMPLE), 6);
+        assert_eq!(day_8_part_1(FIRST_EXAMPLE), Ok(2));
+        assert_eq!(day_8_part_1(SECOND_EXAMPLE), Ok(6));
+        // ZZZ can't be reached, and AAA has no directions
+        assert!(day_8_part_1("L\n\nAAA = (AAA, ZZZ)\nZZZ = (ZZZ, ZZZ)").is_err());
+        assert!(day_8_part_1("\n\nAAA = (ZZZ, ZZZ)").is_err());
     }
 
     #[test]
     fn test_node_ends_with_a() {
         assert!(node_ends_with_a(0));
         assert!(!node_ends_with_a(1));
-        assert!(node_ends_with_a(letters_to_number(['M', 'H', 'A'])));
-        assert!(!node_ends_with_a(letters_to_number(['V', 'Q', 'D'])));
+        assert!(node_ends_with_a(
+            letters_to_number(['M', 'H', 'A']).unwrap()
+        ));
+        assert!(!node_ends_with_a(
+            letters_to_number(['V', 'Q', 'D']).unwrap()
+        ));
     }
 
     #[test]
@@ -302,13 +469,91 @@ XXX = (XXX, XXX)";
         assert!(node_ends_with_z(25));
         assert!(!node_ends_with_z(FIRST_NODE));
         assert!(node_ends_with_z(LAST_NODE));
-        assert!(node_ends_with_z(letters_to_number(['V', 'Q', 'Z'])));
-        assert!(!node_ends_with_z(letters_to_number(['V', 'Q', 'D'])));
-        assert!(node_ends_with_z(letters_to_number(['B', 'Z', 'Z'])));
+        assert!(node_ends_with_z(
+            letters_to_number(['V', 'Q', 'Z']).unwrap()
+        ));
+        assert!(!node_ends_with_z(
+            letters_to_number(['V', 'Q', 'D']).unwrap()
+        ));
+        assert!(node_ends_with_z(
+            letters_to_number(['B', 'Z', 'Z']).unwrap()
+        ));
     }
 
+    // The ghosts reach their Z nodes at odd steps, and at steps 2, 5, 8…
+    // The lcm of the first steps would say 2.
+    const PART_2_OFFSETS_EXAMPLE: &str = "L
+
+BBA = (BBZ, BBZ)
+BBZ = (BBC, BBC)
+BBC = (BBZ, BBZ)
+CCA = (CCB, CCB)
+CCB = (CCZ, CCZ)
+CCZ = (CCD, CCD)
+CCD = (CCB, CCB)";
+
     #[test]
     fn test_day_8_part_2() {
-        assert_eq!(day_8_part_2(PART_2_EXAMPLE), 6);
+        assert_eq!(day_8_part_2(PART_2_EXAMPLE), Ok(6));
+        assert_eq!(day_8_part_2(PART_2_OFFSETS_EXAMPLE), Ok(5));
+    }
+
+    #[test]
+    fn test_find_ghost_cycle() {
+        let (_, (directions, nodes)) =
+            parse_input_data(PART_2_OFFSETS_EXAMPLE).expect("Failed to parse input data");
+        let dictionary = build_dictionary(&nodes);
+        let mut ending_nodes: [bool; MAX_NODES] = [false; MAX_NODES];
+        for node in nodes.iter() {
+            ending_nodes[node.name as usize] = node_ends_with_z(node.name);
+        }
+
+        let cycle = find_ghost_cycle(
+            &dictionary,
+            &directions,
+            letters_to_number(['C', 'C', 'A']).unwrap(),
+            &ending_nodes,
+        );
+        assert_eq!(cycle.cycle_start, 1);
+        assert_eq!(cycle.cycle_length, 3);
+        assert!(cycle.prefix_hits.is_empty());
+        assert_eq!(cycle.cycle_hits, vec![2]);
+        assert!(cycle.is_on_ending_node(8));
+        assert!(!cycle.is_on_ending_node(9));
+    }
+
+    #[test]
+    fn test_first_step_all_on_ending_nodes() {
+        // 2^40 combinations of cycle hits, but always the same two residues
+        let cycles = (0..40)
+            .map(|_| GhostCycle {
+                prefix_hits: vec![],
+                cycle_hits: vec![1, 2],
+                cycle_start: 1,
+                cycle_length: 2,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(first_step_all_on_ending_nodes(&cycles), Ok(1));
+
+        // one ghost on even steps, the other on odd steps
+        let cycles = [2, 3].map(|hit| GhostCycle {
+            prefix_hits: vec![],
+            cycle_hits: vec![hit],
+            cycle_start: 1,
+            cycle_length: 2,
+        });
+        assert!(first_step_all_on_ending_nodes(&cycles).is_err());
+    }
+
+    #[test]
+    fn test_day_8_dot() {
+        let dot = day_8_dot(PART_2_EXAMPLE).unwrap();
+        assert!(day_8_dot("LR\n\nAAA = (BBB").is_err());
+        assert!(dot.starts_with("digraph \"day_08\" {\n"));
+        assert!(dot.contains("    \"DDA\" [style=\"filled\", fillcolor=\"#4a90d9\""));
+        assert!(dot.contains("    \"EEZ\" [style=\"filled\", fillcolor=\"#5cb85c\""));
+        assert!(dot.contains("    \"DDA\" -> \"DDB\" [label=\"L\"];"));
+        assert!(dot.contains("    \"DDA\" -> \"XXX\" [label=\"R\"];"));
+        assert!(dot.contains("    \"XXX\" -> \"XXX\" [label=\"L/R\"];"));
     }
 }
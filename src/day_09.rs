@@ -2,22 +2,15 @@
     Should be relatively straightforward, so I suspect I'm missing something.
 */
 
-use nom::{
-    character::complete::{i64, line_ending, space1},
-    multi::{separated_list0, separated_list1},
-    IResult,
-};
-
-fn parse_history(data: &str) -> IResult<&str, Vec<i64>> {
-    separated_list1(space1, i64)(data)
-}
+use crate::parsing::{lines, parse_all, spaced_i64s, IResult};
 
 fn parse_input_data(data: &str) -> IResult<&str, Vec<Vec<i64>>> {
-    separated_list0(line_ending, parse_history)(data)
+    lines(spaced_i64s)(data)
 }
 
-fn solve_history_part_1(history: &Vec<i64>) -> i64 {
-    let mut workbench = history.clone();
+// None when the numbers overflow.
+fn solve_history_part_1(history: &[i64]) -> Option<i64> {
+    let mut workbench = history.to_vec();
     let mut last_index = workbench.len() - 1;
 
     let mut last_numbers_per_turn = Vec::new();
@@ -28,7 +21,7 @@ fn solve_history_part_1(history: &Vec<i64>) -> i64 {
 
         // compute the difference between each element and the next one
         for i in 0..last_index {
-            let diff = workbench[i + 1] - workbench[i];
+            let diff = workbench[i + 1].checked_sub(workbench[i])?;
             workbench[i] = diff;
             if diff != 0 {
                 all_zeros = false;
@@ -41,17 +34,17 @@ fn solve_history_part_1(history: &Vec<i64>) -> i64 {
         last_index -= 1;
     }
 
-    let mut previous_last_value = 0;
+    let mut previous_last_value = 0_i64;
     for last_number_for_turn in last_numbers_per_turn.iter().rev() {
-        let new_last_value = previous_last_value + last_number_for_turn;
+        let new_last_value = previous_last_value.checked_add(*last_number_for_turn)?;
         previous_last_value = new_last_value;
     }
 
-    previous_last_value
+    Some(previous_last_value)
 }
 
-fn solve_history_part_2(history: &Vec<i64>) -> i64 {
-    let mut workbench = history.clone();
+fn solve_history_part_2(history: &[i64]) -> Option<i64> {
+    let mut workbench = history.to_vec();
     let mut last_index = workbench.len() - 1;
 
     let mut last_numbers_per_turn = Vec::new();
@@ -63,7 +56,7 @@ fn solve_history_part_2(history: &Vec<i64>) -> i64 {
 
         for i in 0..last_index {
             // difference is inverted for part 2
-            let diff = workbench[i] - workbench[i + 1];
+            let diff = workbench[i].checked_sub(workbench[i + 1])?;
             workbench[i] = diff;
             if diff != 0 {
                 all_zeros = false;
@@ -76,30 +69,32 @@ fn solve_history_part_2(history: &Vec<i64>) -> i64 {
         last_index -= 1;
     }
 
-    let mut previous_last_value = 0;
+    let mut previous_last_value = 0_i64;
     for last_number_for_turn in last_numbers_per_turn.iter().rev() {
-        let new_last_value = previous_last_value + last_number_for_turn;
+        let new_last_value = previous_last_value.checked_add(*last_number_for_turn)?;
         previous_last_value = new_last_value;
     }
 
-    previous_last_value
+    Some(previous_last_value)
 }
 
-pub fn day_9_part_1(data: &str) -> i64 {
-    let (_, histories) = parse_input_data(data).expect("Failed to parse input data");
+pub fn day_9_part_1(data: &str) -> Result<i64, String> {
+    let histories = parse_all(parse_input_data, data)?;
     histories
         .iter()
         .map(|history| solve_history_part_1(history))
-        .sum()
+        .try_fold(0_i64, |sum, value| sum.checked_add(value?))
+        .ok_or_else(|| "The numbers overflow".to_string())
 }
 
 /* easiest part 2 so far */
-pub fn day_9_part_2(data: &str) -> i64 {
-    let (_, histories) = parse_input_data(data).expect("Failed to parse input data");
+pub fn day_9_part_2(data: &str) -> Result<i64, String> {
+    let histories = parse_all(parse_input_data, data)?;
     histories
         .iter()
         .map(|history| solve_history_part_2(history))
-        .sum()
+        .try_fold(0_i64, |sum, value| sum.checked_add(value?))
+        .ok_or_else(|| "The numbers overflow".to_string())
 }
 
 #[cfg(test)]
@@ -112,17 +107,18 @@ mod tests {
 
     #[test]
     fn test_day_9_part_1() {
-        assert_eq!(day_9_part_1("0 3 6 9 12 15"), 18);
-        assert_eq!(day_9_part_1("1 3 6 10 15 21"), 28);
-        assert_eq!(day_9_part_1("10 13 16 21 30 45"), 68);
-        assert_eq!(day_9_part_1(EXAMPLE), 114);
+        assert_eq!(day_9_part_1("0 3 6 9 12 15"), Ok(18));
+        assert_eq!(day_9_part_1("1 3 6 10 15 21"), Ok(28));
+        assert_eq!(day_9_part_1("10 13 16 21 30 45"), Ok(68));
+        assert_eq!(day_9_part_1(EXAMPLE), Ok(114));
+        assert!(day_9_part_1("9223372036854775807 -9223372036854775808").is_err());
     }
 
     #[test]
     fn test_day_9_part_2() {
-        assert_eq!(day_9_part_2("0 3 6 9 12 15"), -3);
-        assert_eq!(day_9_part_2("1 3 6 10 15 21"), 0);
-        assert_eq!(day_9_part_2("10 13 16 21 30 45"), 5);
-        assert_eq!(day_9_part_2(EXAMPLE), 2);
+        assert_eq!(day_9_part_2("0 3 6 9 12 15"), Ok(-3));
+        assert_eq!(day_9_part_2("1 3 6 10 15 21"), Ok(0));
+        assert_eq!(day_9_part_2("10 13 16 21 30 45"), Ok(5));
+        assert_eq!(day_9_part_2(EXAMPLE), Ok(2));
     }
 }
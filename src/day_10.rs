@@ -7,11 +7,12 @@
 use std::collections::VecDeque;
 
 use ndarray::Array2;
-use nom::{
-    character::complete::{line_ending, one_of},
-    multi::{many0, separated_list0},
-    IResult,
-};
+use nom::{character::complete::one_of, combinator::map};
+
+use crate::geometry::{self, PickError, Point};
+use crate::image::{Image, Palette};
+use crate::parsing::{grid_array2, parse_all, IResult};
+use crate::render::{Backend, Glyph, Overlay, Render, Rgb, ToGlyph};
 
 // I prefer to work on an enum than the ascii characters.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -40,23 +41,29 @@ impl Tile {
             _ => panic!("Unknown tile: {}", c),
         }
     }
+
+    // The (row, col) offsets of the two tiles connected by the pipe.
+    fn connections(&self) -> Option<[(i64, i64); 2]> {
+        match self {
+            Tile::Vertical => Some([(-1, 0), (1, 0)]),
+            Tile::Horizontal => Some([(0, -1), (0, 1)]),
+            Tile::LNorthToEast => Some([(-1, 0), (0, 1)]),
+            Tile::JNorthToWest => Some([(-1, 0), (0, -1)]),
+            Tile::SevenSouthToWest => Some([(1, 0), (0, -1)]),
+            Tile::FSouthToEast => Some([(1, 0), (0, 1)]),
+            Tile::Ground | Tile::Start => None,
+        }
+    }
 }
 
 fn parse_input_data(data: &str) -> IResult<&str, Array2<Tile>> {
-    let (left, data) = separated_list0(line_ending, many0(one_of("|-LJ7F.S")))(data)?;
-
-    let nb_rows = data.len();
-    let nb_cols = data.first().map_or(0, |row| row.len());
-
-    Ok((
-        left,
-        Array2::from_shape_fn((nb_rows, nb_cols), |(row, col)| {
-            Tile::from_char(data[row][col])
-        }),
-    ))
+    grid_array2(map(one_of("|-LJ7F.S"), Tile::from_char))(data)
 }
 
-fn visit_main_pipe(grid: &Array2<Tile>) -> (u64, Array2<u64>, (usize, usize), Tile) {
+// The highest distance, the distances, the start and its tile.
+type MainPipe = (u64, Array2<u64>, (usize, usize), Tile);
+
+fn visit_main_pipe(grid: &Array2<Tile>) -> Result<MainPipe, String> {
     // The problem could be solve with a recursive function
     // But I will manage the stack manually to make sure it is optimised,
     // as I don't want to rely on the compiler to optimise it.
@@ -68,7 +75,7 @@ fn visit_main_pipe(grid: &Array2<Tile>) -> (u64, Array2<u64>, (usize, usize), Ti
     let start = grid
         .indexed_iter()
         .find(|(_, ref tile)| **tile == Tile::Start)
-        .expect("Failed to find the starting point")
+        .ok_or("Failed to find the starting point")?
         .0;
 
     let (nb_rows, nb_cols) = grid.dim();
@@ -136,7 +143,7 @@ fn visit_main_pipe(grid: &Array2<Tile>) -> (u64, Array2<u64>, (usize, usize), Ti
     } else if has_right && has_down {
         Tile::FSouthToEast
     } else {
-        panic!("Invalid starting point: too many or too few connections");
+        return Err("Invalid starting point: too many or too few connections".to_string());
     };
 
     // created a matrix of visited tiles, could be a bitfield, but
@@ -174,23 +181,23 @@ fn visit_main_pipe(grid: &Array2<Tile>) -> (u64, Array2<u64>, (usize, usize), Ti
             }
             Tile::Vertical => {
                 side_a = (row + 1, col);
-                side_b = (row - 1, col);
+                side_b = (row.wrapping_sub(1), col);
             }
             Tile::Horizontal => {
                 side_a = (row, col + 1);
-                side_b = (row, col - 1);
+                side_b = (row, col.wrapping_sub(1));
             }
             Tile::LNorthToEast => {
-                side_a = (row - 1, col);
+                side_a = (row.wrapping_sub(1), col);
                 side_b = (row, col + 1);
             }
             Tile::JNorthToWest => {
-                side_a = (row - 1, col);
-                side_b = (row, col - 1);
+                side_a = (row.wrapping_sub(1), col);
+                side_b = (row, col.wrapping_sub(1));
             }
             Tile::SevenSouthToWest => {
                 side_a = (row + 1, col);
-                side_b = (row, col - 1);
+                side_b = (row, col.wrapping_sub(1));
             }
             Tile::FSouthToEast => {
                 side_a = (row + 1, col);
@@ -202,6 +209,7 @@ fn visit_main_pipe(grid: &Array2<Tile>) -> (u64, Array2<u64>, (usize, usize), Ti
             highest_distance = distance;
         }
 
+        // the sides before the first row or column wrapped around, and are out of bounds too
         if side_a.0 < nb_rows && side_a.1 < nb_cols {
             stack.push_back((side_a.0, side_a.1, distance + 1));
         }
@@ -210,280 +218,147 @@ fn visit_main_pipe(grid: &Array2<Tile>) -> (u64, Array2<u64>, (usize, usize), Ti
         }
     }
 
-    (highest_distance, visited, start, start_tile)
-}
-
-pub fn day_10_part_1(data: &str) -> i64 {
-    let (_, grid) = parse_input_data(data).expect("Failed to parse input data");
-    let (highest_distance, _, _, _) = visit_main_pipe(&grid);
-    highest_distance as i64
-}
-
-#[allow(dead_code)]
-fn pretty_print_input(input: &str) {
-    let mut output = String::new();
-
-    for c in input.chars() {
-        match c {
-            'F' => output.push('\u{250F}'),
-            'J' => output.push('\u{251B}'),
-            'L' => output.push('\u{2517}'),
-            '7' => output.push('\u{2513}'),
-            '|' => output.push('\u{2503}'),
-            '-' => output.push('\u{2501}'),
-            _ => output.push(c),
-        };
-    }
-
-    println!("{}", output);
-}
-
-#[allow(dead_code)]
-fn pretty_print_grid(grid: &Array2<Tile>) {
-    // print row by row
-    for row in grid.outer_iter() {
-        for tile in row {
-            match tile {
-                Tile::Vertical => print!("\u{2503}"),
-                Tile::Horizontal => print!("\u{2501}"),
-                Tile::LNorthToEast => print!("\u{2517}"),
-                Tile::JNorthToWest => print!("\u{251B}"),
-                Tile::SevenSouthToWest => print!("\u{2513}"),
-                Tile::FSouthToEast => print!("\u{250F}"),
-                Tile::Ground => print!("."),
-                Tile::Start => print!("S"),
-            }
-        }
-        println!();
-    }
+    Ok((highest_distance, visited, start, start_tile))
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum VisitedState {
-    None,
-    NotVisitedGround,
-    NotVisitedPipe,
-    FromTopLeft,
-    FromTopRight,
-    FromBottomLeft,
-    FromBottomRight,
+pub fn day_10_part_1(data: &str) -> Result<i64, String> {
+    let grid = parse_all(parse_input_data, data)?;
+    let (highest_distance, _, _, _) = visit_main_pipe(&grid)?;
+    Ok(highest_distance as i64)
 }
 
-#[allow(dead_code)]
-fn pretty_print_visited(grid: &Array2<VisitedState>, grid2: &Array2<Tile>) {
-    for (row_index, row) in grid.outer_iter().enumerate() {
-        for (col_index, tile) in row.iter().enumerate() {
-            match tile {
-                VisitedState::None
-                | VisitedState::NotVisitedGround
-                | VisitedState::NotVisitedPipe => {
-                    let tile2 = grid2[(row_index, col_index)];
-                    match tile2 {
-                        Tile::Vertical => print!("\u{2503}"),
-                        Tile::Horizontal => print!("\u{2501}"),
-                        Tile::LNorthToEast => print!("\u{2517}"),
-                        Tile::JNorthToWest => print!("\u{251B}"),
-                        Tile::SevenSouthToWest => print!("\u{2513}"),
-                        Tile::FSouthToEast => print!("\u{250F}"),
-                        Tile::Ground => print!("."),
-                        Tile::Start => print!("S"),
-                    }
-                }
-                VisitedState::FromTopLeft => print!("a"),
-                VisitedState::FromTopRight => print!("b"),
-                VisitedState::FromBottomLeft => print!("c"),
-                VisitedState::FromBottomRight => print!("d"),
-            }
+impl ToGlyph for Tile {
+    fn to_glyph(&self) -> Glyph {
+        match self {
+            Tile::Vertical => Glyph::new('|').with_unicode('┃'),
+            Tile::Horizontal => Glyph::new('-').with_unicode('━'),
+            Tile::LNorthToEast => Glyph::new('L').with_unicode('┗'),
+            Tile::JNorthToWest => Glyph::new('J').with_unicode('┛'),
+            Tile::SevenSouthToWest => Glyph::new('7').with_unicode('┓'),
+            Tile::FSouthToEast => Glyph::new('F').with_unicode('┏'),
+            Tile::Ground => Glyph::new('.'),
+            Tile::Start => Glyph::new('S').with_colour(Rgb(255, 80, 80)),
         }
-        println!();
     }
 }
 
 /*
     Part 2 is a bit more complicated because of the rules,
-    as the filling algorithm needs relatively quite some code.
-*/
-
-pub fn day_10_part_2(data: &str) -> i64 {
-    let (_, grid) = parse_input_data(data).expect("Failed to parse input data");
-    let (_, visited_by_main_pipe, start_main_pipe, start_type_main_pipe) = visit_main_pipe(&grid);
+    a flood fill needed relatively quite some code.
 
-    let (nb_rows, nb_cols) = grid.dim();
-
-    let mut visited = Array2::<VisitedState>::from_elem((nb_rows, nb_cols), VisitedState::None);
+    Like day 18 says, the main loop is a lattice polygon going through
+    the centres of the tiles, and the enclosed tiles are its interior
+    points. So Pick's theorem does it without any flood fill.
+*/
 
-    // we clean up all the pipes that are not visited, they are to be ignored
-    // so they are replaced by ground for simplicity
-    let mut grid = grid;
-    for ((row, col), tile) in grid.indexed_iter_mut() {
-        if visited_by_main_pipe[(row, col)] == 0 {
-            *tile = Tile::Ground;
-            visited[(row, col)] = VisitedState::NotVisitedGround;
+// Walk along the main loop, and keep the corners in order.
+// Fails if the pipes lead out of the grid, or never back to the start.
+fn main_loop_vertices(
+    grid: &Array2<Tile>,
+    start: (usize, usize),
+    start_tile: Tile,
+) -> Result<Vec<Point>, String> {
+    let start = (start.0 as i64, start.1 as i64);
+    let mut vertices = Vec::new();
+    let mut previous = start;
+    let mut current = start;
+
+    for _ in 0..grid.len() {
+        let tile = if current == start {
+            start_tile
         } else {
-            visited[(row, col)] = VisitedState::NotVisitedPipe;
+            *usize::try_from(current.0)
+                .ok()
+                .zip(usize::try_from(current.1).ok())
+                .and_then(|index| grid.get(index))
+                .ok_or("The main loop leaves the grid")?
+        };
+        if tile != Tile::Vertical && tile != Tile::Horizontal {
+            vertices.push(current);
         }
-    }
 
-    // The start tile is also replaced by the only shape that it could be
-    // so it's a tight loop
-    grid[(start_main_pipe.0, start_main_pipe.1)] = start_type_main_pipe;
+        let [side_a, side_b] = tile
+            .connections()
+            .ok_or("The main loop should only contain pipes")?;
+        let next_a = (current.0 + side_a.0, current.1 + side_a.1);
+        let next_b = (current.0 + side_b.0, current.1 + side_b.1);
 
-    // coordinates of the tile to be visited, and state of the previous tile
-    // so we know from which side of the pipes we are coming from
-    let mut stack: Vec<(usize, usize, VisitedState)> = Vec::new();
-
-    // allways start with the top left corner
-    stack.push((0, 0, VisitedState::FromTopLeft));
+        // Don't go back, unless we just started
+        let next = if next_a != previous || current == start {
+            next_a
+        } else {
+            next_b
+        };
+        previous = current;
+        current = next;
 
-    while let Some((row, col, previous_tile_state)) = stack.pop() {
-        if row >= nb_rows || col >= nb_cols {
-            unreachable!("We should never be out of bound");
+        if current == start {
+            return Ok(vertices);
         }
+    }
 
-        let visited_tile_state = visited[(row, col)];
-        if visited_tile_state != VisitedState::NotVisitedPipe
-            && visited_tile_state != VisitedState::NotVisitedGround
-        {
-            continue;
+    Err("The main loop never comes back to the start".to_string())
+}
+
+pub fn day_10_part_2(data: &str) -> Result<i64, String> {
+    let grid = parse_all(parse_input_data, data)?;
+    let (_, _, start, start_tile) = visit_main_pipe(&grid)?;
+    let vertices = main_loop_vertices(&grid, start, start_tile)?;
+    match geometry::interior_points(&vertices) {
+        Ok(nb_points) => {
+            i64::try_from(nb_points).map_err(|_| "The main loop is too big".to_string())
         }
-        match previous_tile_state {
-            VisitedState::None | VisitedState::NotVisitedGround | VisitedState::NotVisitedPipe => {
-                unreachable!("Invalid previous tile state");
-            }
-            visited_tile_state => {
-                visited[(row, col)] = visited_tile_state;
-            }
+        Err(PickError::Overflow) => Err("The main loop is too big".to_string()),
+        Err(PickError::NotSimple | PickError::Empty) => {
+            Err("The main loop isn't a simple polygon".to_string())
         }
-        //pretty_print_visited(&visited, &grid);
-        //println!("");
+    }
+}
 
-        let mut push_left = VisitedState::None;
-        let mut push_right = VisitedState::None;
-        let mut push_up = VisitedState::None;
-        let mut push_down = VisitedState::None;
+// The main loop highlighted, and the enclosed tiles marked with an I.
+fn main_loop_overlay(data: &str) -> (Array2<Tile>, Overlay) {
+    let grid = parse_all(parse_input_data, data).expect("Failed to parse input data");
+    let (_, visited, start, start_tile) =
+        visit_main_pipe(&grid).expect("Failed to find the main loop");
+    let is_on_loop = |position: (usize, usize)| visited[position] > 0 || position == start;
 
-        let tile = &grid[(row, col)];
-        match tile {
-            Tile::Start => {
-                unreachable!("We should never encounter the starting point");
-            }
-            Tile::Ground => {
-                push_left = VisitedState::FromTopRight;
-                push_right = VisitedState::FromTopLeft;
-                push_up = VisitedState::FromBottomLeft;
-                push_down = VisitedState::FromTopLeft;
-            }
-            Tile::Vertical => match previous_tile_state {
-                VisitedState::FromTopLeft | VisitedState::FromBottomLeft => {
-                    push_up = VisitedState::FromBottomLeft;
-                    push_down = VisitedState::FromTopLeft;
-                    push_left = VisitedState::FromTopRight;
-                }
-                VisitedState::FromTopRight | VisitedState::FromBottomRight => {
-                    push_up = VisitedState::FromBottomRight;
-                    push_down = VisitedState::FromTopRight;
-                    push_right = VisitedState::FromTopLeft;
-                }
-                _ => {}
-            },
-            Tile::Horizontal => match previous_tile_state {
-                VisitedState::FromTopLeft | VisitedState::FromTopRight => {
-                    push_left = VisitedState::FromTopRight;
-                    push_right = VisitedState::FromTopLeft;
-                    push_up = VisitedState::FromBottomLeft;
-                }
-                VisitedState::FromBottomLeft | VisitedState::FromBottomRight => {
-                    push_left = VisitedState::FromBottomRight;
-                    push_right = VisitedState::FromBottomLeft;
-                    push_down = VisitedState::FromTopLeft;
-                }
-                _ => {}
-            },
-            Tile::LNorthToEast => match previous_tile_state {
-                VisitedState::FromTopLeft
-                | VisitedState::FromBottomLeft
-                | VisitedState::FromBottomRight => {
-                    push_left = VisitedState::FromTopRight;
-                    push_right = VisitedState::FromBottomLeft;
-                    push_up = VisitedState::FromBottomLeft;
-                    push_down = VisitedState::FromTopLeft;
-                }
-                VisitedState::FromTopRight => {
-                    push_up = VisitedState::FromBottomRight;
-                    push_right = VisitedState::FromTopLeft;
-                }
-                _ => {}
-            },
-            Tile::JNorthToWest => match previous_tile_state {
-                VisitedState::FromBottomRight
-                | VisitedState::FromBottomLeft
-                | VisitedState::FromTopRight => {
-                    push_left = VisitedState::FromBottomRight;
-                    push_right = VisitedState::FromTopLeft;
-                    push_up = VisitedState::FromBottomRight;
-                    push_down = VisitedState::FromTopLeft;
-                }
-                VisitedState::FromTopLeft => {
-                    push_up = VisitedState::FromBottomLeft;
-                    push_left = VisitedState::FromTopRight;
-                }
-                _ => {}
-            },
-            Tile::SevenSouthToWest => match previous_tile_state {
-                VisitedState::FromTopLeft
-                | VisitedState::FromTopRight
-                | VisitedState::FromBottomRight => {
-                    push_left = VisitedState::FromTopRight;
-                    push_right = VisitedState::FromTopLeft;
-                    push_up = VisitedState::FromBottomLeft;
-                    push_down = VisitedState::FromTopRight;
-                }
-                VisitedState::FromBottomLeft => {
-                    push_down = VisitedState::FromTopLeft;
-                    push_left = VisitedState::FromBottomRight;
-                }
-                _ => {}
-            },
-            Tile::FSouthToEast => match previous_tile_state {
-                VisitedState::FromTopLeft
-                | VisitedState::FromTopRight
-                | VisitedState::FromBottomLeft => {
-                    push_left = VisitedState::FromTopRight;
-                    push_right = VisitedState::FromTopLeft;
-                    push_up = VisitedState::FromBottomLeft;
-                    push_down = VisitedState::FromTopLeft;
-                }
-                VisitedState::FromBottomRight => {
-                    push_down = VisitedState::FromTopRight;
-                    push_right = VisitedState::FromBottomLeft;
+    let mut overlay = Overlay::new();
+    for (row_index, row) in grid.outer_iter().enumerate() {
+        // Scanline: we are inside after crossing an odd number of
+        // pipes going north.
+        let mut inside = false;
+        for (col_index, &tile) in row.iter().enumerate() {
+            let position = (row_index, col_index);
+            if is_on_loop(position) {
+                overlay.highlight(position, Rgb(30, 110, 50));
+                let tile = if tile == Tile::Start {
+                    start_tile
+                } else {
+                    tile
+                };
+                if matches!(
+                    tile,
+                    Tile::Vertical | Tile::LNorthToEast | Tile::JNorthToWest
+                ) {
+                    inside = !inside;
                 }
-                _ => {}
-            },
-        }
-        if push_left != VisitedState::None && col > 0 {
-            stack.push((row, col - 1, push_left));
-        }
-        if push_right != VisitedState::None && col < nb_cols - 1 {
-            stack.push((row, col + 1, push_right));
-        }
-        if push_up != VisitedState::None && row > 0 {
-            stack.push((row - 1, col, push_up));
-        }
-        if push_down != VisitedState::None && row < nb_rows - 1 {
-            stack.push((row + 1, col, push_down));
+            } else if inside {
+                overlay.mark(position, 'I', Rgb(160, 40, 40));
+            }
         }
     }
 
-    // pretty_print_grid(&grid);
+    (grid, overlay)
+}
 
-    // count the number of not visited tiles
-    visited.iter().fold(0, |acc, tile| {
-        if *tile == VisitedState::NotVisitedGround {
-            acc + 1
-        } else {
-            acc
-        }
-    }) as i64
+pub fn day_10_show(data: &str, backend: Backend) -> String {
+    let (grid, overlay) = main_loop_overlay(data);
+    grid.render_with_overlay(backend, &overlay)
+}
+
+pub fn day_10_image(data: &str, palette: &Palette, scale: usize) -> Image {
+    let (grid, overlay) = main_loop_overlay(data);
+    Image::from_render(&grid, &overlay, palette, scale)
 }
 
 #[cfg(test)]
@@ -608,24 +483,63 @@ L7JLJL-JLJLJL--JLJ.L";
 
     #[test]
     fn test_day_10_part_1() {
-        assert_eq!(day_10_part_1(EXAMPLE_A), 4);
-        assert_eq!(day_10_part_1(EXAMPLE_B), 8);
+        assert_eq!(day_10_part_1(EXAMPLE_A), Ok(4));
+        assert_eq!(day_10_part_1(EXAMPLE_B), Ok(8));
+        // the input files end with a line ending
+        assert_eq!(day_10_part_1(&format!("{}\n", EXAMPLE_B)), Ok(8));
     }
 
     #[test]
     fn test_day_10_part_2() {
-        /*
-        pretty_print_input(EXAMPLE_A);
-        pretty_print_input(EXAMPLE_B);
-        pretty_print_input(EXAMPLE_C);
-        pretty_print_input(EXAMPLE_D);
-        pretty_print_input(EXAMPLE_E);
-        pretty_print_input(EXAMPLE_F);*/
-        assert_eq!(day_10_part_2(EXAMPLE_A), 1);
-        assert_eq!(day_10_part_2(EXAMPLE_B), 1);
-        assert_eq!(day_10_part_2(EXAMPLE_C), 4);
-        assert_eq!(day_10_part_2(EXAMPLE_D), 4);
-        assert_eq!(day_10_part_2(EXAMPLE_E), 8);
-        assert_eq!(day_10_part_2(EXAMPLE_F), 10);
+        assert_eq!(day_10_part_2(EXAMPLE_A), Ok(1));
+        assert_eq!(day_10_part_2(EXAMPLE_B), Ok(1));
+        assert_eq!(day_10_part_2(EXAMPLE_C), Ok(4));
+        assert_eq!(day_10_part_2(EXAMPLE_D), Ok(4));
+        assert_eq!(day_10_part_2(EXAMPLE_E), Ok(8));
+        assert_eq!(day_10_part_2(EXAMPLE_F), Ok(10));
+
+        // the pipes lead out of the grid, or nowhere
+        assert!(day_10_part_2("S-\n|.").is_err());
+        assert!(day_10_part_2("S.\n..").is_err());
+    }
+
+    #[test]
+    fn test_main_loop_vertices() {
+        let (_, grid) = parse_input_data(EXAMPLE_A).expect("Failed to parse input data");
+        let (_, _, start, start_tile) =
+            visit_main_pipe(&grid).expect("Failed to find the main loop");
+        let vertices =
+            main_loop_vertices(&grid, start, start_tile).expect("Failed to walk the loop");
+        assert_eq!(vertices, vec![(1, 1), (3, 1), (3, 3), (1, 3)]);
+        assert_eq!(geometry::is_self_intersecting(&vertices), Some(false));
+        assert_eq!(
+            geometry::locate_point(&vertices, (2, 2)),
+            Some(geometry::PointLocation::Inside)
+        );
+    }
+
+    #[test]
+    fn test_day_10_show() {
+        let shown = day_10_show(EXAMPLE_A, Backend::Ascii);
+        assert_eq!(shown, ".....\n.S-7.\n.|I|.\n.L-J.\n.....\n");
+
+        // the enclosed tiles are the same as part 2
+        for example in [EXAMPLE_C, EXAMPLE_D, EXAMPLE_E, EXAMPLE_F] {
+            assert_eq!(
+                day_10_show(example, Backend::Ascii).matches('I').count() as i64,
+                day_10_part_2(example).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_day_10_image() {
+        let palette = Palette::new(Rgb(0, 0, 0));
+        let image = day_10_image(EXAMPLE_A, &palette, 2);
+        assert_eq!((image.width, image.height), (10, 10));
+        // the main loop, the enclosed tile and the ground
+        assert_eq!(image.pixels[2 * 10 + 2], Rgb(30, 110, 50));
+        assert_eq!(image.pixels[4 * 10 + 4], Rgb(160, 40, 40));
+        assert_eq!(image.pixels[0], Rgb(0, 0, 0));
     }
 }
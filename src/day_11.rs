@@ -5,11 +5,11 @@
 */
 
 use ndarray::Array2;
-use nom::{
-    character::complete::{line_ending, one_of},
-    multi::{many0, separated_list0},
-    IResult,
-};
+use nom::{character::complete::one_of, combinator::map};
+
+use crate::number_theory::checked_sum;
+use crate::parsing::{grid_array2, parse_all, IResult};
+use crate::trace::{trace_event, Level};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Tile {
@@ -43,21 +43,14 @@ fn couples(n: usize) -> impl Iterator<Item = (usize, usize)> {
 }
 
 fn parse_input_data(data: &str) -> IResult<&str, Array2<Tile>> {
-    let (left, data) = separated_list0(line_ending, many0(one_of(".#")))(data)?;
-
-    let nb_rows = data.len();
-    let nb_cols = data.first().map_or(0, |row| row.len());
-
-    Ok((
-        left,
-        Array2::from_shape_fn((nb_rows, nb_cols), |(row, col)| {
-            Tile::from_char(data[row][col])
-        }),
-    ))
+    grid_array2(map(one_of(".#"), Tile::from_char))(data)
 }
 
-fn compute_sum_of_manhattan_distances(grid: &Array2<Tile>, expansion: usize) -> i64 {
-    //println!("{:?}", grid);
+fn compute_sum_of_manhattan_distances(
+    grid: &Array2<Tile>,
+    expansion: usize,
+) -> Result<i64, String> {
+    trace_event!(Level::Trace, "grid: {:?}", grid.dim());
 
     // Iterate row by row on the grid Array2
     let empty_rows: Vec<bool> = grid
@@ -72,14 +65,14 @@ fn compute_sum_of_manhattan_distances(grid: &Array2<Tile>, expansion: usize) ->
         .map(|column| column.iter().all(|tile| *tile == Tile::Empty))
         .collect();
 
-    //println!("{:?}", empty_rows);
-    //println!("{:?}", empty_columns);
+    trace_event!(Level::Debug, "empty rows: {:?}", empty_rows);
+    trace_event!(Level::Debug, "empty columns: {:?}", empty_columns);
 
     let index_rows: Vec<usize> = empty_lists_indexes(&empty_rows, expansion);
     let index_columns: Vec<usize> = empty_lists_indexes(&empty_columns, expansion);
 
-    //println!("{:?}", index_rows);
-    //println!("{:?}", index_columns);
+    trace_event!(Level::Trace, "expanded row indexes: {:?}", index_rows);
+    trace_event!(Level::Trace, "expanded column indexes: {:?}", index_columns);
 
     // find all the galaxies and but them into a list
     let list_of_galaxies: Vec<(usize, usize)> = grid
@@ -88,35 +81,33 @@ fn compute_sum_of_manhattan_distances(grid: &Array2<Tile>, expansion: usize) ->
         .map(|((row, col), _)| (row, col))
         .collect();
 
-    //println!("{:?}", list_of_galaxies);
+    trace_event!(Level::Debug, "galaxies: {:?}", list_of_galaxies);
     let nb_galaxies = list_of_galaxies.len();
 
-    couples(nb_galaxies)
-        .map(|(galaxy_a, galaxy_b)| {
-            //println!("{:?} {:?}", galaxy_a, galaxy_b);
+    checked_sum(couples(nb_galaxies).map(|(galaxy_a, galaxy_b)| {
+        trace_event!(Level::Trace, "couple: {} {}", galaxy_a, galaxy_b);
 
-            let (row_a, col_a) = list_of_galaxies[galaxy_a];
-            let (row_b, col_b) = list_of_galaxies[galaxy_b];
+        let (row_a, col_a) = list_of_galaxies[galaxy_a];
+        let (row_b, col_b) = list_of_galaxies[galaxy_b];
 
-            let corrected_row_a = index_rows[row_a];
-            let corrected_row_b = index_rows[row_b];
-            let corrected_col_a = index_columns[col_a];
-            let corrected_col_b = index_columns[col_b];
+        let corrected_row_a = index_rows[row_a];
+        let corrected_row_b = index_rows[row_b];
+        let corrected_col_a = index_columns[col_a];
+        let corrected_col_b = index_columns[col_b];
 
-            // manhattan distance
-            (corrected_row_a as i64 - corrected_row_b as i64).abs()
-                + (corrected_col_a as i64 - corrected_col_b as i64).abs()
-        })
-        .sum()
+        // manhattan distance
+        (corrected_row_a as i64 - corrected_row_b as i64).abs()
+            + (corrected_col_a as i64 - corrected_col_b as i64).abs()
+    }))
 }
 
-pub fn day_11_part_1(data: &str) -> i64 {
-    let (_, grid) = parse_input_data(data).expect("Failed to parse input data");
+pub fn day_11_part_1(data: &str) -> Result<i64, String> {
+    let grid = parse_all(parse_input_data, data)?;
     compute_sum_of_manhattan_distances(&grid, 2)
 }
 
-pub fn day_11_part_2(data: &str) -> i64 {
-    let (_, grid) = parse_input_data(data).expect("Failed to parse input data");
+pub fn day_11_part_2(data: &str) -> Result<i64, String> {
+    let grid = parse_all(parse_input_data, data)?;
     compute_sum_of_manhattan_distances(&grid, 1000000)
 }
 
@@ -137,13 +128,15 @@ mod tests {
 
     #[test]
     fn test_day_11_part_1() {
-        assert_eq!(day_11_part_1(EXAMPLE), 374);
+        assert_eq!(day_11_part_1(EXAMPLE), Ok(374));
+        // the input files end with a line ending
+        assert_eq!(day_11_part_1(&format!("{}\n", EXAMPLE)), Ok(374));
     }
 
     #[test]
     fn test_day_11_part_2() {
         let (_, grid) = parse_input_data(EXAMPLE).expect("Failed to parse input data");
-        assert_eq!(compute_sum_of_manhattan_distances(&grid, 10), 1030);
-        assert_eq!(compute_sum_of_manhattan_distances(&grid, 100), 8410);
+        assert_eq!(compute_sum_of_manhattan_distances(&grid, 10), Ok(1030));
+        assert_eq!(compute_sum_of_manhattan_distances(&grid, 100), Ok(8410));
     }
 }
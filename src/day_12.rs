@@ -32,14 +32,14 @@ use core::hash::Hash;
 use std::{collections::HashMap, hash::Hasher, marker::PhantomData};
 
 use nom::{
-    bytes::complete::tag,
     character::complete::{line_ending, one_of, space1, u64},
     combinator::map,
     multi::{many0, separated_list0},
     sequence::tuple,
-    IResult,
 };
 
+use crate::parsing::{parse_all, tag, IResult};
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum SpringCondition {
     Operational,
@@ -72,7 +72,10 @@ fn parse_spring(data: &str) -> IResult<&str, (Vec<SpringCondition>, Vec<u64>)> {
     )(data)
 }
 
-fn parse_input_data(data: &str) -> IResult<&str, Vec<(Vec<SpringCondition>, Vec<u64>)>> {
+// The springs of each row, with the sizes of their damaged groups.
+type Rows = Vec<(Vec<SpringCondition>, Vec<u64>)>;
+
+fn parse_input_data(data: &str) -> IResult<&str, Rows> {
     separated_list0(line_ending, parse_spring)(data)
 }
 
@@ -101,14 +104,15 @@ impl<'a> PartialEq for MemoKey<'a> {
 
 impl<'a> Eq for MemoKey<'a> {}
 
+// None if the number of solutions doesn't fit in a u64.
 fn num_solutions<'a>(
     spring: &'a [SpringCondition],
     sizes: &'a [u64],
     num_done_in_group: u64,
     memo: &mut HashMap<MemoKey<'a>, u64>,
-) -> u64 {
+) -> Option<u64> {
     if spring.is_empty() {
-        return (sizes.is_empty() && num_done_in_group == 0) as u64;
+        return Some((sizes.is_empty() && num_done_in_group == 0) as u64);
     }
 
     let key = MemoKey {
@@ -118,14 +122,19 @@ fn num_solutions<'a>(
         _marker: PhantomData,
     };
     if let Some(&cached) = memo.get(&key) {
-        return cached;
+        return Some(cached);
     }
 
-    let mut num_sols = 0;
+    let mut num_sols = 0_u64;
     let first_condition = spring[0];
 
     if first_condition == SpringCondition::Damaged || first_condition == SpringCondition::Unknown {
-        num_sols += num_solutions(&spring[1..], sizes, num_done_in_group + 1, memo);
+        num_sols = num_sols.checked_add(num_solutions(
+            &spring[1..],
+            sizes,
+            num_done_in_group + 1,
+            memo,
+        )?)?;
     }
 
     if first_condition == SpringCondition::Operational
@@ -134,22 +143,41 @@ fn num_solutions<'a>(
         if num_done_in_group > 0 {
             if let Some((&first_size, rest_sizes)) = sizes.split_first() {
                 if first_size == num_done_in_group {
-                    num_sols += num_solutions(&spring[1..], rest_sizes, 0, memo);
+                    num_sols =
+                        num_sols.checked_add(num_solutions(&spring[1..], rest_sizes, 0, memo)?)?;
                 }
             }
         } else {
-            num_sols += num_solutions(&spring[1..], sizes, 0, memo);
+            num_sols = num_sols.checked_add(num_solutions(&spring[1..], sizes, 0, memo)?)?;
         }
     }
 
     memo.insert(key, num_sols);
-    num_sols
+    Some(num_sols)
+}
+
+// The recursion goes as deep as the row is long, the real rows
+// have about 20 springs.
+const MAX_SPRINGS: usize = 200;
+
+fn parse_and_check(data: &str) -> Result<Rows, String> {
+    let springs = parse_all(parse_input_data, data)?;
+    if springs.iter().any(|(spring, _)| spring.len() > MAX_SPRINGS) {
+        return Err(format!("A row has more than {} springs", MAX_SPRINGS));
+    }
+    Ok(springs)
+}
+
+fn sum_of_solutions(solutions: Option<u64>) -> Result<i64, String> {
+    solutions
+        .and_then(|solutions| i64::try_from(solutions).ok())
+        .ok_or_else(|| "Too many arrangements".to_string())
 }
 
-pub fn day_12_part_1(data: &str) -> i64 {
-    let (_, springs) = parse_input_data(data).expect("Failed to parse input data");
+pub fn day_12_part_1(data: &str) -> Result<i64, String> {
+    let springs = parse_and_check(data)?;
 
-    springs
+    let solutions = springs
         .par_iter()
         .map(|(spring, sizes)| {
             let mut extended_spring = Vec::with_capacity(spring.len() + 1);
@@ -158,13 +186,14 @@ pub fn day_12_part_1(data: &str) -> i64 {
             let mut memo = HashMap::new();
             num_solutions(&extended_spring, sizes, 0, &mut memo)
         })
-        .sum::<u64>() as i64
+        .try_reduce(|| 0, |a, b| a.checked_add(b));
+    sum_of_solutions(solutions)
 }
 
-pub fn day_12_part_2(data: &str) -> i64 {
-    let (_, springs) = parse_input_data(data).expect("Failed to parse input data");
+pub fn day_12_part_2(data: &str) -> Result<i64, String> {
+    let springs = parse_and_check(data)?;
 
-    springs
+    let solutions = springs
         .par_iter()
         .map(|(spring, sizes)| {
             let mut extended_spring: Vec<SpringCondition> =
@@ -183,7 +212,8 @@ pub fn day_12_part_2(data: &str) -> i64 {
             let mut memo = HashMap::new();
             num_solutions(&extended_spring, &extended_sizes, 0, &mut memo)
         })
-        .sum::<u64>() as i64
+        .try_reduce(|| 0, |a, b| a.checked_add(b));
+    sum_of_solutions(solutions)
 }
 
 #[cfg(test)]
@@ -199,11 +229,11 @@ mod tests {
 
     #[test]
     fn test_day_12_part_1() {
-        assert_eq!(day_12_part_1(EXAMPLE), 21);
+        assert_eq!(day_12_part_1(EXAMPLE), Ok(21));
     }
 
     #[test]
     fn test_day_12_part_2() {
-        assert_eq!(day_12_part_2(EXAMPLE), 525152);
+        assert_eq!(day_12_part_2(EXAMPLE), Ok(525152));
     }
 }
@@ -6,38 +6,17 @@ use rayon::prelude::*;
 
 use ndarray::{s, Array2, ArrayView2};
 
-use nom::{
-    character::complete::{line_ending, one_of},
-    combinator::map,
-    multi::{count, many1, separated_list0, separated_list1},
-    IResult,
-};
+use nom::{character::complete::one_of, combinator::map};
+
+use crate::parsing::{blocks, grid_array2, parse_all, IResult};
+use crate::render::{Backend, Overlay, Render, Rgb};
 
 fn parse_pattern(data: &str) -> IResult<&str, Array2<bool>> {
-    map(
-        separated_list1(line_ending, many1(map(one_of(".#"), |c| c == '#'))),
-        |rows| {
-            let nb_rows = rows.len();
-            let nb_cols = rows.first().map_or(0, |row| row.len());
-
-            Array2::from_shape_fn((nb_rows, nb_cols), |(row, col)| rows[row][col])
-        },
-    )(data)
+    grid_array2(map(one_of(".#"), |c| c == '#'))(data)
 }
 
 fn parse_input_data(data: &str) -> IResult<&str, Vec<Array2<bool>>> {
-    separated_list0(count(line_ending, 2), parse_pattern)(data)
-}
-
-#[allow(dead_code)]
-fn print_pattern(row: &ArrayView2<bool>) {
-    for i in 0..row.nrows() {
-        for j in 0..row.ncols() {
-            print!("{}", if row[[i, j]] { '#' } else { '.' });
-        }
-        println!();
-    }
-    println!();
+    blocks(parse_pattern)(data)
 }
 
 // we need to check whether we have mirrorred rows,
@@ -88,34 +67,61 @@ fn compute_almost_mirrored_rows(pattern: &ArrayView2<bool>) -> Option<usize> {
     }
     return None;
 }
-pub fn day_13_part_1(data: &str) -> i64 {
-    let (_, patterns) = parse_input_data(data).expect("Failed to parse input data");
+pub fn day_13_part_1(data: &str) -> Result<i64, String> {
+    let patterns = parse_all(parse_input_data, data)?;
 
     patterns
         .par_iter()
         .map(|p| match compute_mirrored_rows(&p.view()) {
-            Some(split_row) => split_row * 100,
+            Some(split_row) => Ok(split_row as i64 * 100),
             None => match compute_mirrored_rows(&p.t().view()) {
-                Some(split_row) => split_row,
-                None => panic!("No mirrors found"),
+                Some(split_row) => Ok(split_row as i64),
+                None => Err("No mirrors found".to_string()),
             },
         })
-        .sum::<usize>() as i64
+        .sum()
 }
 
-pub fn day_13_part_2(data: &str) -> i64 {
-    let (_, patterns) = parse_input_data(data).expect("Failed to parse input data");
+pub fn day_13_part_2(data: &str) -> Result<i64, String> {
+    let patterns = parse_all(parse_input_data, data)?;
 
     patterns
         .par_iter()
         .map(|p| match compute_almost_mirrored_rows(&p.view()) {
-            Some(split_row) => split_row * 100,
+            Some(split_row) => Ok(split_row as i64 * 100),
             None => match compute_almost_mirrored_rows(&p.t().view()) {
-                Some(split_row) => split_row,
-                None => panic!("No almost mirrors found"),
+                Some(split_row) => Ok(split_row as i64),
+                None => Err("No almost mirrors found".to_string()),
             },
         })
-        .sum::<usize>() as i64
+        .sum()
+}
+
+// The patterns, with the two rows or columns next to the mirror highlighted.
+pub fn day_13_show(data: &str, backend: Backend) -> String {
+    let patterns = parse_all(parse_input_data, data).expect("Failed to parse input data");
+    let mirror_colour = Rgb(40, 90, 160);
+
+    patterns
+        .iter()
+        .map(|pattern| {
+            let mut overlay = Overlay::new();
+            let (nb_rows, nb_cols) = pattern.dim();
+            if let Some(split_row) = compute_mirrored_rows(&pattern.view()) {
+                for col in 0..nb_cols {
+                    overlay.highlight((split_row - 1, col), mirror_colour);
+                    overlay.highlight((split_row, col), mirror_colour);
+                }
+            } else if let Some(split_col) = compute_mirrored_rows(&pattern.t().view()) {
+                for row in 0..nb_rows {
+                    overlay.highlight((row, split_col - 1), mirror_colour);
+                    overlay.highlight((row, split_col), mirror_colour);
+                }
+            }
+            pattern.render_with_overlay(backend, &overlay)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 #[cfg(test)]
@@ -140,11 +146,24 @@ mod tests {
 
     #[test]
     fn test_day_13_part_1() {
-        assert_eq!(day_13_part_1(EXAMPLE), 405);
+        assert_eq!(day_13_part_1(EXAMPLE), Ok(405));
+        assert!(day_13_part_1("#.\n.#").is_err());
     }
 
     #[test]
     fn test_day_13_part_2() {
-        assert_eq!(day_13_part_2(EXAMPLE), 400);
+        assert_eq!(day_13_part_2(EXAMPLE), Ok(400));
+    }
+
+    #[test]
+    fn test_day_13_show() {
+        assert_eq!(
+            day_13_show(EXAMPLE, Backend::Ascii),
+            format!("{}\n", EXAMPLE)
+        );
+
+        let shown = day_13_show(EXAMPLE, Backend::Ansi);
+        // 2 columns of 7 cells in the first pattern, 2 rows of 9 in the second
+        assert_eq!(shown.matches("\x1b[48;2;40;90;160m").count(), 14 + 18);
     }
 }
@@ -5,17 +5,21 @@
     then, we need to count based on the row number.
 */
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use ndarray::{s, Array2, ArrayView1, ArrayView2};
+use ndarray::{s, Array2, ArrayView1};
 
 use nom::{
     character::complete::{line_ending, one_of},
-    combinator::map,
+    combinator::{map, verify},
     multi::{many1, separated_list1},
-    IResult,
 };
 
+use crate::animation::{Animation, Frame, Recorder};
+use crate::parsing::{parse_all, IResult};
+use crate::render::{Backend, Glyph, Overlay, Render, Rgb, ToGlyph};
+use crate::trace::{span, trace_event, Level};
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 enum Tile {
     Empty,
@@ -25,14 +29,18 @@ enum Tile {
 
 fn parse_input_data(data: &str) -> IResult<&str, Array2<Tile>> {
     map(
-        separated_list1(
-            line_ending,
-            many1(map(one_of(".#O"), |c| match c {
-                '.' => Tile::Empty,
-                'O' => Tile::RoundedRock,
-                '#' => Tile::CubeShapedRock,
-                _ => unreachable!("Unknown tile"),
-            })),
+        // the rows must all have the same length to fit in the array
+        verify(
+            separated_list1(
+                line_ending,
+                many1(map(one_of(".#O"), |c| match c {
+                    '.' => Tile::Empty,
+                    'O' => Tile::RoundedRock,
+                    '#' => Tile::CubeShapedRock,
+                    _ => unreachable!("Unknown tile"),
+                })),
+            ),
+            |rows: &Vec<Vec<Tile>>| rows.iter().all(|row| row.len() == rows[0].len()),
         ),
         |rows| {
             let nb_rows = rows.len();
@@ -43,34 +51,18 @@ fn parse_input_data(data: &str) -> IResult<&str, Array2<Tile>> {
     )(data)
 }
 
-#[allow(dead_code)]
-fn print_grid(grid: &ArrayView2<Tile>) {
-    for i in 0..grid.nrows() {
-        for j in 0..grid.ncols() {
-            print!(
-                "{}",
-                match grid[[i, j]] {
-                    Tile::Empty => '.',
-                    Tile::RoundedRock => 'O',
-                    Tile::CubeShapedRock => '#',
-                }
-            );
+impl ToGlyph for Tile {
+    fn to_glyph(&self) -> Glyph {
+        match self {
+            Tile::Empty => Glyph::new('.').with_unicode('·'),
+            Tile::RoundedRock => Glyph::new('O')
+                .with_unicode('●')
+                .with_colour(Rgb(230, 180, 60)),
+            Tile::CubeShapedRock => Glyph::new('#')
+                .with_unicode('█')
+                .with_colour(Rgb(140, 140, 150)),
         }
-        println!();
     }
-    println!();
-}
-
-#[allow(dead_code)]
-fn print_column(column: &ArrayView1<Tile>) {
-    column.iter().for_each(|&tile| {
-        let symbol = match tile {
-            Tile::Empty => '.',
-            Tile::RoundedRock => 'O',
-            Tile::CubeShapedRock => '#',
-        };
-        println!("{}", symbol);
-    });
 }
 
 fn extract_subsections(column: &ArrayView1<Tile>) -> Vec<(usize, usize)> {
@@ -184,18 +176,18 @@ fn tilt_horizontally(mut grid: Array2<Tile>, direction: TiltDirection) -> Array2
     grid
 }
 
-pub fn day_14_part_1(data: &str) -> i64 {
-    let (_, grid) = parse_input_data(data).expect("Failed to parse input data");
+pub fn day_14_part_1(data: &str) -> Result<i64, String> {
+    let grid = parse_all(parse_input_data, data)?;
     let nb_rows = grid.nrows();
-    //print_grid(&grid.view());
     // We will mutate the grid so we create a copy
-    tilt_vertically(grid.to_owned(), TiltDirection::North)
+    let load = tilt_vertically(grid.to_owned(), TiltDirection::North)
         .indexed_iter()
         // look at the rounded rocks
         .filter(|(_, tile)| **tile == Tile::RoundedRock)
         // count the number of rows below the rounded rock
         .map(|((row, _), _)| nb_rows - row)
-        .sum::<usize>() as i64
+        .sum::<usize>() as i64;
+    Ok(load)
 }
 
 // Part 2: bruteforce is not an option.
@@ -213,15 +205,11 @@ fn cycle(mut grid: Array2<Tile>) -> Array2<Tile> {
     return grid;
 }
 
-pub fn day_14_part_2(data: &str) -> i64 {
-    let (_, grid) = parse_input_data(data).expect("Failed to parse input data");
+pub fn day_14_part_2(data: &str) -> Result<i64, String> {
+    let grid = parse_all(parse_input_data, data)?;
     let nb_rows = grid.nrows();
     let mut work_grid = grid.to_owned();
 
-    /*print_grid(&work_grid.view());
-    let lol = cycle(work_grid.clone());
-    print_grid(&lol.view());*/
-
     // We will cache the grids we have already seen
     // Key is the grid, value is the index of the cycle
     let mut previous_grids: HashMap<Array2<Tile>, usize> = HashMap::new();
@@ -231,14 +219,25 @@ pub fn day_14_part_2(data: &str) -> i64 {
     // hopefully the cycle is reached well before 1_000_000_000
     let mut cycle_start = 0_usize;
     let mut cycle_length = nb_cycles;
+    let cycle_search = span(Level::Info, "cycle search");
     for i in 1..=nb_cycles {
         work_grid = cycle(work_grid);
-        if let Some(previous_i) = previous_grids.insert(work_grid.clone(), i) {
+        // get before insert, to not lose the index of the start of the cycle
+        if let Some(&previous_i) = previous_grids.get(&work_grid) {
             cycle_start = previous_i;
             cycle_length = i - previous_i;
+            trace_event!(
+                Level::Debug,
+                "cycle found after {} spin cycles: starts at {}, length {}",
+                i,
+                cycle_start,
+                cycle_length
+            );
             break;
         }
+        previous_grids.insert(work_grid.clone(), i);
     }
+    drop(cycle_search);
 
     // Index of the grid after 1_000_000_000 cycles
     let cycle_index_stop = cycle_start + (nb_cycles - cycle_start) % cycle_length;
@@ -247,13 +246,58 @@ pub fn day_14_part_2(data: &str) -> i64 {
     let (final_grid, _) = previous_grids
         .iter()
         .find(|(_, &value)| value == cycle_index_stop)
-        .expect("Cycle index not found");
+        .ok_or("Cycle index not found")?;
 
-    final_grid
+    Ok(final_grid
         .indexed_iter()
         .filter(|(_, tile)| **tile == Tile::RoundedRock)
         .map(|((row, _), _)| nb_rows - row)
-        .sum::<usize>() as i64
+        .sum::<usize>() as i64)
+}
+
+// The platform after the part 1 tilt, with the load of each row.
+pub fn day_14_show(data: &str, backend: Backend) -> String {
+    let grid = parse_all(parse_input_data, data).expect("Failed to parse input data");
+    let tilted = tilt_vertically(grid, TiltDirection::North);
+    let nb_rows = tilted.nrows();
+
+    tilted
+        .render(backend)
+        .lines()
+        .zip(tilted.rows())
+        .enumerate()
+        .map(|(row_index, (line, row))| {
+            let nb_rocks = row
+                .iter()
+                .filter(|&&tile| tile == Tile::RoundedRock)
+                .count();
+            format!("{} {:>3}\n", line, nb_rocks * (nb_rows - row_index))
+        })
+        .collect()
+}
+
+// Every tilt of the part 2 cycles, until the platform repeats itself.
+pub fn day_14_frames(data: &str, max_frames: usize) -> Animation {
+    let mut grid = parse_all(parse_input_data, data).expect("Failed to parse input data");
+    let mut recorder = Recorder::new(max_frames);
+    let mut previous_grids: HashSet<Array2<Tile>> = HashSet::new();
+
+    while previous_grids.insert(grid.clone()) {
+        for direction in [
+            TiltDirection::North,
+            TiltDirection::West,
+            TiltDirection::South,
+            TiltDirection::East,
+        ] {
+            recorder.step(|| Frame::capture(&grid, Overlay::new()));
+            grid = match direction {
+                TiltDirection::North | TiltDirection::South => tilt_vertically(grid, direction),
+                TiltDirection::West | TiltDirection::East => tilt_horizontally(grid, direction),
+            };
+        }
+    }
+
+    recorder.finish(Frame::capture(&grid, Overlay::new()))
 }
 
 #[cfg(test)]
@@ -273,11 +317,44 @@ O.#..O.#.#
 
     #[test]
     fn test_day_14_part_1() {
-        assert_eq!(day_14_part_1(EXAMPLE), 136);
+        assert_eq!(day_14_part_1(EXAMPLE), Ok(136));
+        assert!(day_14_part_1("O.#\n.O").is_err());
     }
 
     #[test]
     fn test_day_14_part_2() {
-        assert_eq!(day_14_part_2(EXAMPLE), 64);
+        assert_eq!(day_14_part_2(EXAMPLE), Ok(64));
+        // the platform doesn't change, so the cycle starts at 0
+        // and the grid after the last cycle is the first one
+        assert_eq!(day_14_part_2("#.\n.O"), Ok(1));
+    }
+
+    #[test]
+    fn test_day_14_frames() {
+        let animation = day_14_frames(EXAMPLE, 1000);
+        assert_eq!(
+            animation.frames[0].render(Backend::Ascii),
+            format!("{}\n", EXAMPLE)
+        );
+        // the first tilt is the one of part 1
+        assert!(day_14_show(EXAMPLE, Backend::Ascii)
+            .starts_with(&animation.frames[1].render(Backend::Ascii)[..10]));
+        // the cycle starts after 3 cycles, and lasts 7, of 4 tilts each
+        assert_eq!(animation.frames.len(), 10 * 4 + 1);
+    }
+
+    #[test]
+    fn test_day_14_show() {
+        let shown = day_14_show(EXAMPLE, Backend::Ascii);
+        let lines = shown.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "OOOO.#.O..  50");
+        assert_eq!(lines[9], "#....#....   0");
+        assert_eq!(
+            lines
+                .iter()
+                .map(|line| line[11..].trim().parse::<i64>().unwrap())
+                .sum::<i64>(),
+            136
+        );
     }
 }
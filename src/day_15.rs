@@ -13,9 +13,11 @@ use nom::{
     combinator::map,
     multi::{many0, separated_list0},
     sequence::tuple,
-    IResult,
 };
 
+use crate::parsing::{parse_all, IResult};
+use crate::trace::{trace_event, Level};
+
 fn parse_input_data_part_1(data: &str) -> IResult<&str, Vec<Vec<char>>> {
     separated_list0(char(','), many0(none_of(",")))(data)
 }
@@ -73,23 +75,24 @@ fn parse_input_data_part_2(data: &str) -> IResult<&str, Vec<Operation>> {
     )(data)
 }
 
-pub fn day_15_part_1(data: &str) -> i64 {
-    let (_, data) = parse_input_data_part_1(data).expect("Failed to parse input data");
+pub fn day_15_part_1(data: &str) -> Result<i64, String> {
+    let data = parse_all(parse_input_data_part_1, data)?;
 
-    data.iter()
+    Ok(data
+        .iter()
         .map(|string| compute_hash(string) as i64)
-        .sum::<i64>()
+        .sum::<i64>())
 }
 
-pub fn day_15_part_2(data: &str) -> i64 {
-    let (_, operations) = parse_input_data_part_2(data).expect("Failed to parse input data");
+pub fn day_15_part_2(data: &str) -> Result<i64, String> {
+    let operations = parse_all(parse_input_data_part_2, data)?;
 
     // 256 boxes as an array containing Vec. gave a try with LinkedList but this is not stable.
     const INIT_BOX: Vec<Rc<Lens>> = Vec::new();
     let mut boxes: [Vec<Rc<Lens>>; 256] = [INIT_BOX; 256];
 
     for operation in operations {
-        //println!("{:?}", operation);
+        trace_event!(Level::Debug, "operation: {:?}", operation);
         let hash = operation.lens.hash as usize;
         match operation.operation_type {
             OperationType::Remove => {
@@ -115,18 +118,17 @@ pub fn day_15_part_2(data: &str) -> i64 {
                 }
             }
         }
-        /*println!(
-            "{:?}",
+        trace_event!(
+            Level::Trace,
+            "boxes: {:?}",
             boxes
                 .iter()
-                .filter(|lens| lens.len() > 0)
+                .filter(|lens| !lens.is_empty())
                 .collect::<Vec<&Vec<Rc<Lens>>>>()
-        );*/
+        );
     }
-    /*println!("{:?}", left);
-    println!("{:?}", boxes);*/
 
-    boxes
+    Ok(boxes
         .iter()
         .enumerate()
         .map(|(i, box_)| {
@@ -138,7 +140,7 @@ pub fn day_15_part_2(data: &str) -> i64 {
                 })
                 .sum::<i64>()
         })
-        .sum::<i64>()
+        .sum::<i64>())
 }
 
 #[cfg(test)]
@@ -149,11 +151,11 @@ mod tests {
 
     #[test]
     fn test_day_15_part_1() {
-        assert_eq!(day_15_part_1(EXAMPLE), 1320);
+        assert_eq!(day_15_part_1(EXAMPLE), Ok(1320));
     }
 
     #[test]
     fn test_day_15_part_2() {
-        assert_eq!(day_15_part_2(EXAMPLE), 145);
+        assert_eq!(day_15_part_2(EXAMPLE), Ok(145));
     }
 }
@@ -7,10 +7,14 @@
 use ndarray::Array2;
 use nom::{
     character::complete::{line_ending, one_of},
-    combinator::map,
+    combinator::{map, verify},
     multi::{many1, separated_list1},
-    IResult,
 };
+
+use crate::animation::{Animation, Frame, Recorder};
+use crate::image::{Image, Palette};
+use crate::parsing::{parse_all, IResult};
+use crate::render::{Backend, Glyph, Overlay, Render, Rgb, ToGlyph};
 use rayon::prelude::*;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -24,16 +28,20 @@ enum Tile {
 
 fn parse_input_data(data: &str) -> IResult<&str, Array2<Tile>> {
     map(
-        separated_list1(
-            line_ending,
-            many1(map(one_of("./\\-|"), |c| match c {
-                '.' => Tile::EmptySpace,
-                '/' => Tile::MirrorSlash,
-                '\\' => Tile::MirrorBackslash,
-                '-' => Tile::SplitterHorizontal,
-                '|' => Tile::SplitterVertical,
-                _ => unreachable!("Unknown tile"),
-            })),
+        // the rows must all have the same length to fit in the array
+        verify(
+            separated_list1(
+                line_ending,
+                many1(map(one_of("./\\-|"), |c| match c {
+                    '.' => Tile::EmptySpace,
+                    '/' => Tile::MirrorSlash,
+                    '\\' => Tile::MirrorBackslash,
+                    '-' => Tile::SplitterHorizontal,
+                    '|' => Tile::SplitterVertical,
+                    _ => unreachable!("Unknown tile"),
+                })),
+            ),
+            |rows: &Vec<Vec<Tile>>| rows.iter().all(|row| row.len() == rows[0].len()),
         ),
         |rows| {
             let nb_rows = rows.len();
@@ -67,35 +75,55 @@ struct Visit {
     left: bool,
 }
 
-#[allow(dead_code)]
-fn pretty_print_visits(grid: &Array2<Tile>, visits: &Array2<Visit>) {
-    for i in 0..visits.nrows() {
-        for j in 0..visits.ncols() {
-            let visit = visits[[i, j]];
-            print!(
-                "{}",
-                match grid[[i, j]] {
-                    Tile::MirrorSlash => '/',
-                    Tile::MirrorBackslash => '\\',
-                    Tile::SplitterHorizontal => '-',
-                    Tile::SplitterVertical => '|',
-                    Tile::EmptySpace => match (visit.up, visit.right, visit.down, visit.left) {
-                        (false, false, false, false) => '.',
-                        (true, false, false, false) => '^',
-                        (false, true, false, false) => '>',
-                        (false, false, true, false) => 'v',
-                        (false, false, false, true) => '<',
-                        _ => '#',
-                    },
-                }
-            );
+impl ToGlyph for Tile {
+    fn to_glyph(&self) -> Glyph {
+        match self {
+            Tile::EmptySpace => Glyph::new('.').with_unicode('·'),
+            Tile::MirrorSlash => Glyph::new('/').with_unicode('╱'),
+            Tile::MirrorBackslash => Glyph::new('\\').with_unicode('╲'),
+            Tile::SplitterHorizontal => Glyph::new('-').with_unicode('─'),
+            Tile::SplitterVertical => Glyph::new('|').with_unicode('│'),
+        }
+    }
+}
+
+// The grid with the beams on the empty tiles, like on the puzzle page.
+struct Beams<'a> {
+    grid: &'a Array2<Tile>,
+    visits: &'a Array2<Visit>,
+}
+
+impl Render for Beams<'_> {
+    fn dim(&self) -> (usize, usize) {
+        self.grid.dim()
+    }
+
+    fn glyph(&self, row: usize, col: usize) -> Glyph {
+        let tile = self.grid[[row, col]];
+        let visit = self.visits[[row, col]];
+        match tile {
+            Tile::EmptySpace => match (visit.up, visit.right, visit.down, visit.left) {
+                (false, false, false, false) => tile.to_glyph(),
+                (true, false, false, false) => Glyph::new('^').with_unicode('↑'),
+                (false, true, false, false) => Glyph::new('>').with_unicode('→'),
+                (false, false, true, false) => Glyph::new('v').with_unicode('↓'),
+                (false, false, false, true) => Glyph::new('<').with_unicode('←'),
+                _ => Glyph::new('#').with_unicode('┼'),
+            },
+            _ => tile.to_glyph(),
         }
-        println!();
     }
-    println!();
 }
 
 fn compute_beams(start: VisitSchedule, grid: &Array2<Tile>) -> Array2<Visit> {
+    compute_beams_with(start, grid, |_| {})
+}
+
+// on_step sees the visits before each step, to record the beams.
+fn compute_beams_with<F>(start: VisitSchedule, grid: &Array2<Tile>, mut on_step: F) -> Array2<Visit>
+where
+    F: FnMut(&Array2<Visit>),
+{
     let (nb_rows, nb_cols) = grid.dim();
 
     let mut visits = Array2::from_elem((nb_rows, nb_cols), Visit::default());
@@ -104,7 +132,7 @@ fn compute_beams(start: VisitSchedule, grid: &Array2<Tile>) -> Array2<Visit> {
     stack.push(start);
 
     while let Some(visit) = stack.pop() {
-        //pretty_print_visits(&grid, &visits);
+        on_step(&visits);
         let tile = &grid[[visit.row, visit.col]];
         let previous_visit = visits[[visit.row, visit.col]];
 
@@ -361,10 +389,10 @@ impl VisitScheduleExt for Array2<Visit> {
     }
 }
 
-pub fn day_16_part_1(data: &str) -> i64 {
-    let (_, grid) = parse_input_data(data).expect("Failed to parse input data");
+pub fn day_16_part_1(data: &str) -> Result<i64, String> {
+    let grid = parse_all(parse_input_data, data)?;
 
-    compute_beams(
+    Ok(compute_beams(
         VisitSchedule {
             row: 0,
             col: 0,
@@ -372,14 +400,14 @@ pub fn day_16_part_1(data: &str) -> i64 {
         },
         &grid,
     )
-    .compute_nb_visits() as i64
+    .compute_nb_visits() as i64)
 }
 
-pub fn day_16_part_2(data: &str) -> i64 {
-    let (_, grid) = parse_input_data(data).expect("Failed to parse input data");
+pub fn day_16_part_2(data: &str) -> Result<i64, String> {
+    let grid = parse_all(parse_input_data, data)?;
     let (nb_rows, nb_cols) = grid.dim();
 
-    (0..nb_cols)
+    Ok((0..nb_cols)
         .map(|col| VisitSchedule {
             row: 0,
             col,
@@ -403,7 +431,75 @@ pub fn day_16_part_2(data: &str) -> i64 {
         .par_bridge()
         .map(|schedule| compute_beams(schedule, &grid).compute_nb_visits())
         .max()
-        .unwrap_or(0) as i64
+        .unwrap_or(0) as i64)
+}
+
+// The beams of part 1, with the energised tiles highlighted.
+fn energised_overlay(data: &str) -> (Array2<Tile>, Array2<Visit>, Overlay) {
+    let grid = parse_all(parse_input_data, data).expect("Failed to parse input data");
+    let visits = compute_beams(
+        VisitSchedule {
+            row: 0,
+            col: 0,
+            direction: VisitHeading::Right,
+        },
+        &grid,
+    );
+
+    let overlay = energised_tiles(&visits);
+    (grid, visits, overlay)
+}
+
+fn energised_tiles(visits: &Array2<Visit>) -> Overlay {
+    let mut overlay = Overlay::new();
+    for (position, visit) in visits.indexed_iter() {
+        if visit.up || visit.right || visit.down || visit.left {
+            overlay.highlight(position, Rgb(120, 60, 0));
+        }
+    }
+    overlay
+}
+
+pub fn day_16_show(data: &str, backend: Backend) -> String {
+    let (grid, visits, overlay) = energised_overlay(data);
+    Beams {
+        grid: &grid,
+        visits: &visits,
+    }
+    .render_with_overlay(backend, &overlay)
+}
+
+pub fn day_16_image(data: &str, palette: &Palette, scale: usize) -> Image {
+    let (grid, visits, overlay) = energised_overlay(data);
+    let beams = Beams {
+        grid: &grid,
+        visits: &visits,
+    };
+    Image::from_render(&beams, &overlay, palette, scale)
+}
+
+// The beams of part 1 as they propagate, step by step.
+pub fn day_16_frames(data: &str, max_frames: usize) -> Animation {
+    let grid = parse_all(parse_input_data, data).expect("Failed to parse input data");
+    let capture = |visits: &Array2<Visit>| {
+        let beams = Beams {
+            grid: &grid,
+            visits,
+        };
+        Frame::capture(&beams, energised_tiles(visits))
+    };
+
+    let mut recorder = Recorder::new(max_frames);
+    let visits = compute_beams_with(
+        VisitSchedule {
+            row: 0,
+            col: 0,
+            direction: VisitHeading::Right,
+        },
+        &grid,
+        |visits| recorder.step(|| capture(visits)),
+    );
+    recorder.finish(capture(&visits))
 }
 
 #[cfg(test)]
@@ -423,11 +519,54 @@ mod tests {
 
     #[test]
     fn test_day_16_part_1() {
-        assert_eq!(day_16_part_1(EXAMPLE), 46);
+        assert_eq!(day_16_part_1(EXAMPLE), Ok(46));
+        assert!(day_16_part_1("..\n.").is_err());
     }
 
     #[test]
     fn test_day_16_part_2() {
-        assert_eq!(day_16_part_2(EXAMPLE), 51);
+        assert_eq!(day_16_part_2(EXAMPLE), Ok(51));
+    }
+
+    #[test]
+    fn test_day_16_show() {
+        let shown = day_16_show(EXAMPLE, Backend::Ascii);
+        assert_eq!(shown.lines().next(), Some(">|<<<\\...."));
+        assert_eq!(shown.lines().count(), 10);
+
+        let shown = day_16_show(EXAMPLE, Backend::Ansi);
+        assert_eq!(shown.matches("\x1b[48;2;120;60;0m").count(), 46);
+    }
+
+    #[test]
+    fn test_day_16_frames() {
+        let animation = day_16_frames(EXAMPLE, 1000);
+        let first = animation.frames.first().unwrap();
+        let last = animation.frames.last().unwrap();
+        assert_eq!(
+            first.render(Backend::Ascii).lines().next(),
+            Some(".|...\\....")
+        );
+        assert_eq!(
+            last.render(Backend::Ascii),
+            day_16_show(EXAMPLE, Backend::Ascii)
+        );
+
+        let animation = day_16_frames(EXAMPLE, 10);
+        assert!(animation.frames.len() <= 11);
+    }
+
+    #[test]
+    fn test_day_16_image() {
+        let image = day_16_image(EXAMPLE, &Palette::default(), 1);
+        assert_eq!((image.width, image.height), (10, 10));
+        assert_eq!(
+            image
+                .pixels
+                .iter()
+                .filter(|&&pixel| pixel == Rgb(120, 60, 0))
+                .count(),
+            46
+        );
     }
 }
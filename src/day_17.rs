@@ -21,14 +21,19 @@ use nom::{
         complete::{line_ending, satisfy},
         is_digit,
     },
-    combinator::map,
+    combinator::{map, verify},
     multi::{many1, separated_list1},
-    IResult,
 };
 
+use crate::parsing::{parse_all, IResult};
+
 fn parse_input_data(data: &str) -> IResult<&str, Array2<u8>> {
     map(
-        separated_list1(line_ending, many1(satisfy(|c| is_digit(c as u8)))),
+        // the rows must all have the same length to fit in the array
+        verify(
+            separated_list1(line_ending, many1(satisfy(|c| is_digit(c as u8)))),
+            |rows: &Vec<Vec<char>>| rows.iter().all(|row| row.len() == rows[0].len()),
+        ),
         |rows| {
             let nb_rows = rows.len();
             let nb_cols = rows.first().map_or(0, |row| row.len());
@@ -96,28 +101,33 @@ fn dijkstra_part_1(grid: &Array2<u8>, start: (usize, usize), end: (usize, usize)
     // I think it could have been possible to do it
     // a bit more consily, but I got off by one errors
     // a bit too many times.
-    priority_queue.push(Reverse((
-        grid[[start.0, start.1 + 1]] as usize,
-        CostlyTile {
-            row: start.0,
-            col: start.1 + 1,
-            straight: Straight {
-                direction: Direction::Right,
-                straight_length: 2,
+    // The grid can be a single row or column.
+    if let Some(&cost) = grid.get([start.0, start.1 + 1]) {
+        priority_queue.push(Reverse((
+            cost as usize,
+            CostlyTile {
+                row: start.0,
+                col: start.1 + 1,
+                straight: Straight {
+                    direction: Direction::Right,
+                    straight_length: 2,
+                },
             },
-        },
-    )));
-    priority_queue.push(Reverse((
-        grid[[start.0 + 1, start.1]] as usize,
-        CostlyTile {
-            row: start.0 + 1,
-            col: start.1,
-            straight: Straight {
-                direction: Direction::Down,
-                straight_length: 2,
+        )));
+    }
+    if let Some(&cost) = grid.get([start.0 + 1, start.1]) {
+        priority_queue.push(Reverse((
+            cost as usize,
+            CostlyTile {
+                row: start.0 + 1,
+                col: start.1,
+                straight: Straight {
+                    direction: Direction::Down,
+                    straight_length: 2,
+                },
             },
-        },
-    )));
+        )));
+    }
 
     while let Some(Reverse((cost, costly_tile))) = priority_queue.pop() {
         let CostlyTile { row, col, straight } = costly_tile;
@@ -278,29 +288,33 @@ fn dijkstra_part_2(grid: &Array2<u8>) -> Option<usize> {
         return None;
     }
 
-    // Start on the right and down
-    priority_queue.push(Reverse((
-        cost_four_next_tiles(&grid, start.0, start.1 + 1, Direction::Right).unwrap(),
-        CostlyTile {
-            row: start.0,
-            col: start.1 + 4,
-            straight: Straight {
-                direction: Direction::Right,
-                straight_length: 5,
+    // Start on the right and down, a 4 wide grid can't go right
+    if let Some(cost) = cost_four_next_tiles(&grid, start.0, start.1 + 1, Direction::Right) {
+        priority_queue.push(Reverse((
+            cost,
+            CostlyTile {
+                row: start.0,
+                col: start.1 + 4,
+                straight: Straight {
+                    direction: Direction::Right,
+                    straight_length: 5,
+                },
             },
-        },
-    )));
-    priority_queue.push(Reverse((
-        cost_four_next_tiles(&grid, start.0 + 1, start.1, Direction::Down).unwrap(),
-        CostlyTile {
-            row: start.0 + 4,
-            col: start.1,
-            straight: Straight {
-                direction: Direction::Down,
-                straight_length: 5,
+        )));
+    }
+    if let Some(cost) = cost_four_next_tiles(&grid, start.0 + 1, start.1, Direction::Down) {
+        priority_queue.push(Reverse((
+            cost,
+            CostlyTile {
+                row: start.0 + 4,
+                col: start.1,
+                straight: Straight {
+                    direction: Direction::Down,
+                    straight_length: 5,
+                },
             },
-        },
-    )));
+        )));
+    }
 
     while let Some(Reverse((cost, costly_tile))) = priority_queue.pop() {
         let CostlyTile { row, col, straight } = costly_tile;
@@ -464,16 +478,18 @@ fn dijkstra_part_2(grid: &Array2<u8>) -> Option<usize> {
     None
 }
 
-pub fn day_17_part_1(data: &str) -> i64 {
-    let (_, grid) = parse_input_data(data).expect("Failed to parse input data");
+pub fn day_17_part_1(data: &str) -> Result<i64, String> {
+    let grid = parse_all(parse_input_data, data)?;
     let start = (0, 0);
     let end = (grid.nrows() - 1, grid.ncols() - 1);
-    dijkstra_part_1(&grid, start, end).expect("Failed to find a path") as i64
+    let cost = dijkstra_part_1(&grid, start, end).ok_or("Failed to find a path")?;
+    Ok(cost as i64)
 }
 
-pub fn day_17_part_2(data: &str) -> i64 {
-    let (_, grid) = parse_input_data(data).expect("Failed to parse input data");
-    dijkstra_part_2(&grid).expect("Failed to find a path") as i64
+pub fn day_17_part_2(data: &str) -> Result<i64, String> {
+    let grid = parse_all(parse_input_data, data)?;
+    let cost = dijkstra_part_2(&grid).ok_or("Failed to find a path")?;
+    Ok(cost as i64)
 }
 
 #[cfg(test)]
@@ -507,7 +523,9 @@ mod tests {
 
     #[test]
     fn test_day_17_part_1() {
-        assert_eq!(day_17_part_1(EXAMPLE), 102);
+        assert_eq!(day_17_part_1(EXAMPLE), Ok(102));
+        assert!(day_17_part_1("1").is_err());
+        assert!(day_17_part_1("12\n1").is_err());
     }
 
     #[test]
@@ -521,7 +539,8 @@ mod tests {
 
     #[test]
     fn test_day_17_part_2() {
-        assert_eq!(day_17_part_2(EXAMPLE), 94);
-        assert_eq!(day_17_part_2(SECOND_EXAMPLE), 71);
+        assert_eq!(day_17_part_2(EXAMPLE), Ok(94));
+        assert!(day_17_part_2("1111\n1111\n1111\n1111").is_err());
+        assert_eq!(day_17_part_2(SECOND_EXAMPLE), Ok(71));
     }
 }
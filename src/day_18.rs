@@ -14,16 +14,22 @@
         - yolo it
 */
 
+use std::collections::HashSet;
+
+use ndarray::Array2;
 use nom::{
     branch::alt,
-    bytes::complete::tag,
     character::complete::{line_ending, satisfy, u64},
     combinator::{map, map_res, value},
     multi::{count, separated_list0},
     sequence::tuple,
-    IResult,
 };
 
+use crate::geometry::{self, PickError, Point};
+use crate::image::{Image, Palette};
+use crate::parsing::{parse_all, tag, IResult};
+use crate::render::{Backend, Glyph, Overlay, Render, Rgb, ToGlyph};
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Direction {
     Up,
@@ -148,71 +154,78 @@ fn flood_fill_inside(matrice: &Array2<bool>) -> Array2<bool> {
     visited
 }
 
-#[allow(dead_code)]
-fn pretty_print_matrice(matrice: &Array2<bool>) {
-    for row in matrice.rows() {
-        for tile in row {
-            print!("{}", if *tile { '#' } else { '.' });
-        }
-        println!();
-    }
-}
-
 pub fn day_18_part_1(data: &str) -> i64 {
     let (_, instructions) = parse_input_data(data).expect("Failed to parse input data");
     let matrice = build_matrice(&instructions);
     let flooded = flood_fill_inside(&matrice);
-    //pretty_print_matrice(&flooded);
     // count the number of true
     flooded.iter().filter(|tile| **tile).count() as i64
 }*/
 
-fn polygon_inner_area(points: Vec<(isize, isize)>) -> isize {
-    let mut area = 0;
-    let n = points.len();
-
-    for i in 0..n - 1 {
-        let (x1, y1) = points[i];
-        let (x2, y2) = points[i + 1];
+// The corners of the trench, walking the instructions from (0, 0).
+fn dig_plan_vertices(instructions: &[Instruction]) -> Result<Vec<Point>, String> {
+    let mut points: Vec<Point> = Vec::new();
+    let mut current_tile = (0_i64, 0_i64);
 
-        area += x1 * y2;
-        area -= y1 * x2;
+    for instruction in instructions {
+        let steps = i64::try_from(instruction.steps).map_err(|_| "Too many steps")?;
+        let next_tile = match instruction.direction {
+            Direction::Up => current_tile
+                .0
+                .checked_sub(steps)
+                .map(|row| (row, current_tile.1)),
+            Direction::Down => current_tile
+                .0
+                .checked_add(steps)
+                .map(|row| (row, current_tile.1)),
+            Direction::Left => current_tile
+                .1
+                .checked_sub(steps)
+                .map(|col| (current_tile.0, col)),
+            Direction::Right => current_tile
+                .1
+                .checked_add(steps)
+                .map(|col| (current_tile.0, col)),
+        };
+        current_tile = next_tile.ok_or("The trench goes too far")?;
+        points.push(current_tile);
     }
 
-    (area.abs() / 2) as isize
+    Ok(points)
 }
 
-fn digging_area(instructions: &[Instruction]) -> isize {
-    let mut points = Vec::new();
-    let mut current_tile = (0_isize, 0_isize);
-    let mut boundary_length = 0_isize;
-
-    for instruction in instructions {
-        let steps = instruction.steps as isize;
-        boundary_length += steps;
-        match instruction.direction {
-            Direction::Up => current_tile.0 -= steps,
-            Direction::Down => current_tile.0 += steps,
-            Direction::Left => current_tile.1 -= steps,
-            Direction::Right => current_tile.1 += steps,
-        }
-        points.push(current_tile);
+fn digging_area(instructions: &[Instruction]) -> Result<i64, String> {
+    let points = dig_plan_vertices(instructions)?;
+    if points.last().is_some_and(|&last| last != (0, 0)) {
+        return Err("The trench doesn't come back to the start".to_string());
     }
 
-    // Pick's theorem
-    polygon_inner_area(points) + boundary_length / 2 + 1
+    // Pick's theorem, the trench itself is the boundary
+    let interior = match geometry::interior_points(&points) {
+        Ok(interior) => interior,
+        Err(PickError::Overflow) => return Err("The lagoon is too big".to_string()),
+        Err(PickError::NotSimple) => {
+            return Err("The trench isn't a simple closed polygon".to_string())
+        }
+        Err(PickError::Empty) => return Err("The trench doesn't enclose anything".to_string()),
+    };
+    instructions
+        .iter()
+        .try_fold(interior, |area, instruction| {
+            area.checked_add(instruction.steps as u64)
+        })
+        .and_then(|area| i64::try_from(area).ok())
+        .ok_or_else(|| "The lagoon is too big".to_string())
 }
 
-pub fn day_18_part_1(data: &str) -> i64 {
-    let (_, instructions) = parse_input_data(data).expect("Failed to parse input data");
-    digging_area(&instructions) as i64
+pub fn day_18_part_1(data: &str) -> Result<i64, String> {
+    let instructions = parse_all(parse_input_data, data)?;
+    digging_area(&instructions)
 }
 
-pub fn day_18_part_2(data: &str) -> i64 {
-    let (_, instructions) = parse_input_data(data).expect("Failed to parse input data");
-
-    // We need to translate the instructions from the colour number to a new list of instructions
-    let fixed_instructions = instructions
+// We need to translate the instructions from the colour number to a new list of instructions
+fn decode_instructions(instructions: &[Instruction]) -> Result<Vec<Instruction>, String> {
+    instructions
         .iter()
         .map(|instruction| {
             let colour = instruction.colour;
@@ -223,19 +236,197 @@ pub fn day_18_part_2(data: &str) -> i64 {
                 0x1 => Direction::Down,
                 0x2 => Direction::Left,
                 0x3 => Direction::Up,
-                _ => panic!("Unexpected last digit: {}", last_digit),
+                _ => return Err(format!("Unexpected last digit: {}", last_digit)),
             };
             // the new steps is the number without the last digit, using a shift
             let new_steps = colour >> 4;
-            Instruction {
+            Ok(Instruction {
                 direction: new_direction,
                 steps: new_steps as usize,
                 colour,
-            }
+            })
         })
-        .collect::<Vec<_>>();
+        .collect()
+}
+
+pub fn day_18_part_2(data: &str) -> Result<i64, String> {
+    let instructions = parse_all(parse_input_data, data)?;
+    digging_area(&decode_instructions(&instructions)?)
+}
+
+/*
+    SVG of the dig plan, each edge of the polygon in the colour of its
+    instruction. The coordinates are scaled to fit in SVG_SIZE pixels,
+    as the part 2 lagoon is millions of tiles wide.
+*/
+const SVG_SIZE: f64 = 1000.0;
+const SVG_MARGIN: f64 = 10.0;
+
+fn dig_plan_svg(instructions: &[Instruction], filled: bool) -> Result<String, String> {
+    let vertices = dig_plan_vertices(instructions)?;
+    let min_row = vertices.iter().map(|(row, _)| *row).min().unwrap_or(0);
+    let max_row = vertices.iter().map(|(row, _)| *row).max().unwrap_or(0);
+    let min_col = vertices.iter().map(|(_, col)| *col).min().unwrap_or(0);
+    let max_col = vertices.iter().map(|(_, col)| *col).max().unwrap_or(0);
+
+    // in floats, the drawing can be less precise but not wrap
+    let offset = |value: i64, min: i64| (value as i128 - min as i128) as f64;
+    let extent = offset(max_row, min_row)
+        .max(offset(max_col, min_col))
+        .max(1.0);
+    let scale = SVG_SIZE / extent;
+    let width = offset(max_col, min_col) * scale + 2.0 * SVG_MARGIN;
+    let height = offset(max_row, min_row) * scale + 2.0 * SVG_MARGIN;
+    let to_svg = |(row, col): Point| {
+        (
+            offset(col, min_col) * scale + SVG_MARGIN,
+            offset(row, min_row) * scale + SVG_MARGIN,
+        )
+    };
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" viewBox=\"0 0 {:.2} {:.2}\">\n",
+        width, height, width, height
+    );
+
+    if filled {
+        let points = vertices
+            .iter()
+            .map(|&vertex| {
+                let (x, y) = to_svg(vertex);
+                format!("{:.2},{:.2}", x, y)
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        svg.push_str(&format!(
+            "  <polygon points=\"{}\" fill=\"#8b5a2b\" fill-opacity=\"0.5\" />\n",
+            points
+        ));
+    }
+
+    // the last vertex is where the first instruction starts
+    let starts = vertices
+        .iter()
+        .cycle()
+        .skip(vertices.len().saturating_sub(1));
+    for ((&start, &end), instruction) in starts.zip(vertices.iter()).zip(instructions) {
+        let (x1, y1) = to_svg(start);
+        let (x2, y2) = to_svg(end);
+        svg.push_str(&format!(
+            "  <line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"#{:06x}\" stroke-width=\"2\" stroke-linecap=\"square\" />\n",
+            x1, y1, x2, y2, instruction.colour
+        ));
+    }
+
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
 
-    digging_area(&fixed_instructions) as i64
+pub fn day_18_svg(data: &str, part: usize, filled: bool) -> Result<String, String> {
+    let instructions = parse_all(parse_input_data, data)?;
+    match part {
+        1 => dig_plan_svg(&instructions, filled),
+        2 => dig_plan_svg(&decode_instructions(&instructions)?, filled),
+        _ => Err(format!("Day 18 has no part {}", part)),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum LagoonTile {
+    Ground,
+    Trench { colour: u32 },
+    Interior,
+}
+
+impl ToGlyph for LagoonTile {
+    fn to_glyph(&self) -> Glyph {
+        match self {
+            LagoonTile::Ground => Glyph::new('.').with_unicode('·'),
+            LagoonTile::Trench { colour } => Glyph::new('#')
+                .with_unicode('█')
+                .with_colour(Rgb::from_u32(*colour)),
+            LagoonTile::Interior => Glyph::new('#').with_unicode('░'),
+        }
+    }
+}
+
+// The lagoon tile by tile, like the part 1 I didn't keep.
+// Only usable with the part 1 instructions, part 2 is way too big.
+fn dig_lagoon(instructions: &[Instruction]) -> Array2<LagoonTile> {
+    let mut trench: Vec<(Point, u32)> = Vec::new();
+    // tiles connected to the tile above them by the trench
+    let mut going_north: HashSet<Point> = HashSet::new();
+    let mut current_tile = (0_i64, 0_i64);
+
+    for instruction in instructions {
+        for _ in 0..instruction.steps {
+            let previous_tile = current_tile;
+            match instruction.direction {
+                Direction::Up => {
+                    current_tile.0 -= 1;
+                    going_north.insert(previous_tile);
+                }
+                Direction::Down => {
+                    current_tile.0 += 1;
+                    going_north.insert(current_tile);
+                }
+                Direction::Left => current_tile.1 -= 1,
+                Direction::Right => current_tile.1 += 1,
+            }
+            trench.push((current_tile, instruction.colour));
+        }
+    }
+
+    let min_row = trench.iter().map(|((row, _), _)| *row).min().unwrap_or(0);
+    let max_row = trench.iter().map(|((row, _), _)| *row).max().unwrap_or(0);
+    let min_col = trench.iter().map(|((_, col), _)| *col).min().unwrap_or(0);
+    let max_col = trench.iter().map(|((_, col), _)| *col).max().unwrap_or(0);
+
+    let mut lagoon = Array2::from_elem(
+        (
+            (max_row - min_row + 1) as usize,
+            (max_col - min_col + 1) as usize,
+        ),
+        LagoonTile::Ground,
+    );
+    for ((row, col), colour) in trench {
+        lagoon[((row - min_row) as usize, (col - min_col) as usize)] =
+            LagoonTile::Trench { colour };
+    }
+
+    // Scanline: we are inside after crossing an odd number of
+    // trench tiles going north.
+    for (row_index, mut row) in lagoon.outer_iter_mut().enumerate() {
+        let mut inside = false;
+        for (col_index, tile) in row.iter_mut().enumerate() {
+            let position = (row_index as i64 + min_row, col_index as i64 + min_col);
+            match tile {
+                LagoonTile::Trench { .. } => {
+                    if going_north.contains(&position) {
+                        inside = !inside;
+                    }
+                }
+                _ => {
+                    if inside {
+                        *tile = LagoonTile::Interior;
+                    }
+                }
+            }
+        }
+    }
+
+    lagoon
+}
+
+// The lagoon of part 1, with the trench in its colours.
+pub fn day_18_show(data: &str, backend: Backend) -> String {
+    let instructions = parse_all(parse_input_data, data).expect("Failed to parse input data");
+    dig_lagoon(&instructions).render(backend)
+}
+
+pub fn day_18_image(data: &str, palette: &Palette, scale: usize) -> Image {
+    let instructions = parse_all(parse_input_data, data).expect("Failed to parse input data");
+    Image::from_render(&dig_lagoon(&instructions), &Overlay::new(), palette, scale)
 }
 
 #[cfg(test)]
@@ -259,11 +450,87 @@ U 2 (#7a21e3)";
 
     #[test]
     fn test_day_18_part_1() {
-        assert_eq!(day_18_part_1(EXAMPLE), 62);
+        assert_eq!(day_18_part_1(EXAMPLE), Ok(62));
+        assert!(day_18_part_1("R 2 (#000000)\nD 2 (#000000)").is_err());
+        assert!(day_18_part_1("R 9223372036854775807 (#000000)\nR 1 (#000000)").is_err());
+        // the trench goes back over itself
+        assert_eq!(
+            day_18_part_1("R 2 (#000000)\nL 2 (#000000)"),
+            Err("The trench isn't a simple closed polygon".to_string())
+        );
+        for empty in ["", "\n", "R 0 (#000000)"] {
+            assert_eq!(
+                day_18_part_1(empty),
+                Err("The trench doesn't enclose anything".to_string())
+            );
+        }
+        assert!(day_18_part_2("").is_err());
+        assert!(day_18_part_2("R 0 (#000000)").is_err());
     }
 
     #[test]
     fn test_day_18_part_2() {
-        assert_eq!(day_18_part_2(EXAMPLE), 952408144115);
+        assert_eq!(day_18_part_2(EXAMPLE), Ok(952408144115));
+        assert!(day_18_part_2("R 1 (#000004)").is_err());
+    }
+
+    #[test]
+    fn test_dig_plan_svg() {
+        let svg = day_18_svg(EXAMPLE, 1, false).unwrap();
+        assert!(svg.starts_with(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"687\" height=\"1020\""
+        ));
+        assert_eq!(svg.matches("<line ").count(), 14);
+        assert!(!svg.contains("<polygon"));
+        // R 6 (#70c710) from the start, which is the top left corner
+        assert!(svg.contains(
+            "<line x1=\"10.00\" y1=\"10.00\" x2=\"676.67\" y2=\"10.00\" stroke=\"#70c710\""
+        ));
+
+        let svg = day_18_svg(EXAMPLE, 2, true).unwrap();
+        assert_eq!(svg.matches("<polygon ").count(), 1);
+        assert_eq!(svg.matches("<line ").count(), 14);
+        // the part 2 coordinates are scaled down as well
+        assert!(svg.contains("width=\"1020\""));
+        assert!(svg.contains("stroke=\"#0dc571\""));
+
+        assert!(day_18_svg(EXAMPLE, 3, true).is_err());
+        assert!(day_18_svg("R 2 (#00000", 1, true).is_err());
+        assert!(day_18_svg("R 1 (#000004)", 2, true).is_err());
+    }
+
+    #[test]
+    fn test_dig_lagoon() {
+        let instructions = parse_all(parse_input_data, EXAMPLE).unwrap();
+        let lagoon = dig_lagoon(&instructions);
+        assert_eq!(lagoon.dim(), (10, 7));
+        assert_eq!(
+            lagoon
+                .iter()
+                .filter(|&&tile| tile != LagoonTile::Ground)
+                .count(),
+            62
+        );
+        // the start is dug by the last instruction
+        assert_eq!(lagoon[(0, 0)], LagoonTile::Trench { colour: 0x7a21e3 });
+        assert_eq!(lagoon[(0, 1)], LagoonTile::Trench { colour: 0x70c710 });
+
+        let shown = day_18_show(EXAMPLE, Backend::Ascii);
+        assert_eq!(shown.lines().nth(3), Some("..#####"));
+        assert_eq!(shown.lines().nth(6), Some("#####.."));
+    }
+
+    #[test]
+    fn test_day_18_image() {
+        let palette = Palette::new(Rgb(0, 0, 0)).with('#', Rgb(255, 255, 255));
+        let image = day_18_image(EXAMPLE, &palette, 1);
+        assert_eq!((image.width, image.height), (7, 10));
+        // the trench in the colours of the instructions
+        assert_eq!(image.pixels[1], Rgb(0x70, 0xc7, 0x10));
+        // R 6 (#70c710) then D 5 (#0dc571)
+        assert_eq!(image.pixels[6 + 7], Rgb(0x0d, 0xc5, 0x71));
+        // the interior, and the ground
+        assert_eq!(image.pixels[7 + 1], Rgb(255, 255, 255));
+        assert_eq!(image.pixels[3 * 7], Rgb(0, 0, 0));
     }
 }
@@ -7,6 +7,12 @@
 */
 
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use crate::box_set::{BoxSet, HyperRectangle};
+use crate::dot::{DotGraph, ToDot, ACCEPT_COLOUR, REJECT_COLOUR, START_COLOUR};
+use crate::parsing::{parse_all, IResult};
+use crate::trace::{trace_event, Level};
 
 use nom::{
     branch::alt,
@@ -15,7 +21,6 @@ use nom::{
     combinator::{map, value},
     multi::{count, separated_list0},
     sequence::{delimited, preceded, tuple},
-    IResult,
 };
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -26,6 +31,18 @@ enum Category {
     S,
 }
 
+impl Category {
+    // The axis of the category in the x, m, a, s search space.
+    fn axis(&self) -> usize {
+        match self {
+            Category::X => 0,
+            Category::M => 1,
+            Category::A => 2,
+            Category::S => 3,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum RuleType {
     Send,
@@ -54,9 +71,10 @@ impl RuleCondition {
         };
 
         // take into account that is not greater or equal…
+        // saturating is fine, the ratings are between 1 and 4000
         let value = match self.greater_lower {
-            GreaterLower::Greater => self.value + 1,
-            GreaterLower::Lower => self.value - 1,
+            GreaterLower::Greater => self.value.saturating_add(1),
+            GreaterLower::Lower => self.value.saturating_sub(1),
         };
 
         RuleCondition {
@@ -67,6 +85,23 @@ impl RuleCondition {
     }
 }
 
+// Like in the input, a<2006
+impl fmt::Display for RuleCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let category = match self.category {
+            Category::X => 'x',
+            Category::M => 'm',
+            Category::A => 'a',
+            Category::S => 's',
+        };
+        let greater_lower = match self.greater_lower {
+            GreaterLower::Greater => '>',
+            GreaterLower::Lower => '<',
+        };
+        write!(f, "{}{}{}", category, greater_lower, self.value)
+    }
+}
+
 #[derive(Debug, Clone)]
 struct Rule {
     rule_type: RuleType,
@@ -89,8 +124,16 @@ struct PartRating {
 }
 
 impl PartRating {
-    fn sum(&self) -> u64 {
-        self.x + self.m + self.a + self.s
+    fn sum(&self) -> Option<u64> {
+        self.x
+            .checked_add(self.m)?
+            .checked_add(self.a)?
+            .checked_add(self.s)
+    }
+
+    #[allow(dead_code)]
+    fn as_point(&self) -> [i64; 4] {
+        [self.x as i64, self.m as i64, self.a as i64, self.s as i64]
     }
 }
 
@@ -191,8 +234,13 @@ fn parse_input_data(data: &str) -> IResult<&str, (Vec<Workflow>, Vec<PartRating>
     )(data)
 }
 
-fn process_part_rating(part_rating: &PartRating, workflows: &HashMap<String, Workflow>) -> bool {
-    let mut current_workflow = workflows.get("in").expect("Failed to find in workflow");
+fn process_part_rating(
+    part_rating: &PartRating,
+    workflows: &HashMap<String, Workflow>,
+) -> Result<bool, String> {
+    let mut current_workflow = workflows.get("in").ok_or("Failed to find in workflow")?;
+    // a part that goes through every workflow without a decision is in a loop
+    let mut nb_sends = 0;
 
     // CurrentRules as a VecDeque
     let mut current_rules: VecDeque<Rule> = current_workflow.rules.iter().cloned().collect();
@@ -220,28 +268,33 @@ fn process_part_rating(part_rating: &PartRating, workflows: &HashMap<String, Wor
 
         match rule.rule_type {
             RuleType::Accept => {
-                return true;
+                return Ok(true);
             }
             RuleType::Reject => {
-                return false;
+                return Ok(false);
             }
             RuleType::Send => {
                 let destination = rule
                     .send_destination
                     .as_ref()
-                    .expect("Send rule without destination");
-                current_workflow = workflows.get(destination).expect("Failed to find workflow");
+                    .ok_or("Send rule without destination")?;
+                current_workflow = workflows
+                    .get(destination)
+                    .ok_or_else(|| format!("Failed to find workflow {}", destination))?;
                 current_rules = current_workflow.rules.iter().cloned().collect();
+                nb_sends += 1;
+                if nb_sends > workflows.len() {
+                    return Err("The workflows loop".to_string());
+                }
             }
         }
     }
 
-    panic!("Failed to finish workflow, weird");
+    Err("Failed to finish workflow, weird".to_string())
 }
 
-pub fn day_19_part_1(data: &str) -> i64 {
-    let (_, workflows_and_part_ratings) =
-        parse_input_data(data).expect("Failed to parse input data");
+pub fn day_19_part_1(data: &str) -> Result<i64, String> {
+    let workflows_and_part_ratings = parse_all(parse_input_data, data)?;
     let (workflows, part_ratings) = workflows_and_part_ratings;
 
     let hashmap_workflows: std::collections::HashMap<String, Workflow> = workflows
@@ -250,11 +303,17 @@ pub fn day_19_part_1(data: &str) -> i64 {
         .collect();
 
     // for each part rating, find the matching workflow
-    part_ratings
-        .iter()
-        .filter(|part_rating| process_part_rating(&part_rating, &hashmap_workflows))
-        .map(|part_rating| part_rating.sum())
-        .sum::<u64>() as i64
+    let mut total = 0_i64;
+    for part_rating in part_ratings.iter() {
+        if process_part_rating(part_rating, &hashmap_workflows)? {
+            total = part_rating
+                .sum()
+                .and_then(|sum| i64::try_from(sum).ok())
+                .and_then(|sum| total.checked_add(sum))
+                .ok_or("The ratings overflow")?;
+        }
+    }
+    Ok(total)
 }
 
 /**
@@ -262,131 +321,35 @@ pub fn day_19_part_1(data: &str) -> i64 {
  * Part 2: We could consider the workflows as a graph, and look for all the paths
  * while keeping track of the ranges.
  */
+// A search range is a box in the 4 dimensions x, m, a, s.
+type SearchRange = HyperRectangle<4>;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct SearchRange {
-    min_x: u64,
-    max_x: u64,
-
-    min_m: u64,
-    max_m: u64,
-
-    min_a: u64,
-    max_a: u64,
-
-    min_s: u64,
-    max_s: u64,
-}
-
-impl Default for SearchRange {
-    fn default() -> Self {
-        SearchRange {
-            min_x: 1,
-            max_x: 4000,
-
-            min_m: 1,
-            max_m: 4000,
-
-            min_a: 1,
-            max_a: 4000,
-
-            min_s: 1,
-            max_s: 4000,
-        }
-    }
+fn full_search_range() -> SearchRange {
+    HyperRectangle::new([1; 4], [4000; 4])
 }
 
-impl SearchRange {
-    fn is_valid(&self) -> bool {
-        self.min_x <= self.max_x
-            && self.min_m <= self.max_m
-            && self.min_a <= self.max_a
-            && self.min_s <= self.max_s
-    }
-
-    fn apply_rule_condition(&self, rule_condition: &RuleCondition) -> Option<SearchRange> {
-        let mut new_search_range = self.clone();
-
-        let RuleCondition {
-            category,
-            value,
-            greater_lower,
-        } = rule_condition;
-
-        match category {
-            Category::X => match greater_lower {
-                GreaterLower::Greater => {
-                    new_search_range.min_x = value + 1;
-                }
-                GreaterLower::Lower => {
-                    new_search_range.max_x = value - 1;
-                }
-            },
-            Category::M => match greater_lower {
-                GreaterLower::Greater => {
-                    new_search_range.min_m = value + 1;
-                }
-                GreaterLower::Lower => {
-                    new_search_range.max_m = value - 1;
-                }
-            },
-            Category::A => match greater_lower {
-                GreaterLower::Greater => {
-                    new_search_range.min_a = value + 1;
-                }
-                GreaterLower::Lower => {
-                    new_search_range.max_a = value - 1;
-                }
-            },
-            Category::S => match greater_lower {
-                GreaterLower::Greater => {
-                    new_search_range.min_s = value + 1;
-                }
-                GreaterLower::Lower => {
-                    new_search_range.max_s = value - 1;
-                }
-            },
+fn apply_rule_condition(
+    search_range: &SearchRange,
+    rule_condition: &RuleCondition,
+) -> Option<SearchRange> {
+    let RuleCondition {
+        category,
+        value,
+        greater_lower,
+    } = rule_condition;
+    let value = i64::try_from(*value).unwrap_or(i64::MAX);
+
+    match greater_lower {
+        GreaterLower::Greater => {
+            search_range.restrict(category.axis(), value.saturating_add(1), i64::MAX)
         }
-
-        match new_search_range.is_valid() {
-            true => Some(new_search_range),
-            false => None,
+        GreaterLower::Lower => {
+            search_range.restrict(category.axis(), i64::MIN, value.saturating_sub(1))
         }
     }
-
-    #[allow(dead_code)]
-    fn contains_part_rating(&self, part_rating: &PartRating) -> bool {
-        self.min_x <= part_rating.x
-            && part_rating.x <= self.max_x
-            && self.min_m <= part_rating.m
-            && part_rating.m <= self.max_m
-            && self.min_a <= part_rating.a
-            && part_rating.a <= self.max_a
-            && self.min_s <= part_rating.s
-            && part_rating.s <= self.max_s
-    }
-
-    #[allow(dead_code)]
-    fn contains_search_range(&self, search_range: &SearchRange) -> bool {
-        self.min_x <= search_range.min_x
-            && search_range.max_x <= self.max_x
-            && self.min_m <= search_range.min_m
-            && search_range.max_m <= self.max_m
-            && self.min_a <= search_range.min_a
-            && search_range.max_a <= self.max_a
-            && self.min_s <= search_range.min_s
-            && search_range.max_s <= self.max_s
-    }
-
-    fn nb_combinations(&self) -> u64 {
-        (self.max_x - self.min_x + 1)
-            * (self.max_m - self.min_m + 1)
-            * (self.max_a - self.min_a + 1)
-            * (self.max_s - self.min_s + 1)
-    }
 }
 
-fn compute_valid_ranges(workflows: &[Workflow]) -> Vec<SearchRange> {
+fn compute_valid_ranges(workflows: &[Workflow]) -> Result<Vec<SearchRange>, String> {
     let hashmap_workflows: std::collections::HashMap<String, Workflow> = workflows
         .iter()
         .map(|workflow| (workflow.name.clone(), workflow.clone()))
@@ -400,13 +363,13 @@ fn compute_valid_ranges(workflows: &[Workflow]) -> Vec<SearchRange> {
     let mut visited: HashSet<(String, SearchRange)> = HashSet::new();
 
     // start with the "in" workflow
-    stack.push(("in".to_string(), SearchRange::default()));
+    stack.push(("in".to_string(), full_search_range()));
 
     let mut valid_ranges: Vec<SearchRange> = Vec::new();
 
     while let Some(stack_element) = stack.pop() {
         if visited.contains(&stack_element) {
-            //println!("Already visited: {:?}", stack_element);
+            trace_event!(Level::Trace, "already visited: {:?}", stack_element);
             continue;
         }
 
@@ -416,9 +379,10 @@ fn compute_valid_ranges(workflows: &[Workflow]) -> Vec<SearchRange> {
 
         let workflow = hashmap_workflows
             .get(&workflow_name)
-            .expect("Failed to find workflow");
+            .ok_or_else(|| format!("Failed to find workflow {}", workflow_name))?;
 
-        let mut work_search_range = search_range.clone();
+        // None once the previous rules took all the parts
+        let mut work_search_range = Some(search_range);
 
         for rule in workflow.rules.iter() {
             let Rule {
@@ -426,36 +390,35 @@ fn compute_valid_ranges(workflows: &[Workflow]) -> Vec<SearchRange> {
                 condition,
                 send_destination,
             } = rule;
-            //println!("{:?}", rule);
-            //println!("{:?}", work_search_range);
-
-            let mut current_search_range = work_search_range.clone();
+            trace_event!(Level::Trace, "rule: {:?}", rule);
+            trace_event!(Level::Trace, "search range: {:?}", work_search_range);
 
-            if !current_search_range.is_valid() {
-                //println!("Invalid search range: {:?}", current_search_range);
-                continue;
-            }
+            let Some(remaining_search_range) = work_search_range else {
+                break;
+            };
 
-            if let Some(condition) = &condition {
-                if let Some(new_search_range) = work_search_range.apply_rule_condition(condition) {
-                    current_search_range = new_search_range;
+            // restrict returns None for an empty range, so a condition that
+            // no part matches sends nothing, and one that every part matches
+            // leaves nothing for the next rules
+            let current_search_range = match &condition {
+                Some(condition) => {
+                    work_search_range =
+                        apply_rule_condition(&remaining_search_range, &condition.invert());
+                    apply_rule_condition(&remaining_search_range, condition)
                 }
-                let inverted_condition = condition.invert();
-                if let Some(new_search_range) =
-                    work_search_range.apply_rule_condition(&inverted_condition)
-                {
-                    work_search_range = new_search_range.clone();
+                None => {
+                    work_search_range = None;
+                    Some(remaining_search_range)
                 }
-            }
+            };
+            let Some(current_search_range) = current_search_range else {
+                continue;
+            };
 
             match rule_type {
                 RuleType::Accept => {
-                    if search_range.is_valid() {
-                        //println!("Found a solution: {:?}", current_search_range);
-                        valid_ranges.push(current_search_range);
-                    } else {
-                        panic!("WTF");
-                    }
+                    trace_event!(Level::Debug, "accepted range: {:?}", current_search_range);
+                    valid_ranges.push(current_search_range);
                 }
                 RuleType::Reject => {
                     continue;
@@ -463,9 +426,8 @@ fn compute_valid_ranges(workflows: &[Workflow]) -> Vec<SearchRange> {
                 RuleType::Send => {
                     let destination = send_destination
                         .as_ref()
-                        .expect("Send rule without destination");
-                    let new_stack_element: (String, SearchRange);
-                    new_stack_element = (destination.clone(), current_search_range.clone());
+                        .ok_or("Send rule without destination")?;
+                    let new_stack_element = (destination.clone(), current_search_range);
                     if !visited.contains(&new_stack_element) {
                         stack.push(new_stack_element);
                     }
@@ -474,18 +436,52 @@ fn compute_valid_ranges(workflows: &[Workflow]) -> Vec<SearchRange> {
         }
     }
 
-    return valid_ranges;
+    Ok(valid_ranges)
 }
 
-pub fn day_19_part_2(data: &str) -> i64 {
-    let (_, workflows) = parse_workflows(data).expect("Failed to parse input data");
-    let valid_ranges = compute_valid_ranges(&workflows);
+pub fn day_19_part_2(data: &str) -> Result<i64, String> {
+    let (workflows, _) = parse_all(parse_input_data, data)?;
+    let valid_ranges = compute_valid_ranges(&workflows)?;
 
-    // it looks like there is no overlap between the valid ranges in the input data
-    valid_ranges
-        .iter()
-        .map(|search_range| search_range.nb_combinations())
-        .sum::<u64>() as i64
+    // It looks like there is no overlap between the valid ranges in the input data,
+    // but the box set counts the overlapping combinations only once anyway.
+    let accepted: BoxSet<4> = valid_ranges.into_iter().collect();
+    Ok(accepted.volume() as i64)
+}
+
+// The workflows and where their rules send the parts,
+// from "in" in blue to the accepted and rejected states.
+impl ToDot for [Workflow] {
+    fn to_dot(&self) -> String {
+        let mut graph = DotGraph::new("day_19");
+        graph.highlighted_node("in", START_COLOUR);
+        graph.highlighted_node("A", ACCEPT_COLOUR);
+        graph.highlighted_node("R", REJECT_COLOUR);
+
+        for workflow in self.iter() {
+            for rule in workflow.rules.iter() {
+                let destination = match rule.rule_type {
+                    RuleType::Send => rule
+                        .send_destination
+                        .as_deref()
+                        .expect("Send rule without destination"),
+                    RuleType::Accept => "A",
+                    RuleType::Reject => "R",
+                };
+                let condition = rule
+                    .condition
+                    .as_ref()
+                    .map(|condition| condition.to_string());
+                graph.edge(&workflow.name, destination, condition.as_deref());
+            }
+        }
+        graph.to_dot()
+    }
+}
+
+pub fn day_19_dot(data: &str) -> Result<String, String> {
+    let (workflows, _) = parse_all(parse_input_data, data)?;
+    Ok(workflows.to_dot())
 }
 
 #[cfg(test)]
@@ -512,7 +508,10 @@ hdj{m>838:A,pv}
 
     #[test]
     fn test_day_19_part_1() {
-        assert_eq!(day_19_part_1(EXAMPLE), 19114);
+        assert_eq!(day_19_part_1(EXAMPLE), Ok(19114));
+        assert!(day_19_part_1("in{A}\n\n{x=1,m=2,a=3,s=18446744073709551615}").is_err());
+        assert!(day_19_part_1("in{a}\na{in}\n\n{x=1,m=2,a=3,s=4}").is_err());
+        assert!(day_19_part_1("in{b}\n\n{x=1,m=2,a=3,s=4}").is_err());
     }
 
     #[test]
@@ -522,23 +521,23 @@ hdj{m>838:A,pv}
             parse_input_data(EXAMPLE).expect("Failed to parse input data");
         let (workflows, part_ratings) = workflows_and_part_ratings;
 
-        let valid_ranges = compute_valid_ranges(&workflows);
+        let valid_ranges = compute_valid_ranges(&workflows).unwrap();
 
         let sum = part_ratings
             .iter()
             .filter(|part_rating| {
                 valid_ranges
                     .iter()
-                    .any(|range| range.contains_part_rating(part_rating))
+                    .any(|range| range.contains_point(&part_rating.as_point()))
             })
-            .map(|part_rating| part_rating.sum())
+            .map(|part_rating| part_rating.sum().unwrap())
             .sum::<u64>();
         assert_eq!(sum, 19114);
     }
 
     #[test]
     fn test_day_19_part_2() {
-        assert_eq!(day_19_part_2(EXAMPLE), 167409079868000);
+        assert_eq!(day_19_part_2(EXAMPLE), Ok(167409079868000));
     }
 
     #[test]
@@ -582,14 +581,49 @@ hdj{m>838:A,pv}
         );
     }
 
+    #[test]
+    fn test_day_19_part_2_always_or_never_true() {
+        // The first rule is always true, so nothing is left for the catch-all rule.
+        let (_, workflows) = parse_workflows("in{x<5000:A,A}").expect("Failed to parse input data");
+        let valid_ranges = compute_valid_ranges(&workflows).unwrap();
+        assert_eq!(valid_ranges, vec![full_search_range()]);
+
+        // The first rule is never true, so it accepts nothing.
+        let (_, workflows) = parse_workflows("in{x>5000:A,R}").expect("Failed to parse input data");
+        assert!(compute_valid_ranges(&workflows).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_day_19_part_2_overlapping_ranges() {
+        // The accepted boxes of two different workflows overlap
+        // where both x and m are below 2001.
+        let (_, low_x) = parse_workflows("in{x<2001:A,R}").expect("Failed to parse input data");
+        let (_, low_m) = parse_workflows("in{m<2001:A,R}").expect("Failed to parse input data");
+        let valid_ranges = compute_valid_ranges(&low_x)
+            .unwrap()
+            .into_iter()
+            .chain(compute_valid_ranges(&low_m).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(valid_ranges.len(), 2);
+        assert!(valid_ranges[0].intersection(&valid_ranges[1]).is_some());
+
+        // 2000 * 4000^3 twice, minus the 2000 * 2000 * 4000^2 counted twice
+        let accepted: BoxSet<4> = valid_ranges.into_iter().collect();
+        assert_eq!(
+            accepted.volume(),
+            2 * 2000 * 4000_u128.pow(3) - 2000 * 2000 * 4000_u128.pow(2)
+        );
+        assert_eq!(day_19_part_2(EXAMPLE), Ok(167409079868000));
+    }
+
     #[test]
     fn day_19_no_ranges_overlap() {
         let (_, workflows) = parse_workflows(EXAMPLE).expect("Failed to parse input data");
-        let valid_ranges = compute_valid_ranges(&workflows);
+        let valid_ranges = compute_valid_ranges(&workflows).unwrap();
         // check if a range
         for i in 0..valid_ranges.len() {
             for j in i + 1..valid_ranges.len() {
-                if valid_ranges[i].contains_search_range(&valid_ranges[j]) {
+                if valid_ranges[i].contains(&valid_ranges[j]) {
                     panic!(
                         "Found overlapping ranges: {:?} and {:?}",
                         valid_ranges[i], valid_ranges[j]
@@ -598,4 +632,18 @@ hdj{m>838:A,pv}
             }
         }
     }
+
+    #[test]
+    fn test_day_19_dot() {
+        let dot = day_19_dot(EXAMPLE).unwrap();
+        assert!(day_19_dot("in{x<5:A").is_err());
+        assert!(dot.contains("    \"in\" [style=\"filled\", fillcolor=\"#4a90d9\""));
+        assert!(dot.contains("    \"R\" [style=\"filled\", fillcolor=\"#d9534f\""));
+        assert!(dot.contains("    \"px\" -> \"qkq\" [label=\"a<2006\"];"));
+        assert!(dot.contains("    \"px\" -> \"A\" [label=\"m>2090\"];"));
+        assert!(dot.contains("    \"px\" -> \"rfg\";"));
+        assert!(dot.contains("    \"pv\" -> \"R\" [label=\"a>1716\"];"));
+        // one edge per rule
+        assert_eq!(dot.matches(" -> ").count(), 25);
+    }
 }
@@ -0,0 +1,323 @@
+/*
+    Differential testing of the optimised solutions against the references.
+
+    Many small inputs are generated, each one is solved by both, and the
+    first disagreement is shrunk to a small input before being reported:
+    chunks of lines are removed as long as the solutions still disagree,
+    starting with big chunks and halving until single lines.
+
+    The inputs the reference can't handle, because they're too big or
+    not valid puzzle inputs once shrunk, are not checked. The other ones
+    are valid, so an error from the optimised solution is a failure too.
+*/
+
+use crate::generate::{self, Random};
+use crate::reference;
+use crate::{day_05, day_06, day_11, day_12, day_18, day_19};
+
+pub type Solution = fn(&str) -> Result<i64, String>;
+type Reference = fn(&str) -> Option<i64>;
+type Generator = fn(&mut Random, usize) -> String;
+
+pub struct Case {
+    pub day: u32,
+    pub part: u32,
+    pub solution: Solution,
+    pub reference: Reference,
+    pub generator: Generator,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Agree,
+    Unchecked,
+    Mismatch { expected: i64, found: i64 },
+    Error(String),
+    Panic(String),
+    // an error without a reference to tell if the input was valid,
+    // so it may as well be the right answer
+    Rejected(String),
+}
+
+impl Outcome {
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            self,
+            Outcome::Mismatch { .. } | Outcome::Error(_) | Outcome::Panic(_)
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct Counterexample {
+    pub seed: u64,
+    pub size: usize,
+    pub input: String,
+    pub outcome: Outcome,
+}
+
+// Small inputs, so the references stay fast.
+fn small_almanac(random: &mut Random, size: usize) -> String {
+    generate::almanac(random, size, 100)
+}
+
+fn small_races(random: &mut Random, size: usize) -> String {
+    let width = random.between(1, 2) as u32;
+    generate::races(random, size.clamp(1, 3), width)
+}
+
+fn small_springs(random: &mut Random, size: usize) -> String {
+    generate::springs(random, size, 10, 10)
+}
+
+// The rows are unfolded five times for part 2.
+fn small_unfolded_springs(random: &mut Random, size: usize) -> String {
+    generate::springs(random, size, 8, 2)
+}
+
+fn small_dig_plan(random: &mut Random, size: usize) -> String {
+    generate::dig_plan(random, size.clamp(2, 4), 3, 3)
+}
+
+pub fn cases() -> Vec<Case> {
+    let case = |day, part, solution, reference, generator| Case {
+        day,
+        part,
+        solution,
+        reference,
+        generator,
+    };
+    vec![
+        case(
+            5,
+            1,
+            day_05::day_5_part_1,
+            reference::day_5_part_1,
+            small_almanac,
+        ),
+        case(
+            5,
+            2,
+            day_05::day_5_part_2,
+            reference::day_5_part_2,
+            small_almanac,
+        ),
+        case(
+            6,
+            1,
+            day_06::day_6_part_1,
+            reference::day_6_part_1,
+            small_races,
+        ),
+        case(
+            6,
+            2,
+            day_06::day_6_part_2,
+            reference::day_6_part_2,
+            small_races,
+        ),
+        case(
+            11,
+            1,
+            day_11::day_11_part_1,
+            reference::day_11_part_1,
+            generate::day_11,
+        ),
+        case(
+            11,
+            2,
+            day_11::day_11_part_2,
+            reference::day_11_part_2,
+            generate::day_11,
+        ),
+        case(
+            12,
+            1,
+            day_12::day_12_part_1,
+            reference::day_12_part_1,
+            small_springs,
+        ),
+        case(
+            12,
+            2,
+            day_12::day_12_part_2,
+            reference::day_12_part_2,
+            small_unfolded_springs,
+        ),
+        case(
+            18,
+            1,
+            day_18::day_18_part_1,
+            reference::day_18_part_1,
+            small_dig_plan,
+        ),
+        case(
+            18,
+            2,
+            day_18::day_18_part_2,
+            reference::day_18_part_2,
+            small_dig_plan,
+        ),
+        case(
+            19,
+            1,
+            day_19::day_19_part_1,
+            reference::day_19_part_1,
+            generate::day_19,
+        ),
+        case(
+            19,
+            2,
+            day_19::day_19_part_2,
+            reference::day_19_part_2,
+            generate::day_19,
+        ),
+    ]
+}
+
+// The reference goes first, the inputs it rejects could make
+// the optimised solution panic for good reasons.
+pub fn check(case: &Case, input: &str) -> Outcome {
+    let expected = match (case.reference)(input) {
+        Some(expected) => expected,
+        None => return Outcome::Unchecked,
+    };
+    let solution = case.solution;
+    match crate::catch_panic(|| solution(input)) {
+        Ok(Ok(found)) if found == expected => Outcome::Agree,
+        Ok(Ok(found)) => Outcome::Mismatch { expected, found },
+        // the reference solved it, so it was a valid input
+        Ok(Err(error)) => Outcome::Error(error),
+        Err(message) => Outcome::Panic(message),
+    }
+}
+
+// Removes chunks of items while they still fail, the chunks start as big
+// as the whole list and are halved until single items.
+pub fn remove_chunks<T, F>(mut items: Vec<T>, fails: F) -> Vec<T>
+where
+    T: Clone,
+    F: Fn(&[T]) -> bool,
+{
+    let mut chunk_size = items.len().max(1);
+    loop {
+        let mut start = 0;
+        while start < items.len() {
+            let end = (start + chunk_size).min(items.len());
+            let mut candidate = items[..start].to_vec();
+            candidate.extend_from_slice(&items[end..]);
+            match fails(&candidate) {
+                true => items = candidate,
+                false => start += chunk_size,
+            }
+        }
+        if chunk_size == 1 {
+            break;
+        }
+        chunk_size = chunk_size.div_ceil(2);
+    }
+    items
+}
+
+// Removes chunks of lines while the input still fails.
+pub fn shrink<F>(input: &str, fails: F) -> String
+where
+    F: Fn(&str) -> bool,
+{
+    let lines = input.lines().map(str::to_string).collect::<Vec<_>>();
+    let lines = remove_chunks(lines, |lines| fails(&(lines.join("\n") + "\n")));
+    lines.join("\n") + "\n"
+}
+
+// Generates nb_inputs inputs of growing sizes, up to max_size,
+// and returns the first failure, shrunk.
+pub fn search(case: &Case, nb_inputs: usize, max_size: usize, seed: u64) -> Option<Counterexample> {
+    (0..nb_inputs).find_map(|index| {
+        let seed = seed + index as u64;
+        let size = 1 + index * max_size / nb_inputs.max(1);
+        let input = (case.generator)(&mut Random::new(seed), size);
+        if !check(case, &input).is_failure() {
+            return None;
+        }
+        let input = shrink(&input, |input| check(case, input).is_failure());
+        let outcome = check(case, &input);
+        Some(Counterexample {
+            seed,
+            size,
+            input,
+            outcome,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_no_counterexample(day: u32, nb_inputs: usize, max_size: usize) {
+        for case in cases().iter().filter(|case| case.day == day) {
+            if let Some(counterexample) = search(case, nb_inputs, max_size, 0) {
+                panic!(
+                    "Day {} part {} failed with seed {} and size {}: {:?}\n{}",
+                    case.day,
+                    case.part,
+                    counterexample.seed,
+                    counterexample.size,
+                    counterexample.outcome,
+                    counterexample.input
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_day_05() {
+        assert_no_counterexample(5, 200, 8);
+    }
+
+    #[test]
+    fn test_day_06() {
+        assert_no_counterexample(6, 200, 3);
+    }
+
+    #[test]
+    fn test_day_11() {
+        assert_no_counterexample(11, 100, 20);
+    }
+
+    #[test]
+    fn test_day_12() {
+        assert_no_counterexample(12, 60, 6);
+    }
+
+    #[test]
+    fn test_day_18() {
+        assert_no_counterexample(18, 100, 4);
+    }
+
+    #[test]
+    fn test_day_19() {
+        assert_no_counterexample(19, 50, 10);
+    }
+
+    #[test]
+    fn test_shrink() {
+        let input = (0..100).map(|n| format!("{}\n", n)).collect::<String>();
+        let fails = |input: &str| {
+            input.lines().any(|line| line == "42") && input.lines().any(|line| line == "77")
+        };
+        assert_eq!(shrink(&input, fails), "42\n77\n");
+        assert_eq!(shrink("a\nb\n", |_| false), "a\nb\n");
+    }
+
+    #[test]
+    fn test_check() {
+        let cases = cases();
+        let day_6 = &cases[2];
+        assert_eq!(
+            check(day_6, "Time:      7  15   30\nDistance:  9  40  200\n"),
+            Outcome::Agree
+        );
+        assert_eq!(check(day_6, "nonsense"), Outcome::Unchecked);
+    }
+}
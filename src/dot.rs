@@ -0,0 +1,119 @@
+/*
+    Graphviz DOT export, for the puzzles that are graphs.
+
+    Render them with something like:
+        dot -Tsvg day_08.dot > day_08.svg
+
+    The graphs are small builders: add the nodes that need attributes,
+    add the edges, and get the DOT text. Nodes only used by edges are fine,
+    graphviz creates them.
+*/
+
+use std::fmt::Write;
+
+pub trait ToDot {
+    fn to_dot(&self) -> String;
+}
+
+// Colours shared by the exporters, so the graphs look alike.
+pub const START_COLOUR: &str = "#4a90d9";
+pub const ACCEPT_COLOUR: &str = "#5cb85c";
+pub const REJECT_COLOUR: &str = "#d9534f";
+
+#[derive(Debug, Clone, Default)]
+pub struct DotGraph {
+    name: String,
+    nodes: Vec<(String, Vec<(String, String)>)>,
+    edges: Vec<(String, String, Option<String>)>,
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl DotGraph {
+    pub fn new(name: &str) -> DotGraph {
+        DotGraph {
+            name: name.to_string(),
+            ..DotGraph::default()
+        }
+    }
+
+    pub fn node(&mut self, id: &str, attributes: &[(&str, &str)]) {
+        self.nodes.push((
+            id.to_string(),
+            attributes
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        ));
+    }
+
+    // A filled node, for the start and the terminal states.
+    pub fn highlighted_node(&mut self, id: &str, colour: &str) {
+        self.node(
+            id,
+            &[
+                ("style", "filled"),
+                ("fillcolor", colour),
+                ("fontcolor", "white"),
+            ],
+        );
+    }
+
+    pub fn edge(&mut self, from: &str, to: &str, label: Option<&str>) {
+        self.edges
+            .push((from.to_string(), to.to_string(), label.map(str::to_string)));
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut output = String::new();
+        writeln!(output, "digraph {} {{", quote(&self.name)).unwrap();
+        for (id, attributes) in self.nodes.iter() {
+            let attributes = attributes
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, quote(value)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(output, "    {} [{}];", quote(id), attributes).unwrap();
+        }
+        for (from, to, label) in self.edges.iter() {
+            match label {
+                Some(label) => writeln!(
+                    output,
+                    "    {} -> {} [label={}];",
+                    quote(from),
+                    quote(to),
+                    quote(label)
+                ),
+                None => writeln!(output, "    {} -> {};", quote(from), quote(to)),
+            }
+            .unwrap();
+        }
+        output.push_str("}\n");
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dot_graph() {
+        let mut graph = DotGraph::new("test");
+        graph.highlighted_node("in", START_COLOUR);
+        graph.edge("in", "A", Some("a<2006"));
+        graph.edge("in", "say \"hi\"", None);
+
+        assert_eq!(
+            graph.to_dot(),
+            "digraph \"test\" {
+    \"in\" [style=\"filled\", fillcolor=\"#4a90d9\", fontcolor=\"white\"];
+    \"in\" -> \"A\" [label=\"a<2006\"];
+    \"in\" -> \"say \\\"hi\\\"\";
+}
+"
+        );
+    }
+}
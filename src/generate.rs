@@ -0,0 +1,1108 @@
+/*
+    Random puzzle inputs, to stress the solutions on bigger inputs
+    than the examples and my own input:
+
+        advent_of_code_2023_rust generate 2023 10 --size 140 --seed 42 > inputs/day_10.txt
+
+    The inputs have the shape of the real ones, and the properties the
+    puzzles promise and the solutions rely on: a single closed loop for day 10,
+    ghosts that eventually meet for day 8, exactly one reflection and one
+    smudge per pattern for day 13, a dig plan that doesn't cross itself for
+    day 18, and so on.
+
+    The size is the main dimension of the input: the side of the grid,
+    the number of lines, games, cards or workflows. Without --size, the size
+    of the real inputs is used.
+
+    The same seed always gives the same input. The random numbers come
+    from a SplitMix64, it's good enough and it avoids a crate.
+*/
+
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Write;
+
+#[derive(Debug, Clone)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Between low and high, both included.
+    pub fn between(&mut self, low: u64, high: u64) -> u64 {
+        if high <= low {
+            return low;
+        }
+        match (high - low).checked_add(1) {
+            Some(nb_values) => low + self.next_u64() % nb_values,
+            None => self.next_u64(),
+        }
+    }
+
+    pub fn index(&mut self, len: usize) -> usize {
+        self.between(0, len.saturating_sub(1) as u64) as usize
+    }
+
+    pub fn chance(&mut self, probability: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64) < probability
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.index(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.between(0, i as u64) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+fn word(random: &mut Random, min_len: u64, max_len: u64) -> String {
+    (0..random.between(min_len, max_len))
+        .map(|_| (b'a' + random.between(0, 25) as u8) as char)
+        .collect()
+}
+
+fn join<T: ToString>(items: &[T], separator: &str) -> String {
+    items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn grid_to_string(grid: &[Vec<u8>]) -> String {
+    grid.iter()
+        .map(|row| String::from_utf8_lossy(row).into_owned() + "\n")
+        .collect()
+}
+
+// The outline of a random spanning tree of a width × height lattice,
+// drawn three times bigger. A tree has no hole, so the outline is a simple loop.
+// The points are the corners of the cells, consecutive points are neighbours.
+fn tree_outline(random: &mut Random, width: usize, height: usize) -> Vec<(usize, usize)> {
+    // the tree nodes are blocks of 2 × 2 cells, the edges fill the cells between them.
+    // the blocks are thick enough for the loop to enclose some corners.
+    let mut cells = vec![vec![false; 3 * width - 1]; 3 * height - 1];
+    let mut fill = |a: (usize, usize), b: (usize, usize)| {
+        for cells_row in cells[3 * a.0.min(b.0)..3 * a.0.max(b.0) + 2].iter_mut() {
+            cells_row[3 * a.1.min(b.1)..3 * a.1.max(b.1) + 2].fill(true);
+        }
+    };
+    let mut visited = vec![vec![false; width]; height];
+    let start = (random.index(height), random.index(width));
+    let mut stack = vec![start];
+    visited[start.0][start.1] = true;
+    fill(start, start);
+
+    // randomised depth first search, for long corridors
+    while let Some(&(row, col)) = stack.last() {
+        let neighbours = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+            .iter()
+            .map(|(d_row, d_col)| (row as isize + d_row, col as isize + d_col))
+            .filter(|&(row, col)| {
+                row >= 0 && col >= 0 && (row as usize) < height && (col as usize) < width
+            })
+            .map(|(row, col)| (row as usize, col as usize))
+            .filter(|&(row, col)| !visited[row][col])
+            .collect::<Vec<_>>();
+        if neighbours.is_empty() {
+            stack.pop();
+            continue;
+        }
+        let (next_row, next_col) = *random.choose(&neighbours);
+        visited[next_row][next_col] = true;
+        fill((row, col), (next_row, next_col));
+        stack.push((next_row, next_col));
+    }
+
+    let is_cell = |row: isize, col: isize| {
+        row >= 0
+            && col >= 0
+            && cells
+                .get(row as usize)
+                .and_then(|cells_row| cells_row.get(col as usize))
+                .is_some_and(|&cell| cell)
+    };
+
+    // the sides between a cell of the tree and a cell outside
+    let mut links = vec![vec![Vec::new(); 3 * width]; 3 * height];
+    let mut link = |a: (usize, usize), b: (usize, usize)| {
+        links[a.0][a.1].push(b);
+        links[b.0][b.1].push(a);
+    };
+    for (row, cells_row) in cells.iter().enumerate() {
+        for (col, &cell) in cells_row.iter().enumerate() {
+            if !cell {
+                continue;
+            }
+            let (r, c) = (row as isize, col as isize);
+            if !is_cell(r - 1, c) {
+                link((row, col), (row, col + 1));
+            }
+            if !is_cell(r + 1, c) {
+                link((row + 1, col), (row + 1, col + 1));
+            }
+            if !is_cell(r, c - 1) {
+                link((row, col), (row + 1, col));
+            }
+            if !is_cell(r, c + 1) {
+                link((row, col + 1), (row + 1, col + 1));
+            }
+        }
+    }
+
+    // every corner of the outline has two links, follow them
+    let mut outline = vec![(0, 0)];
+    let mut previous = (0, 0);
+    let mut current = links[0][0][0];
+    while current != (0, 0) {
+        outline.push(current);
+        let next = links[current.0][current.1]
+            .iter()
+            .find(|&&next| next != previous)
+            .copied()
+            .expect("The outline is a loop");
+        previous = current;
+        current = next;
+    }
+    outline
+}
+
+const DIGIT_NAMES: [&str; 9] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+
+// Letters, digits and digits written in letters. Each line has at least a digit.
+pub fn day_01(random: &mut Random, size: usize) -> String {
+    let mut output = String::new();
+    for _ in 0..size {
+        let nb_tokens = random.between(2, 8);
+        let digit_position = random.between(0, nb_tokens - 1);
+        let mut line = String::new();
+        for token in 0..nb_tokens {
+            match (token == digit_position, random.between(0, 2)) {
+                (true, _) | (false, 0) => line.push((b'1' + random.between(0, 8) as u8) as char),
+                (false, 1) => {
+                    let name = *random.choose(&DIGIT_NAMES);
+                    line.push_str(name)
+                }
+                _ => line.push_str(&word(random, 1, 5)),
+            }
+        }
+        writeln!(output, "{}", line).unwrap();
+    }
+    output
+}
+
+const CUBE_COLOURS: [&str; 3] = ["red", "green", "blue"];
+
+pub fn day_02(random: &mut Random, size: usize) -> String {
+    let mut output = String::new();
+    for game in 1..=size {
+        let reveals = (0..random.between(1, 6))
+            .map(|_| {
+                let mut colours = CUBE_COLOURS;
+                random.shuffle(&mut colours);
+                let nb_colours = random.between(1, 3) as usize;
+                colours[..nb_colours]
+                    .iter()
+                    .map(|colour| format!("{} {}", random.between(1, 20), colour))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .collect::<Vec<_>>();
+        writeln!(output, "Game {}: {}", game, reveals.join("; ")).unwrap();
+    }
+    output
+}
+
+const SCHEMATIC_SYMBOLS: &[u8] = b"*#+$/=%@&-";
+
+// Most numbers touch a symbol, and the symbols touch some numbers.
+pub fn day_03(random: &mut Random, size: usize) -> String {
+    let mut grid = vec![vec![b'.'; size]; size];
+    let mut numbers = Vec::new();
+    for (row, grid_row) in grid.iter_mut().enumerate() {
+        let mut col = random.between(0, 3) as usize;
+        loop {
+            let number = random.between(1, 999).to_string();
+            if col + number.len() > size {
+                break;
+            }
+            grid_row[col..col + number.len()].copy_from_slice(number.as_bytes());
+            numbers.push((row, col, col + number.len()));
+            col += number.len() + random.between(2, 12) as usize;
+        }
+    }
+
+    for (row, start, end) in numbers {
+        if !random.chance(0.85) {
+            continue;
+        }
+        let free_neighbours = (row.saturating_sub(1)..=(row + 1).min(size - 1))
+            .flat_map(|r| (start.saturating_sub(1)..=end.min(size - 1)).map(move |c| (r, c)))
+            .filter(|&(r, c)| grid[r][c] == b'.')
+            .collect::<Vec<_>>();
+        if free_neighbours.is_empty() {
+            continue;
+        }
+        let (r, c) = *random.choose(&free_neighbours);
+        grid[r][c] = *random.choose(SCHEMATIC_SYMBOLS);
+    }
+    grid_to_string(&grid)
+}
+
+fn spaced_numbers(numbers: &[u64]) -> String {
+    join(
+        &numbers
+            .iter()
+            .map(|number| format!("{:>2}", number))
+            .collect::<Vec<_>>(),
+        " ",
+    )
+}
+
+// The copies never go past the last card, and the number of copies
+// stays reasonable.
+pub fn day_04(random: &mut Random, size: usize) -> String {
+    const NB_WINNING: usize = 10;
+    const NB_NUMBERS: usize = 25;
+    const MAX_COPIES: u64 = 1_000_000_000;
+
+    let width = size.to_string().len();
+    let mut copies = vec![1_u64; size];
+    let mut output = String::new();
+    for card in 0..size {
+        let mut nb_matches = match random.chance(0.5) {
+            true => 0,
+            false => random.between(1, NB_WINNING as u64) as usize,
+        }
+        .min(size - card - 1);
+        while nb_matches > 0
+            && (card + 1..=card + nb_matches).any(|next| copies[next] + copies[card] > MAX_COPIES)
+        {
+            nb_matches -= 1;
+        }
+        for next in card + 1..=card + nb_matches {
+            copies[next] += copies[card];
+        }
+
+        let mut pool = (1..=99).collect::<Vec<u64>>();
+        random.shuffle(&mut pool);
+        let winning = &pool[..NB_WINNING];
+        let mut numbers = pool[..nb_matches].to_vec();
+        numbers.extend(&pool[NB_WINNING..NB_WINNING + NB_NUMBERS - nb_matches]);
+        random.shuffle(&mut numbers);
+        writeln!(
+            output,
+            "Card {:>width$}: {} | {}",
+            card + 1,
+            spaced_numbers(winning),
+            spaced_numbers(&numbers),
+            width = width
+        )
+        .unwrap();
+    }
+    output
+}
+
+const ALMANAC_CATEGORIES: [&str; 8] = [
+    "seed",
+    "soil",
+    "fertilizer",
+    "water",
+    "light",
+    "temperature",
+    "humidity",
+    "location",
+];
+
+pub fn day_05(random: &mut Random, size: usize) -> String {
+    almanac(random, size, 1 << 32)
+}
+
+// Each map is a permutation of nb_pieces pieces of a range below limit,
+// and the identity outside, like the real maps.
+pub fn almanac(random: &mut Random, nb_pieces: usize, limit: u64) -> String {
+    let seeds = (0..10)
+        .flat_map(|_| {
+            let start = random.between(0, limit - 1);
+            let length = random.between(1, (limit - start).min(limit / 20));
+            [start, length]
+        })
+        .collect::<Vec<_>>();
+    let mut output = format!("seeds: {}\n", join(&seeds, " "));
+
+    for categories in ALMANAC_CATEGORIES.windows(2) {
+        let mut cuts = Vec::new();
+        while cuts.len() < nb_pieces + 1 {
+            cuts.push(random.between(0, limit));
+            cuts.sort_unstable();
+            cuts.dedup();
+        }
+        let mut pieces = cuts
+            .windows(2)
+            .map(|cut| (cut[0], cut[1] - cut[0]))
+            .collect::<Vec<_>>();
+        random.shuffle(&mut pieces);
+
+        let mut destination = cuts[0];
+        let mut maps = Vec::new();
+        for (source, length) in pieces {
+            maps.push(format!("{} {} {}", destination, source, length));
+            destination += length;
+        }
+        random.shuffle(&mut maps);
+        write!(
+            output,
+            "\n{}-to-{} map:\n{}\n",
+            categories[0],
+            categories[1],
+            maps.join("\n")
+        )
+        .unwrap();
+    }
+    output
+}
+
+// The best distance when the race lasts time milliseconds.
+fn best_distance(time: u128) -> u128 {
+    (time / 2) * (time - time / 2)
+}
+
+fn concatenated(numbers: &[u64]) -> u128 {
+    join(numbers, "").parse().unwrap_or(u128::MAX)
+}
+
+pub fn day_06(random: &mut Random, size: usize) -> String {
+    let nb_races = size.clamp(1, 8);
+    races(random, nb_races, (8 / nb_races).max(1) as u32)
+}
+
+// Every record can be beaten, including the one of the single race of part 2.
+// The times have width digits.
+pub fn races(random: &mut Random, nb_races: usize, width: u32) -> String {
+    loop {
+        let times = (0..nb_races)
+            .map(|_| random.between(10_u64.pow(width - 1).max(2), 10_u64.pow(width) - 1))
+            .collect::<Vec<_>>();
+        let distances = times
+            .iter()
+            .map(|&time| {
+                let best = best_distance(time as u128) as u64;
+                random.between(best / 2, best - 1)
+            })
+            .collect::<Vec<_>>();
+        if concatenated(&distances) < best_distance(concatenated(&times)) {
+            return format!(
+                "Time:      {}\nDistance:  {}\n",
+                join(
+                    &times
+                        .iter()
+                        .map(|time| format!("{:>4}", time))
+                        .collect::<Vec<_>>(),
+                    " "
+                ),
+                join(
+                    &distances
+                        .iter()
+                        .map(|distance| format!("{:>4}", distance))
+                        .collect::<Vec<_>>(),
+                    " "
+                )
+            );
+        }
+    }
+}
+
+const CARD_LABELS: &[u8] = b"23456789TJQKA";
+
+// Distinct hands, of all the types.
+pub fn day_07(random: &mut Random, size: usize) -> String {
+    let size = size.min(CARD_LABELS.len().pow(5));
+    let mut hands = HashSet::new();
+    let mut output = String::new();
+    while hands.len() < size {
+        let mut labels = CARD_LABELS.to_vec();
+        random.shuffle(&mut labels);
+        let nb_labels = random.between(1, 5) as usize;
+        let hand = (0..5)
+            .map(|_| *random.choose(&labels[..nb_labels]) as char)
+            .collect::<String>();
+        if hands.insert(hand.clone()) {
+            writeln!(output, "{} {}", hand, random.between(1, 1000)).unwrap();
+        }
+    }
+    output
+}
+
+fn node_name(random: &mut Random, used: &mut HashSet<String>, last: Option<char>) -> String {
+    loop {
+        let mut name = (0..2)
+            .map(|_| (b'A' + random.between(0, 25) as u8) as char)
+            .collect::<String>();
+        // the other nodes must not end like the starting and ending nodes
+        name.push(last.unwrap_or((b'B' + random.between(0, 23) as u8) as char));
+        if used.insert(name.clone()) {
+            return name;
+        }
+    }
+}
+
+const GHOST_PRIMES: [u64; 16] = [3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59];
+
+// Each ghost, AAA included, follows a path of a prime number times the
+// number of instructions, and its ending node sends it back to the start
+// of the path. The other side of each node is a random decoy.
+pub fn day_08(random: &mut Random, size: usize) -> String {
+    let mut primes = GHOST_PRIMES;
+    random.shuffle(&mut primes);
+    let primes = &primes[..6];
+    // there are only so many names of three letters
+    let nb_instructions = size.clamp(1, 9000 / primes.iter().sum::<u64>() as usize);
+    let instructions = (0..nb_instructions)
+        .map(|_| random.chance(0.5))
+        .collect::<Vec<_>>();
+
+    let mut used = HashSet::new();
+    // (name, next node, going left)
+    let mut paths = Vec::new();
+    for (ghost, prime) in primes.iter().enumerate() {
+        let path_length = *prime as usize * nb_instructions;
+        let (start, end) = match ghost {
+            0 => ("AAA".to_string(), "ZZZ".to_string()),
+            _ => (
+                node_name(random, &mut used, Some('A')),
+                node_name(random, &mut used, Some('Z')),
+            ),
+        };
+        used.insert(start.clone());
+        used.insert(end.clone());
+        let mut path = vec![start];
+        path.extend((1..path_length).map(|_| node_name(random, &mut used, None)));
+        path.push(end);
+        for position in 0..=path_length {
+            let next = path[position % path_length + 1].clone();
+            paths.push((
+                path[position].clone(),
+                next,
+                instructions[position % nb_instructions],
+            ));
+        }
+    }
+
+    let names = paths
+        .iter()
+        .map(|(name, _, _)| name.clone())
+        .collect::<Vec<_>>();
+    let mut nodes = paths
+        .into_iter()
+        .map(|(name, next, going_left)| {
+            let decoy = random.choose(&names).clone();
+            match going_left {
+                true => format!("{} = ({}, {})", name, next, decoy),
+                false => format!("{} = ({}, {})", name, decoy, next),
+            }
+        })
+        .collect::<Vec<_>>();
+    random.shuffle(&mut nodes);
+
+    let instructions = instructions
+        .iter()
+        .map(|&going_left| match going_left {
+            true => 'L',
+            false => 'R',
+        })
+        .collect::<String>();
+    format!("{}\n\n{}\n", instructions, nodes.join("\n"))
+}
+
+fn binomial(n: i64, k: i64) -> i64 {
+    (0..k).fold(1, |result, i| result * (n - i) / (i + 1))
+}
+
+// Polynomials, so the differences end with zeros.
+pub fn day_09(random: &mut Random, size: usize) -> String {
+    let mut output = String::new();
+    for _ in 0..size {
+        let degree = random.between(0, 8) as i64;
+        let coefficients = (0..=degree)
+            .map(|_| random.between(0, 20) as i64 - 10)
+            .collect::<Vec<_>>();
+        let values = (0..21)
+            .map(|x| {
+                coefficients
+                    .iter()
+                    .enumerate()
+                    .map(|(k, coefficient)| coefficient * binomial(x, k as i64))
+                    .sum::<i64>()
+            })
+            .collect::<Vec<_>>();
+        writeln!(output, "{}", join(&values, " ")).unwrap();
+    }
+    output
+}
+
+fn pipe(from: (usize, usize), a: (usize, usize), b: (usize, usize)) -> u8 {
+    let side = |to: (usize, usize)| match (to.0 as isize - from.0 as isize, to.1 > from.1) {
+        (-1, _) => 'N',
+        (1, _) => 'S',
+        (_, true) => 'E',
+        (_, false) => 'W',
+    };
+    match (side(a), side(b)) {
+        ('N', 'S') | ('S', 'N') => b'|',
+        ('E', 'W') | ('W', 'E') => b'-',
+        ('N', 'E') | ('E', 'N') => b'L',
+        ('N', 'W') | ('W', 'N') => b'J',
+        ('S', 'W') | ('W', 'S') => b'7',
+        _ => b'F',
+    }
+}
+
+// A single loop, in the middle of random pipes.
+pub fn day_10(random: &mut Random, size: usize) -> String {
+    const JUNK: &[u8] = b"|-LJ7F...";
+    let lattice_size = (size / 3).max(2);
+    let outline = tree_outline(random, lattice_size, lattice_size);
+    let nb_points = outline.len();
+    let on_loop = outline.iter().collect::<HashSet<_>>();
+
+    // one tile of junk around the loop
+    let grid_size = 3 * lattice_size + 2;
+    let mut grid = (0..grid_size)
+        .map(|_| (0..grid_size).map(|_| *random.choose(JUNK)).collect())
+        .collect::<Vec<Vec<u8>>>();
+    for (index, &point) in outline.iter().enumerate() {
+        let previous = outline[(index + nb_points - 1) % nb_points];
+        let next = outline[(index + 1) % nb_points];
+        grid[point.0 + 1][point.1 + 1] = pipe(point, previous, next);
+    }
+
+    // the junk next to the start must not look connected to it
+    let (row, col) = *random.choose(&outline);
+    grid[row + 1][col + 1] = b'S';
+    for (d_row, d_col) in [(0, 1), (2, 1), (1, 0), (1, 2)] {
+        let (r, c) = (row + d_row, col + d_col);
+        let is_on_loop = r >= 1 && c >= 1 && on_loop.contains(&(r - 1, c - 1));
+        if !is_on_loop {
+            grid[r][c] = b'.';
+        }
+    }
+    grid_to_string(&grid)
+}
+
+// Some rows and columns are kept empty, to be expanded.
+pub fn day_11(random: &mut Random, size: usize) -> String {
+    let empty_rows = (0..size).map(|_| random.chance(0.1)).collect::<Vec<_>>();
+    let empty_cols = (0..size).map(|_| random.chance(0.1)).collect::<Vec<_>>();
+    let grid = (0..size)
+        .map(|row| {
+            (0..size)
+                .map(
+                    |col| match !empty_rows[row] && !empty_cols[col] && random.chance(0.03) {
+                        true => b'#',
+                        false => b'.',
+                    },
+                )
+                .collect()
+        })
+        .collect::<Vec<Vec<u8>>>();
+    grid_to_string(&grid)
+}
+
+pub fn day_12(random: &mut Random, size: usize) -> String {
+    springs(random, size, 20, 20)
+}
+
+// The groups come from a real row of springs, so there is
+// always at least one arrangement.
+pub fn springs(
+    random: &mut Random,
+    nb_rows: usize,
+    max_length: usize,
+    max_unknowns: usize,
+) -> String {
+    let mut output = String::new();
+    for _ in 0..nb_rows {
+        let length = random.between(3, max_length as u64) as usize;
+        let mut damaged = (0..length).map(|_| random.chance(0.5)).collect::<Vec<_>>();
+        let forced = random.index(length);
+        damaged[forced] = true;
+
+        let groups = damaged
+            .split(|&is_damaged| !is_damaged)
+            .filter(|group| !group.is_empty())
+            .map(|group| group.len())
+            .collect::<Vec<_>>();
+        let mut nb_unknowns = 0;
+        let springs = damaged
+            .iter()
+            .map(|&is_damaged| match (random.chance(0.5), is_damaged) {
+                (true, _) if nb_unknowns < max_unknowns => {
+                    nb_unknowns += 1;
+                    '?'
+                }
+                (_, true) => '#',
+                (_, false) => '.',
+            })
+            .collect::<String>();
+        writeln!(output, "{} {}", springs, join(&groups, ",")).unwrap();
+    }
+    output
+}
+
+// Number of differences when folding the pattern along the line above row.
+fn reflection_differences(pattern: &[Vec<bool>], row: usize) -> usize {
+    (0..row.min(pattern.len() - row))
+        .map(|k| {
+            pattern[row - 1 - k]
+                .iter()
+                .zip(pattern[row + k].iter())
+                .filter(|(a, b)| a != b)
+                .count()
+        })
+        .sum()
+}
+
+fn transpose(pattern: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    (0..pattern[0].len())
+        .map(|col| pattern.iter().map(|row| row[col]).collect())
+        .collect()
+}
+
+// (is a row, position) of the lines with exactly that many differences.
+fn reflection_lines(pattern: &[Vec<bool>], differences: usize) -> Vec<(bool, usize)> {
+    let columns = transpose(pattern);
+    let rows = (1..pattern.len())
+        .filter(|&row| reflection_differences(pattern, row) == differences)
+        .map(|row| (true, row));
+    let cols = (1..columns.len())
+        .filter(|&col| reflection_differences(&columns, col) == differences)
+        .map(|col| (false, col));
+    rows.chain(cols).collect()
+}
+
+// A perfect reflection across a row, and a reflection across a column
+// with one smudge, outside of the rows mirrored by the first one.
+fn mirror_pattern(random: &mut Random) -> Vec<Vec<bool>> {
+    loop {
+        let height = random.between(5, 17) as usize;
+        let width = random.between(5, 17) as usize;
+        let mut pattern = (0..height)
+            .map(|_| (0..width).map(|_| random.chance(0.5)).collect())
+            .collect::<Vec<Vec<bool>>>();
+
+        let col = random.between(1, width as u64 - 1) as usize;
+        let mirrored_cols = col.min(width - col);
+        for row in pattern.iter_mut() {
+            for k in 0..mirrored_cols {
+                row[col + k] = row[col - 1 - k];
+            }
+        }
+
+        let row = random.between(1, (height as u64 - 1) / 2) as usize;
+        for k in 0..row {
+            pattern[row + k] = pattern[row - 1 - k].clone();
+        }
+
+        let smudge_row = random.between(2 * row as u64, height as u64 - 1) as usize;
+        let smudge_col = random.between(
+            (col - mirrored_cols) as u64,
+            (col + mirrored_cols - 1) as u64,
+        ) as usize;
+        pattern[smudge_row][smudge_col] = !pattern[smudge_row][smudge_col];
+
+        if reflection_lines(&pattern, 0) == vec![(true, row)]
+            && reflection_lines(&pattern, 1) == vec![(false, col)]
+        {
+            return match random.chance(0.5) {
+                true => pattern,
+                false => transpose(&pattern),
+            };
+        }
+    }
+}
+
+pub fn day_13(random: &mut Random, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            let pattern = mirror_pattern(random)
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|&rock| match rock {
+                            true => b'#',
+                            false => b'.',
+                        })
+                        .collect()
+                })
+                .collect::<Vec<Vec<u8>>>();
+            grid_to_string(&pattern)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn day_14(random: &mut Random, size: usize) -> String {
+    let grid = (0..size)
+        .map(|_| {
+            (0..size)
+                .map(|_| match random.between(0, 99) {
+                    0..=17 => b'O',
+                    18..=32 => b'#',
+                    _ => b'.',
+                })
+                .collect()
+        })
+        .collect::<Vec<Vec<u8>>>();
+    grid_to_string(&grid)
+}
+
+// Few labels, so the lenses are often replaced and removed.
+pub fn day_15(random: &mut Random, size: usize) -> String {
+    let labels = (0..(size / 8).max(1))
+        .map(|_| word(random, 2, 6))
+        .collect::<Vec<_>>();
+    let steps = (0..size)
+        .map(|_| {
+            let label = random.choose(&labels).clone();
+            match random.chance(0.6) {
+                true => format!("{}={}", label, random.between(1, 9)),
+                false => format!("{}-", label),
+            }
+        })
+        .collect::<Vec<_>>();
+    format!("{}\n", steps.join(","))
+}
+
+pub fn day_16(random: &mut Random, size: usize) -> String {
+    const MIRRORS: &[u8] = b"|-/\\";
+    let grid = (0..size)
+        .map(|_| {
+            (0..size)
+                .map(|_| match random.chance(0.1) {
+                    true => *random.choose(MIRRORS),
+                    false => b'.',
+                })
+                .collect()
+        })
+        .collect::<Vec<Vec<u8>>>();
+    grid_to_string(&grid)
+}
+
+// Big enough for the ultra crucibles to reach the end.
+pub fn day_17(random: &mut Random, size: usize) -> String {
+    let size = size.max(5);
+    let grid = (0..size)
+        .map(|_| {
+            (0..size)
+                .map(|_| b'0' + random.between(1, 9) as u8)
+                .collect()
+        })
+        .collect::<Vec<Vec<u8>>>();
+    grid_to_string(&grid)
+}
+
+// Positions of the lattice lines once stretched by random gaps.
+// The gaps are at least 2, so the trench never touches itself.
+fn stretch(random: &mut Random, nb_lines: usize, max_gap: u64) -> Vec<u64> {
+    let mut position = 0;
+    (0..nb_lines)
+        .map(|_| {
+            position += random.between(2, max_gap.max(2));
+            position
+        })
+        .collect()
+}
+
+const DIG_DIRECTIONS: [char; 4] = ['R', 'D', 'L', 'U'];
+
+pub fn day_18(random: &mut Random, size: usize) -> String {
+    let lattice_size = (size / 3).max(2);
+    // 5 hexadecimal digits for part 2
+    dig_plan(
+        random,
+        lattice_size,
+        10,
+        0xfffff / (3 * lattice_size) as u64,
+    )
+}
+
+// The same tree outline for both parts, stretched differently,
+// and rotated for the part 2 colours. Neither crosses itself.
+pub fn dig_plan(
+    random: &mut Random,
+    lattice_size: usize,
+    max_gap_1: u64,
+    max_gap_2: u64,
+) -> String {
+    let outline = tree_outline(random, lattice_size, lattice_size);
+    let nb_points = outline.len();
+    let direction = |from: (usize, usize), to: (usize, usize)| match (
+        (to.0 as isize - from.0 as isize).signum(),
+        (to.1 as isize - from.1 as isize).signum(),
+    ) {
+        (0, 1) => 0,
+        (1, 0) => 1,
+        (0, -1) => 2,
+        _ => 3,
+    };
+    let corners = (0..nb_points)
+        .filter(|&index| {
+            let previous = outline[(index + nb_points - 1) % nb_points];
+            let next = outline[(index + 1) % nb_points];
+            direction(previous, outline[index]) != direction(outline[index], next)
+        })
+        .map(|index| outline[index])
+        .collect::<Vec<_>>();
+
+    let nb_lines = 3 * lattice_size;
+    let (rows_1, cols_1) = (
+        stretch(random, nb_lines, max_gap_1),
+        stretch(random, nb_lines, max_gap_1),
+    );
+    let (rows_2, cols_2) = (
+        stretch(random, nb_lines, max_gap_2),
+        stretch(random, nb_lines, max_gap_2),
+    );
+    let rotation = random.between(0, 3) as usize;
+
+    let mut output = String::new();
+    for (index, &from) in corners.iter().enumerate() {
+        let to = corners[(index + 1) % corners.len()];
+        let length = |rows: &[u64], cols: &[u64]| {
+            rows[from.0].abs_diff(rows[to.0]) + cols[from.1].abs_diff(cols[to.1])
+        };
+        let direction = direction(from, to);
+        writeln!(
+            output,
+            "{} {} (#{:05x}{})",
+            DIG_DIRECTIONS[direction],
+            length(&rows_1, &cols_1),
+            length(&rows_2, &cols_2),
+            (direction + rotation) % 4
+        )
+        .unwrap();
+    }
+    output
+}
+
+const PART_CATEGORIES: [char; 4] = ['x', 'm', 'a', 's'];
+
+// A tree of workflows starting at in, so the parts always end up
+// accepted or rejected.
+pub fn day_19(random: &mut Random, size: usize) -> String {
+    let mut used = HashSet::from(["in".to_string()]);
+    let mut to_define = VecDeque::from(["in".to_string()]);
+    let mut workflows = Vec::new();
+
+    while let Some(name) = to_define.pop_front() {
+        let nb_rules = random.between(1, 3);
+        let mut rules = Vec::new();
+        for rule in 0..=nb_rules {
+            let destination = match used.len() < size && random.chance(0.6) {
+                true => loop {
+                    let name = word(random, 2, 3);
+                    if used.insert(name.clone()) {
+                        to_define.push_back(name.clone());
+                        break name;
+                    }
+                },
+                false => match random.chance(0.5) {
+                    true => "A".to_string(),
+                    false => "R".to_string(),
+                },
+            };
+            // the last rule has no condition
+            match rule == nb_rules {
+                true => rules.push(destination),
+                false => rules.push(format!(
+                    "{}{}{}:{}",
+                    random.choose(&PART_CATEGORIES),
+                    random.choose(&['<', '>']),
+                    random.between(1, 4000),
+                    destination
+                )),
+            }
+        }
+        workflows.push(format!("{}{{{}}}", name, rules.join(",")));
+    }
+    random.shuffle(&mut workflows);
+
+    let parts = (0..size)
+        .map(|_| {
+            let ratings = PART_CATEGORIES
+                .iter()
+                .map(|category| format!("{}={}", category, random.between(1, 4000)))
+                .collect::<Vec<_>>();
+            format!("{{{}}}", ratings.join(","))
+        })
+        .collect::<Vec<_>>();
+    format!("{}\n\n{}\n", workflows.join("\n"), parts.join("\n"))
+}
+
+type Generator = fn(&mut Random, usize) -> String;
+
+// The generator of the day, and the size of the real inputs.
+fn day_generator(day: u32) -> Option<(Generator, usize)> {
+    match day {
+        1 => Some((day_01, 1000)),
+        2 => Some((day_02, 100)),
+        3 => Some((day_03, 140)),
+        4 => Some((day_04, 200)),
+        5 => Some((day_05, 30)),
+        6 => Some((day_06, 4)),
+        7 => Some((day_07, 1000)),
+        8 => Some((day_08, 40)),
+        9 => Some((day_09, 200)),
+        10 => Some((day_10, 140)),
+        11 => Some((day_11, 140)),
+        12 => Some((day_12, 1000)),
+        13 => Some((day_13, 100)),
+        14 => Some((day_14, 100)),
+        15 => Some((day_15, 4000)),
+        16 => Some((day_16, 110)),
+        17 => Some((day_17, 141)),
+        18 => Some((day_18, 40)),
+        19 => Some((day_19, 500)),
+        _ => None,
+    }
+}
+
+pub fn generate(year: u32, day: u32, size: Option<usize>, seed: u64) -> Result<String, String> {
+    if year != 2023 {
+        return Err(format!("No generators for {}, only for 2023", year));
+    }
+    let (generator, default_size) =
+        day_generator(day).ok_or_else(|| format!("No generator for day {}", day))?;
+    let mut random = Random::new(seed);
+    Ok(generator(&mut random, size.unwrap_or(default_size).max(1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        day_01, day_02, day_03, day_04, day_05, day_06, day_07, day_08, day_09, day_10, day_11,
+        day_12, day_13, day_14, day_15, day_16, day_17, day_18, day_19,
+    };
+
+    #[test]
+    fn test_random() {
+        let mut random = Random::new(42);
+        let mut other = Random::new(42);
+        assert_eq!(random.next_u64(), other.next_u64());
+        for _ in 0..1000 {
+            let value = random.between(3, 7);
+            assert!((3..=7).contains(&value));
+        }
+        assert_eq!(random.between(5, 5), 5);
+
+        let mut items = (0..10).collect::<Vec<_>>();
+        random.shuffle(&mut items);
+        items.sort();
+        assert_eq!(items, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_generate() {
+        assert_eq!(
+            generate(2023, 10, Some(8), 1),
+            generate(2023, 10, Some(8), 1)
+        );
+        assert_ne!(
+            generate(2023, 10, Some(8), 1),
+            generate(2023, 10, Some(8), 2)
+        );
+        assert_eq!(generate(2023, 1, Some(3), 0).unwrap().lines().count(), 3);
+        assert!(generate(2015, 1, None, 0).is_err());
+        assert!(generate(2023, 25, None, 0).is_err());
+    }
+
+    #[test]
+    fn test_tree_outline() {
+        let mut random = Random::new(7);
+        let outline = tree_outline(&mut random, 4, 3);
+        let points = outline.iter().collect::<HashSet<_>>();
+        // a simple loop, of unit steps
+        assert_eq!(points.len(), outline.len());
+        for (index, a) in outline.iter().enumerate() {
+            let b = outline[(index + 1) % outline.len()];
+            assert_eq!(a.0.abs_diff(b.0) + a.1.abs_diff(b.1), 1);
+        }
+        // 12 blocks of 2 × 2 cells, joined by 11 pairs of cells sharing 4 sides
+        assert_eq!(outline.len(), 12 * 8 + 11 * 6 - 11 * 2 * 4);
+    }
+
+    #[test]
+    fn test_generated_inputs_are_solvable() {
+        type Part = fn(&str) -> Result<i64, String>;
+        let parts: [(u32, Part, Part); 19] = [
+            (1, day_01::day_1_part_1, day_01::day_1_part_2),
+            (2, day_02::day_2_part_1, day_02::day_2_part_2),
+            (3, day_03::day_3_part_1, day_03::day_3_part_2),
+            (4, day_04::day_4_part_1, day_04::day_4_part_2),
+            (5, day_05::day_5_part_1, day_05::day_5_part_2),
+            (6, day_06::day_6_part_1, day_06::day_6_part_2),
+            (7, day_07::day_7_part_1, day_07::day_7_part_2),
+            (8, day_08::day_8_part_1, day_08::day_8_part_2),
+            (9, day_09::day_9_part_1, day_09::day_9_part_2),
+            (10, day_10::day_10_part_1, day_10::day_10_part_2),
+            (11, day_11::day_11_part_1, day_11::day_11_part_2),
+            (12, day_12::day_12_part_1, day_12::day_12_part_2),
+            (13, day_13::day_13_part_1, day_13::day_13_part_2),
+            (14, day_14::day_14_part_1, day_14::day_14_part_2),
+            (15, day_15::day_15_part_1, day_15::day_15_part_2),
+            (16, day_16::day_16_part_1, day_16::day_16_part_2),
+            (17, day_17::day_17_part_1, day_17::day_17_part_2),
+            (18, day_18::day_18_part_1, day_18::day_18_part_2),
+            (19, day_19::day_19_part_1, day_19::day_19_part_2),
+        ];
+        for (day, part_1, part_2) in parts {
+            for seed in 0..3 {
+                let input = generate(2023, day, Some(12), seed).unwrap();
+                for (part, solution) in [(1, part_1), (2, part_2)] {
+                    if let Err(error) = solution(&input) {
+                        panic!("Day {} part {} seed {}: {}", day, part, seed, error);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_generated_answers() {
+        // the loop is the outline of a tree of 4 × 4 nodes, of length 6 × 16 + 2,
+        // and encloses the middle of each node and two corners per edge
+        let input = generate(2023, 10, Some(12), 3).unwrap();
+        assert_eq!(day_10::day_10_part_1(&input), Ok((6 * 16 + 2) / 2));
+        assert_eq!(day_10::day_10_part_2(&input), Ok(16 + 2 * 15));
+
+        // AAA needs a prime times the number of instructions
+        let input = generate(2023, 8, Some(10), 3).unwrap();
+        let steps = day_08::day_8_part_1(&input).unwrap() as u64;
+        assert_eq!(steps % 10, 0);
+        assert!(GHOST_PRIMES.contains(&(steps / 10)));
+
+        // every race can be won
+        let input = generate(2023, 6, Some(4), 3).unwrap();
+        assert!(day_06::day_6_part_1(&input).unwrap() > 0);
+        assert!(day_06::day_6_part_2(&input).unwrap() > 0);
+    }
+}
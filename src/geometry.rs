@@ -0,0 +1,342 @@
+/*
+    Computational geometry on polygons with integer vertices.
+
+    A polygon is a slice of vertices in order, and the last vertex is
+    implicitly connected to the first one, so it shouldn't be repeated.
+
+    Everything stays in integers: the shoelace formula gives twice the area,
+    which is always an integer for lattice polygons, and Pick's theorem
+    (A = I + B/2 - 1) turns it into a number of interior lattice points.
+    Days 10 and 18 are both about counting those points.
+
+    The products are done in i128 and checked, the vertices can come
+    straight from an input. None means the numbers don't fit.
+*/
+
+use crate::number_theory::gcd;
+
+pub type Point = (i64, i64);
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Orientation {
+    // With the y axis going up. With the y axis going down, like rows
+    // in a grid, the two are swapped.
+    CounterClockwise,
+    Clockwise,
+    Degenerate,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PointLocation {
+    Inside,
+    Outside,
+    Boundary,
+}
+
+// Why Pick's theorem couldn't count the interior points.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PickError {
+    // the numbers don't fit
+    Overflow,
+    // more boundary points than the area allows, it folds onto itself
+    NotSimple,
+    // no area at all, like no vertices or a single one
+    Empty,
+}
+
+fn edges(vertices: &[Point]) -> impl Iterator<Item = (Point, Point)> + '_ {
+    let n = vertices.len();
+    (0..n).map(move |i| (vertices[i], vertices[(i + 1) % n]))
+}
+
+// Cross product of (b - a) and (c - a), positive if a, b, c turn left.
+// The differences always fit in an i128, not always their products.
+fn cross(a: Point, b: Point, c: Point) -> Option<i128> {
+    let difference = |p: i64, q: i64| p as i128 - q as i128;
+    difference(b.0, a.0)
+        .checked_mul(difference(c.1, a.1))?
+        .checked_sub(difference(b.1, a.1).checked_mul(difference(c.0, a.0))?)
+}
+
+// Only valid if a, b, c are collinear.
+fn is_within_bounding_box(a: Point, b: Point, c: Point) -> bool {
+    a.0.min(b.0) <= c.0 && c.0 <= a.0.max(b.0) && a.1.min(b.1) <= c.1 && c.1 <= a.1.max(b.1)
+}
+
+fn is_on_segment(a: Point, b: Point, point: Point) -> Option<bool> {
+    Some(cross(a, b, point)? == 0 && is_within_bounding_box(a, b, point))
+}
+
+fn segments_intersect(a: Point, b: Point, c: Point, d: Point) -> Option<bool> {
+    let d1 = cross(c, d, a)?.signum();
+    let d2 = cross(c, d, b)?.signum();
+    let d3 = cross(a, b, c)?.signum();
+    let d4 = cross(a, b, d)?.signum();
+
+    if d1 * d2 < 0 && d3 * d4 < 0 {
+        return Some(true);
+    }
+
+    Some(
+        (d1 == 0 && is_within_bounding_box(c, d, a))
+            || (d2 == 0 && is_within_bounding_box(c, d, b))
+            || (d3 == 0 && is_within_bounding_box(a, b, c))
+            || (d4 == 0 && is_within_bounding_box(a, b, d)),
+    )
+}
+
+// Shoelace formula, without the division by 2 to stay in integers.
+pub fn double_signed_area(vertices: &[Point]) -> Option<i128> {
+    edges(vertices).try_fold(0_i128, |sum, ((x1, y1), (x2, y2))| {
+        sum.checked_add((x1 as i128 * y2 as i128).checked_sub(y1 as i128 * x2 as i128)?)
+    })
+}
+
+pub fn area(vertices: &[Point]) -> Option<f64> {
+    Some(double_signed_area(vertices)?.unsigned_abs() as f64 / 2.0)
+}
+
+pub fn orientation(vertices: &[Point]) -> Option<Orientation> {
+    Some(match double_signed_area(vertices)?.signum() {
+        1 => Orientation::CounterClockwise,
+        -1 => Orientation::Clockwise,
+        _ => Orientation::Degenerate,
+    })
+}
+
+// In floats, it can lose precision but not wrap.
+pub fn perimeter(vertices: &[Point]) -> f64 {
+    edges(vertices)
+        .map(|(a, b)| {
+            let dx = b.0 as i128 - a.0 as i128;
+            let dy = b.1 as i128 - a.1 as i128;
+            (dx as f64).hypot(dy as f64)
+        })
+        .sum()
+}
+
+// Number of lattice points on the boundary. For axis aligned polygons,
+// like the ones of days 10 and 18, it's the same as the perimeter.
+pub fn boundary_points(vertices: &[Point]) -> Option<u64> {
+    edges(vertices).try_fold(0_u64, |sum, (a, b)| {
+        sum.checked_add(gcd(a.0.abs_diff(b.0), a.1.abs_diff(b.1)))
+    })
+}
+
+// Pick's theorem: 2A = 2I + B - 2
+// An error instead of overflowing, for the polygons that come straight
+// from an input: too big, or not even a simple polygon.
+pub fn interior_points(vertices: &[Point]) -> Result<u64, PickError> {
+    let double_area = double_signed_area(vertices)
+        .ok_or(PickError::Overflow)?
+        .unsigned_abs();
+    let boundary = boundary_points(vertices).ok_or(PickError::Overflow)? as u128;
+    let double_interior = double_area
+        .checked_add(2)
+        .ok_or(PickError::Overflow)?
+        .checked_sub(boundary)
+        .ok_or(PickError::NotSimple)?;
+    if double_area == 0 {
+        return Err(PickError::Empty);
+    }
+    u64::try_from(double_interior / 2).map_err(|_| PickError::Overflow)
+}
+
+// Ray casting towards the positive x, with exact integer comparisons.
+pub fn locate_point(vertices: &[Point], point: Point) -> Option<PointLocation> {
+    let mut inside = false;
+    for (a, b) in edges(vertices) {
+        if is_on_segment(a, b, point)? {
+            return Some(PointLocation::Boundary);
+        }
+        if (a.1 > point.1) != (b.1 > point.1) {
+            // the crossing is on the right of the point if the cross
+            // product has the same sign as the edge's y direction
+            let side = cross(a, b, point)?.signum();
+            let direction = match b.1 > a.1 {
+                true => 1,
+                false => -1,
+            };
+            if side == direction {
+                inside = !inside;
+            }
+        }
+    }
+    Some(match inside {
+        true => PointLocation::Inside,
+        false => PointLocation::Outside,
+    })
+}
+
+// Checks every pair of edges, so it's quadratic. Consecutive edges
+// are allowed to share their common vertex, but not to fold back.
+pub fn is_self_intersecting(vertices: &[Point]) -> Option<bool> {
+    let edges: Vec<(Point, Point)> = edges(vertices).collect();
+    let n = edges.len();
+    for i in 0..n {
+        for j in i + 1..n {
+            let (a, b) = edges[i];
+            let (c, d) = edges[j];
+            if j == i + 1 {
+                // b == c, they only overlap if d goes back on a to b
+                if (is_on_segment(a, b, d)? && d != b) || (is_on_segment(c, d, a)? && a != c) {
+                    return Some(true);
+                }
+            } else if i == 0 && j == n - 1 {
+                // d == a, same thing the other way around
+                if (is_on_segment(c, d, b)? && b != d) || (is_on_segment(a, b, c)? && c != a) {
+                    return Some(true);
+                }
+            } else if segments_intersect(a, b, c, d)? {
+                return Some(true);
+            }
+        }
+    }
+    Some(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE: [Point; 4] = [(0, 0), (4, 0), (4, 4), (0, 4)];
+    const TRIANGLE: [Point; 3] = [(0, 0), (0, 3), (6, 0)];
+
+    #[test]
+    fn test_area_and_orientation() {
+        assert_eq!(double_signed_area(&SQUARE), Some(32));
+        assert_eq!(area(&SQUARE), Some(16.0));
+        assert_eq!(orientation(&SQUARE), Some(Orientation::CounterClockwise));
+
+        assert_eq!(double_signed_area(&TRIANGLE), Some(-18));
+        assert_eq!(area(&TRIANGLE), Some(9.0));
+        assert_eq!(orientation(&TRIANGLE), Some(Orientation::Clockwise));
+
+        assert_eq!(
+            orientation(&[(0, 0), (1, 1), (2, 2)]),
+            Some(Orientation::Degenerate)
+        );
+
+        // each product fits in an i128, not their sum
+        let huge = [
+            (i64::MIN, i64::MIN),
+            (i64::MAX, i64::MIN),
+            (i64::MAX, i64::MAX),
+            (i64::MIN, i64::MAX),
+        ];
+        assert_eq!(double_signed_area(&huge), None);
+        assert_eq!(orientation(&huge), None);
+    }
+
+    #[test]
+    fn test_perimeter_and_boundary() {
+        assert_eq!(perimeter(&SQUARE), 16.0);
+        assert_eq!(boundary_points(&SQUARE), Some(16));
+
+        // 3 + 6 + the hypotenuse that goes through (2, 2) and (4, 1)
+        assert_eq!(boundary_points(&TRIANGLE), Some(12));
+        assert!((perimeter(&TRIANGLE) - (9.0 + 45.0_f64.sqrt())).abs() < 1e-9);
+
+        let long = [(i64::MIN, 0), (i64::MAX, 0), (i64::MAX, 1)];
+        assert_eq!(boundary_points(&long), None);
+        assert!(perimeter(&long) > 3.6e19);
+    }
+
+    #[test]
+    fn test_pick() {
+        assert_eq!(interior_points(&SQUARE), Ok(9));
+        // (1, 1), (2, 1), (3, 1) and (1, 2)
+        assert_eq!(interior_points(&TRIANGLE), Ok(4));
+
+        // there and back again, more boundary than area
+        assert_eq!(
+            interior_points(&[(0, 0), (5, 0)]),
+            Err(PickError::NotSimple)
+        );
+        assert_eq!(
+            interior_points(&[(0, 0), (i64::MAX, 0), (i64::MAX, i64::MAX)]),
+            Err(PickError::Overflow)
+        );
+        // Pick's theorem would find 1 point inside nothing
+        assert_eq!(interior_points(&[]), Err(PickError::Empty));
+        assert_eq!(interior_points(&[(3, 4)]), Err(PickError::Empty));
+    }
+
+    #[test]
+    fn test_locate_point() {
+        assert_eq!(locate_point(&SQUARE, (2, 2)), Some(PointLocation::Inside));
+        assert_eq!(locate_point(&SQUARE, (4, 2)), Some(PointLocation::Boundary));
+        assert_eq!(locate_point(&SQUARE, (0, 0)), Some(PointLocation::Boundary));
+        assert_eq!(locate_point(&SQUARE, (5, 2)), Some(PointLocation::Outside));
+        assert_eq!(locate_point(&SQUARE, (-1, 4)), Some(PointLocation::Outside));
+
+        assert_eq!(locate_point(&TRIANGLE, (1, 1)), Some(PointLocation::Inside));
+        assert_eq!(
+            locate_point(&TRIANGLE, (2, 2)),
+            Some(PointLocation::Boundary)
+        );
+        assert_eq!(
+            locate_point(&TRIANGLE, (3, 2)),
+            Some(PointLocation::Outside)
+        );
+
+        // a U shape, where the ray crosses several edges and vertices
+        let u_shape = [
+            (0, 0),
+            (3, 0),
+            (3, 3),
+            (2, 3),
+            (2, 1),
+            (1, 1),
+            (1, 3),
+            (0, 3),
+        ];
+        assert_eq!(
+            locate_point(&u_shape, (1, 2)),
+            Some(PointLocation::Boundary)
+        );
+        assert_eq!(
+            locate_point(&u_shape, (-1, 1)),
+            Some(PointLocation::Outside)
+        );
+        assert_eq!(
+            locate_point(&u_shape, (-1, 2)),
+            Some(PointLocation::Outside)
+        );
+        assert_eq!(interior_points(&u_shape), Ok(0));
+
+        // the cross products don't fit in an i64, but fit in an i128
+        let big = [(-1 << 40, -1 << 40), (1 << 40, -1 << 40), (0, 1 << 40)];
+        assert_eq!(locate_point(&big, (0, 0)), Some(PointLocation::Inside));
+        assert_eq!(is_self_intersecting(&big), Some(false));
+        // and then they don't
+        let huge = [(i64::MIN, i64::MIN), (i64::MAX, i64::MIN), (0, i64::MAX)];
+        assert_eq!(is_self_intersecting(&huge), None);
+    }
+
+    #[test]
+    fn test_is_self_intersecting() {
+        assert_eq!(is_self_intersecting(&SQUARE), Some(false));
+        assert_eq!(is_self_intersecting(&TRIANGLE), Some(false));
+
+        let bowtie = [(0, 0), (2, 2), (2, 0), (0, 2)];
+        assert_eq!(is_self_intersecting(&bowtie), Some(true));
+
+        // goes back on itself
+        let spike = [(0, 0), (4, 0), (2, 0), (2, 2)];
+        assert_eq!(is_self_intersecting(&spike), Some(true));
+
+        // two loops touching at one vertex
+        let figure_eight = [
+            (0, 0),
+            (1, 0),
+            (1, 1),
+            (2, 1),
+            (2, 2),
+            (1, 2),
+            (1, 1),
+            (0, 1),
+        ];
+        assert_eq!(is_self_intersecting(&figure_eight), Some(true));
+    }
+}
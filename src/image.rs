@@ -0,0 +1,322 @@
+/*
+    Image export of the grid states, for the grids that don't fit in a terminal.
+
+    Anything implementing Render can be turned into an Image, one square of
+    pixels per cell. The colour of a cell is, in order:
+        - the colour of the overlay highlight, if any
+        - the colour of the glyph, like the trench colours of day 18
+        - the colour of the ASCII symbol in the palette
+        - the default colour of the palette
+
+    The palette can be written on the command line as "symbol=rrggbb" pairs
+    separated by commas, like ".=000000,#=ffffff,*=a0a0a0" where * is the default.
+
+    PPM is trivial to write. PNG is written without any compression crate,
+    using stored deflate blocks: the files are bigger but any viewer opens them.
+*/
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::render::{Glyph, Highlight, Overlay, Render, Rgb};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Palette {
+    colours: HashMap<char, Rgb>,
+    default: Rgb,
+}
+
+impl Default for Palette {
+    // Dark ground, light everything else.
+    fn default() -> Palette {
+        Palette::new(Rgb(200, 200, 200)).with('.', Rgb(20, 20, 30))
+    }
+}
+
+impl Palette {
+    pub fn new(default: Rgb) -> Palette {
+        Palette {
+            colours: HashMap::new(),
+            default,
+        }
+    }
+
+    pub fn with(mut self, symbol: char, colour: Rgb) -> Palette {
+        self.colours.insert(symbol, colour);
+        self
+    }
+
+    pub fn colour(&self, glyph: &Glyph, highlight: Option<&Highlight>) -> Rgb {
+        highlight
+            .map(|highlight| highlight.colour)
+            .or(glyph.colour)
+            .or_else(|| self.colours.get(&glyph.ascii).copied())
+            .unwrap_or(self.default)
+    }
+}
+
+fn parse_hex_colour(hex: &str) -> Option<Rgb> {
+    if hex.len() != 6 {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok().map(Rgb::from_u32)
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(data: &str) -> Result<Palette, String> {
+        let mut palette = Palette::default();
+        for entry in data.split(',').filter(|entry| !entry.is_empty()) {
+            let mut chars = entry.chars();
+            let (symbol, equal) = (chars.next(), chars.next());
+            let colour = match (symbol, equal) {
+                (Some(_), Some('=')) => parse_hex_colour(chars.as_str()),
+                _ => None,
+            };
+            match (symbol, colour) {
+                (Some('*'), Some(colour)) => palette.default = colour,
+                (Some(symbol), Some(colour)) => palette = palette.with(symbol, colour),
+                _ => {
+                    return Err(format!(
+                        "Invalid palette entry: {}, expected symbol=rrggbb",
+                        entry
+                    ))
+                }
+            }
+        }
+        Ok(palette)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    // row by row
+    pub pixels: Vec<Rgb>,
+}
+
+impl Image {
+    // scale is the size in pixels of the square of each cell.
+    pub fn from_render<R: Render + ?Sized>(
+        state: &R,
+        overlay: &Overlay,
+        palette: &Palette,
+        scale: usize,
+    ) -> Image {
+        let scale = scale.max(1);
+        let (nb_rows, nb_cols) = state.dim();
+        let width = nb_cols * scale;
+        let height = nb_rows * scale;
+        let mut pixels = Vec::with_capacity(width * height);
+
+        for row in 0..nb_rows {
+            let colours = (0..nb_cols)
+                .map(|col| palette.colour(&state.glyph(row, col), overlay.get((row, col))))
+                .collect::<Vec<_>>();
+            for _ in 0..scale {
+                for colour in colours.iter() {
+                    pixels.extend(std::iter::repeat_n(*colour, scale));
+                }
+            }
+        }
+
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn rgb_bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.pixels.iter().flat_map(|Rgb(r, g, b)| [*r, *g, *b])
+    }
+
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut output = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        output.extend(self.rgb_bytes());
+        output
+    }
+
+    pub fn to_png(&self) -> Vec<u8> {
+        // each row starts with the filter type, 0 for none
+        let mut raw = Vec::with_capacity(self.height * (self.width * 3 + 1));
+        for row in self.pixels.chunks(self.width.max(1)).take(self.height) {
+            raw.push(0);
+            raw.extend(row.iter().flat_map(|Rgb(r, g, b)| [*r, *g, *b]));
+        }
+
+        let mut header = Vec::with_capacity(13);
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        // 8 bits per channel, RGB, default compression, filter and no interlace
+        header.extend([8, 2, 0, 0, 0]);
+
+        let mut output = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+        write_png_chunk(&mut output, b"IHDR", &header);
+        write_png_chunk(&mut output, b"IDAT", &zlib_stored(&raw));
+        write_png_chunk(&mut output, b"IEND", &[]);
+        output
+    }
+
+    // The format is chosen from the extension, PNG by default.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let is_ppm = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("ppm"));
+        match is_ppm {
+            true => std::fs::write(path, self.to_ppm()),
+            false => std::fs::write(path, self.to_png()),
+        }
+    }
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffff_u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xedb8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1_u32, 0_u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+// A zlib stream made of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut output = vec![0x78, 0x01];
+    let mut blocks = data.chunks(65535).peekable();
+    if blocks.peek().is_none() {
+        // a single empty final block
+        output.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let is_final = blocks.peek().is_none();
+        let length = block.len() as u16;
+        output.push(is_final as u8);
+        output.extend(length.to_le_bytes());
+        output.extend((!length).to_le_bytes());
+        output.extend(block);
+    }
+    output.extend(adler32(data).to_be_bytes());
+    output
+}
+
+fn write_png_chunk(output: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    output.extend((data.len() as u32).to_be_bytes());
+    let start = output.len();
+    output.extend(kind);
+    output.extend(data);
+    let crc = crc32(&output[start..]);
+    output.extend(crc.to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ndarray::array;
+
+    #[test]
+    fn test_palette() {
+        let palette = Palette::new(Rgb(1, 1, 1)).with('#', Rgb(2, 2, 2));
+        assert_eq!(palette.colour(&Glyph::new('#'), None), Rgb(2, 2, 2));
+        assert_eq!(palette.colour(&Glyph::new('?'), None), Rgb(1, 1, 1));
+        let coloured = Glyph::new('#').with_colour(Rgb(3, 3, 3));
+        assert_eq!(palette.colour(&coloured, None), Rgb(3, 3, 3));
+        let highlight = Highlight {
+            symbol: None,
+            colour: Rgb(4, 4, 4),
+        };
+        assert_eq!(palette.colour(&coloured, Some(&highlight)), Rgb(4, 4, 4));
+    }
+
+    #[test]
+    fn test_palette_from_str() {
+        let palette: Palette = "#=ff0000,*=00ff00".parse().unwrap();
+        assert_eq!(palette.colour(&Glyph::new('#'), None), Rgb(255, 0, 0));
+        assert_eq!(palette.colour(&Glyph::new('O'), None), Rgb(0, 255, 0));
+        // the default palette is kept for the other symbols
+        assert_eq!(palette.colour(&Glyph::new('.'), None), Rgb(20, 20, 30));
+
+        assert!("#=ff00".parse::<Palette>().is_err());
+        assert!("#ff0000".parse::<Palette>().is_err());
+    }
+
+    #[test]
+    fn test_image_from_render() {
+        let grid = array![[true, false]];
+        let palette = Palette::new(Rgb(0, 0, 0)).with('#', Rgb(255, 255, 255));
+        let mut overlay = Overlay::new();
+        overlay.highlight((0, 1), Rgb(255, 0, 0));
+
+        let image = Image::from_render(&grid, &overlay, &palette, 2);
+        assert_eq!((image.width, image.height), (4, 2));
+        let white = Rgb(255, 255, 255);
+        let red = Rgb(255, 0, 0);
+        assert_eq!(
+            image.pixels,
+            vec![white, white, red, red, white, white, red, red]
+        );
+    }
+
+    #[test]
+    fn test_ppm() {
+        let grid = array![[true], [false]];
+        let palette = Palette::new(Rgb(0, 0, 0)).with('#', Rgb(1, 2, 3));
+        let image = Image::from_render(&grid, &Overlay::new(), &palette, 1);
+        assert_eq!(image.to_ppm(), b"P6\n1 2\n255\n\x01\x02\x03\x00\x00\x00");
+    }
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn test_png() {
+        let grid = array![[true, false], [false, true]];
+        let image = Image::from_render(&grid, &Overlay::new(), &Palette::default(), 1);
+        let png = image.to_png();
+
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..24], [0, 0, 0, 2, 0, 0, 0, 2]);
+        assert_eq!(&png[png.len() - 12..], b"\x00\x00\x00\x00IEND\xaeB`\x82");
+
+        // 2 rows of 1 filter byte and 2 pixels, in one stored block
+        let idat_length = u32::from_be_bytes(png[33..37].try_into().unwrap());
+        assert_eq!(&png[37..41], b"IDAT");
+        assert_eq!(idat_length, 2 + 5 + 2 * 7 + 4);
+    }
+
+    #[test]
+    fn test_zlib_stored_blocks() {
+        let data = vec![7_u8; 70000];
+        let stream = zlib_stored(&data);
+        // header, 2 blocks of 5 bytes of header, data and checksum
+        assert_eq!(stream.len(), 2 + 2 * 5 + 70000 + 4);
+        assert_eq!(stream[2], 0);
+        assert_eq!(stream[2 + 5 + 65535], 1);
+        assert_eq!(
+            zlib_stored(&[]),
+            vec![0x78, 0x01, 1, 0, 0, 0xff, 0xff, 0, 0, 0, 1]
+        );
+    }
+}
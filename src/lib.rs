@@ -0,0 +1,101 @@
+/*
+    The days and the tools around them, as a library so the fuzz targets
+    in fuzz/ can call the solutions. The command line is in main.rs.
+*/
+
+pub mod animation;
+pub mod box_set;
+pub mod day_01;
+pub mod day_02;
+pub mod day_03;
+pub mod day_04;
+pub mod day_05;
+pub mod day_06;
+pub mod day_07;
+pub mod day_08;
+pub mod day_09;
+pub mod day_10;
+pub mod day_11;
+pub mod day_12;
+pub mod day_13;
+pub mod day_14;
+pub mod day_15;
+pub mod day_16;
+pub mod day_17;
+pub mod day_18;
+pub mod day_19;
+pub mod differential;
+pub mod dot;
+pub mod generate;
+pub mod geometry;
+pub mod image;
+pub mod minimize;
+pub mod number_theory;
+pub mod parsing;
+pub mod reference;
+pub mod render;
+pub mod trace;
+
+pub fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown error".to_string()
+    }
+}
+
+thread_local! {
+    static CATCHING_PANICS: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+// Runs f and turns its panic into the message. The panic hook is installed
+// once, and only stays silent on the threads inside catch_panic: any other
+// panic still prints where it happened.
+pub fn catch_panic<T, F>(f: F) -> Result<T, String>
+where
+    F: FnOnce() -> T + std::panic::UnwindSafe,
+{
+    static INSTALL_HOOK: std::sync::Once = std::sync::Once::new();
+    INSTALL_HOOK.call_once(|| {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if !CATCHING_PANICS.with(|catching| catching.get()) {
+                default_hook(info);
+            }
+        }));
+    });
+
+    // catch_panic can be nested, the outer one is still catching after
+    let was_catching = CATCHING_PANICS.with(|catching| catching.replace(true));
+    let result = std::panic::catch_unwind(f);
+    CATCHING_PANICS.with(|catching| catching.set(was_catching));
+    result.map_err(panic_message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catch_panic() {
+        assert_eq!(catch_panic(|| 42), Ok(42));
+        assert_eq!(
+            catch_panic(|| -> i64 { panic!("oops") }),
+            Err("oops".to_string())
+        );
+        assert_eq!(
+            catch_panic(|| catch_panic(|| -> i64 { panic!("inner") })),
+            Ok(Err("inner".to_string()))
+        );
+        // the other threads, and this one after, aren't silenced
+        let threads = (0..8)
+            .map(|_| std::thread::spawn(|| catch_panic(|| -> i64 { panic!("thread") })))
+            .collect::<Vec<_>>();
+        for thread in threads {
+            assert_eq!(thread.join().unwrap(), Err("thread".to_string()));
+        }
+        assert!(!CATCHING_PANICS.with(|catching| catching.get()));
+    }
+}
@@ -1,63 +1,707 @@
-mod day_01;
-mod day_02;
-mod day_03;
-mod day_04;
-mod day_05;
-mod day_06;
-mod day_07;
-mod day_08;
-mod day_09;
-mod day_10;
-mod day_11;
-mod day_12;
-mod day_13;
-mod day_14;
-mod day_15;
-mod day_16;
-mod day_17;
-mod day_18;
-mod day_19;
-
-fn execute_day<F, G>(day: &str, data: &str, part_1: F, part_2: G) -> ()
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use advent_of_code_2023_rust::animation::{Animation, DEFAULT_GIF_DELAY, DEFAULT_MAX_FRAMES};
+use advent_of_code_2023_rust::image::{Image, Palette};
+use advent_of_code_2023_rust::render::Backend;
+use advent_of_code_2023_rust::trace::Level;
+use advent_of_code_2023_rust::{
+    animation, catch_panic, day_01, day_02, day_03, day_04, day_05, day_06, day_07, day_08, day_09,
+    day_10, day_11, day_12, day_13, day_14, day_15, day_16, day_17, day_18, day_19, generate,
+    minimize, trace,
+};
+
+// Runs a part, and turns its panic into the message,
+// as the parse errors are meant to be read by humans.
+// The parts return their errors, catching the panics is only a safety net.
+fn run_part<F>(part: F, data: &str) -> Result<i64, String>
 where
-    F: Fn(&str) -> i64,
-    G: Fn(&str) -> i64,
+    F: Fn(&str) -> Result<i64, String>,
 {
+    catch_panic(std::panic::AssertUnwindSafe(|| part(data))).and_then(|result| result)
+}
+
+// Same, but the part reads the file itself instead of a loaded string.
+fn run_stream_part(part: StreamFn, path: &Path) -> Result<i64, String> {
+    let file = std::fs::File::open(path).map_err(|error| error.to_string())?;
+    catch_panic(|| part(Box::new(BufReader::new(file)))).and_then(|result| result)
+}
+
+fn read_input(path: &Path) -> Result<String, String> {
+    match path == Path::new("-") {
+        true => std::io::read_to_string(std::io::stdin()),
+        false => std::fs::read_to_string(path),
+    }
+    .map_err(|error| error.to_string())
+}
+
+const USAGE: &str = "Usage: advent_of_code_2023_rust [DAY...] [--show ascii|unicode|ansi]
+    [--image PATH] [--palette SYMBOL=RRGGBB,...] [--scale N]
+    [--animate] [--gif PATH] [--frames N] [--dot PATH] [--svg PATH] [--fill]
+    [--trace PATH] [--log-level error|warn|info|debug|trace] [--input PATH]
+
+The image format is picked from the extension of PATH, png or ppm.
+{day} in PATH is replaced by the day number.
+--animate plays the simulation days in the terminal, with the --show back-end.
+--dot writes the graphviz graph of the graph days, on stdout if PATH is -.
+--svg writes the polygon of each part, {part} in PATH is replaced by the part,
+or _part_N is added before the extension. --fill fills the polygon.
+--trace writes the spans and events as a Chrome trace, for ui.perfetto.dev.
+--log-level prints the events up to that level on stderr, warn by default,
+info with --trace.
+--input runs the days on PATH, or stdin if PATH is -, instead of inputs/.
+The days that can stream a file read it without loading it whole.
+
+       advent_of_code_2023_rust generate YEAR DAY [--size N] [--seed S]
+
+Prints a random input for the day, of the size of the real inputs by default.
+
+       advent_of_code_2023_rust minimize DAY INPUT [--part N]
+
+Shrinks INPUT, or stdin if INPUT is -, while the day still panics, fails,
+or disagrees with its reference, and prints it as a regression test.";
+
+// The command line is simple enough to not need a crate.
+#[derive(Debug, PartialEq, Eq)]
+struct Options {
+    // empty means all the days
+    days: Vec<u32>,
+    show: Option<Backend>,
+    image: Option<PathBuf>,
+    palette: Palette,
+    scale: usize,
+    animate: bool,
+    gif: Option<PathBuf>,
+    max_frames: usize,
+    dot: Option<PathBuf>,
+    svg: Option<PathBuf>,
+    fill: bool,
+    trace: Option<PathBuf>,
+    log_level: Option<Level>,
+    input: Option<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            days: Vec::new(),
+            show: None,
+            image: None,
+            palette: Palette::default(),
+            scale: 1,
+            animate: false,
+            gif: None,
+            max_frames: DEFAULT_MAX_FRAMES,
+            dot: None,
+            svg: None,
+            fill: false,
+            trace: None,
+            log_level: None,
+            input: None,
+        }
+    }
+}
+
+impl Options {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+        let mut options = Options::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--show" => {
+                    let backend = args.next().unwrap_or_else(|| "unicode".to_string());
+                    options.show = Some(backend.parse()?);
+                }
+                "--animate" => options.animate = true,
+                "--fill" => options.fill = true,
+                "--image" | "--palette" | "--scale" | "--gif" | "--frames" | "--dot" | "--svg"
+                | "--trace" | "--log-level" | "--input" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("Missing value for {}", arg))?;
+                    let parse_number = |value: &str| {
+                        value
+                            .parse::<usize>()
+                            .map_err(|_| format!("Invalid number for {}: {}", arg, value))
+                    };
+                    match arg.as_str() {
+                        "--image" => options.image = Some(PathBuf::from(value)),
+                        "--palette" => options.palette = value.parse()?,
+                        "--scale" => options.scale = parse_number(&value)?,
+                        "--gif" => options.gif = Some(PathBuf::from(value)),
+                        "--dot" => options.dot = Some(PathBuf::from(value)),
+                        "--svg" => options.svg = Some(PathBuf::from(value)),
+                        "--trace" => options.trace = Some(PathBuf::from(value)),
+                        "--log-level" => options.log_level = Some(value.parse()?),
+                        "--input" => options.input = Some(PathBuf::from(value)),
+                        _ => options.max_frames = parse_number(&value)?,
+                    }
+                }
+                _ => options.days.push(
+                    arg.parse()
+                        .map_err(|_| format!("Unknown argument: {}", arg))?,
+                ),
+            }
+        }
+        Ok(options)
+    }
+
+    fn selects(&self, day: &str) -> bool {
+        self.days.is_empty() || day.parse().is_ok_and(|day| self.days.contains(&day))
+    }
+
+    fn wants_visuals(&self) -> bool {
+        self.show.is_some()
+            || self.image.is_some()
+            || self.animate
+            || self.gif.is_some()
+            || self.dot.is_some()
+            || self.svg.is_some()
+    }
+}
+
+// Running the days is the default, the other commands are tools around them.
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    // boxed, the options are much bigger than the other commands
+    Run(Box<Options>),
+    Generate {
+        year: u32,
+        day: u32,
+        size: Option<usize>,
+        seed: u64,
+    },
+    Minimize {
+        day: u32,
+        part: Option<u32>,
+        input: PathBuf,
+    },
+}
+
+impl Command {
+    fn parse<I: Iterator<Item = String>>(args: I) -> Result<Command, String> {
+        let mut args = args.peekable();
+        match args.peek().map(String::as_str) {
+            Some("generate") => {
+                args.next();
+                Command::parse_generate(args)
+            }
+            Some("minimize") => {
+                args.next();
+                Command::parse_minimize(args)
+            }
+            _ => Options::parse(args).map(|options| Command::Run(Box::new(options))),
+        }
+    }
+
+    fn parse_generate<I: Iterator<Item = String>>(mut args: I) -> Result<Command, String> {
+        let mut numbers = Vec::new();
+        let (mut size, mut seed) = (None, 0);
+        while let Some(arg) = args.next() {
+            let mut parse_value = |name: &str| {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("Missing value for {}", name))?;
+                value
+                    .parse::<u64>()
+                    .map_err(|_| format!("Invalid number for {}: {}", name, value))
+            };
+            match arg.as_str() {
+                "--size" => size = Some(parse_value("--size")? as usize),
+                "--seed" => seed = parse_value("--seed")?,
+                _ => numbers.push(
+                    arg.parse::<u32>()
+                        .map_err(|_| format!("Unknown argument: {}", arg))?,
+                ),
+            }
+        }
+        match numbers[..] {
+            [year, day] => Ok(Command::Generate {
+                year,
+                day,
+                size,
+                seed,
+            }),
+            _ => Err("generate needs a year and a day".to_string()),
+        }
+    }
+
+    fn parse_minimize<I: Iterator<Item = String>>(mut args: I) -> Result<Command, String> {
+        let mut positional = Vec::new();
+        let mut part = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--part" => {
+                    let value = args.next().ok_or("Missing value for --part")?;
+                    part = Some(
+                        value
+                            .parse::<u32>()
+                            .map_err(|_| format!("Invalid number for --part: {}", value))?,
+                    );
+                }
+                _ => positional.push(arg),
+            }
+        }
+        match &positional[..] {
+            [day, input] => Ok(Command::Minimize {
+                day: day
+                    .parse()
+                    .map_err(|_| format!("Unknown argument: {}", day))?,
+                part,
+                input: PathBuf::from(input),
+            }),
+            _ => Err("minimize needs a day and an input".to_string()),
+        }
+    }
+}
+
+fn run_minimize(day: u32, part: Option<u32>, input: &Path) -> Result<(), String> {
+    let data = read_input(input)?;
+    let minimized = minimize::minimize(day, part, &data)?;
+    eprintln!(
+        "Day {}, part {}: {} bytes shrunk to {}",
+        day,
+        minimized.part,
+        minimized.original_size,
+        minimized.input.len()
+    );
+    print!("{}", minimized.regression_test());
+    Ok(())
+}
+
+type ShowFn = fn(&str, Backend) -> String;
+type ImageFn = fn(&str, &Palette, usize) -> Image;
+type FramesFn = fn(&str, usize) -> Animation;
+type DotFn = fn(&str) -> Result<String, String>;
+type SvgFn = fn(&str, usize, bool) -> Result<String, String>;
+type StreamFn = fn(Box<dyn BufRead>) -> Result<i64, String>;
+
+// What a day can do besides its answers.
+#[derive(Default)]
+struct Visuals {
+    show: Option<ShowFn>,
+    image: Option<ImageFn>,
+    frames: Option<FramesFn>,
+    dot: Option<DotFn>,
+    svg: Option<SvgFn>,
+    // the parts that read a file without loading it, for --input
+    stream: Option<(StreamFn, StreamFn)>,
+}
+
+fn output_path(template: &Path, day: &str) -> PathBuf {
+    PathBuf::from(template.to_string_lossy().replace("{day}", day))
+}
+
+fn part_path(template: &Path, day: &str, part: usize) -> PathBuf {
+    let path = output_path(template, day);
+    let text = path.to_string_lossy();
+    if text.contains("{part}") {
+        return PathBuf::from(text.replace("{part}", &part.to_string()));
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let file_name = match path.extension() {
+        Some(extension) => format!("{}_part_{}.{}", stem, part, extension.to_string_lossy()),
+        None => format!("{}_part_{}", stem, part),
+    };
+    path.with_file_name(file_name)
+}
+
+fn write_svgs(
+    options: &Options,
+    template: &Path,
+    day: &str,
+    data: &str,
+    svg: SvgFn,
+) -> Result<(), String> {
+    for part in [1, 2] {
+        let drawing = catch_panic(|| svg(data, part, options.fill)).and_then(|drawing| drawing)?;
+        let path = part_path(template, day, part);
+        std::fs::write(&path, drawing).map_err(|error| error.to_string())?;
+        println!("Day {}, part {} drawing: {}", day, part, path.display());
+    }
+    Ok(())
+}
+
+fn save_image(options: &Options, path: &Path, data: &str, image: ImageFn) -> Result<(), String> {
+    let image = catch_panic(|| image(data, &options.palette, options.scale))?;
+    image.save(path).map_err(|error| error.to_string())
+}
+
+fn write_dot(template: &Path, day: &str, data: &str, dot: DotFn) -> Result<(), String> {
+    let graph = catch_panic(|| dot(data)).and_then(|graph| graph)?;
+    if template == Path::new("-") {
+        print!("{}", graph);
+        return Ok(());
+    }
+    let path = output_path(template, day);
+    std::fs::write(&path, graph).map_err(|error| error.to_string())?;
+    println!("Day {}, graph: {}", day, path.display());
+    Ok(())
+}
+
+fn animate(options: &Options, day: &str, data: &str, frames: FramesFn) -> Result<(), String> {
+    let animation = catch_panic(|| frames(data, options.max_frames))?;
+
+    if let Some(template) = &options.gif {
+        let path = output_path(template, day);
+        animation.save_gif(&path, &options.palette, options.scale, DEFAULT_GIF_DELAY)?;
+        println!("Day {}, animation: {}", day, path.display());
+    }
+
+    if options.animate {
+        let commands = animation::stdin_commands()
+            .lock()
+            .map_err(|error| error.to_string())?;
+        animation::play(
+            &animation,
+            options.show.unwrap_or(Backend::Unicode),
+            &commands,
+            &mut std::io::stdout(),
+        )
+        .map_err(|error| error.to_string())?;
+    }
+    Ok(())
+}
+
+fn execute_day<F, G>(
+    options: &Options,
+    day: &str,
+    data: &str,
+    part_1: F,
+    part_2: G,
+    visuals: Visuals,
+) where
+    F: Fn(&str) -> Result<i64, String>,
+    G: Fn(&str) -> Result<i64, String>,
+{
+    if !options.selects(day) {
+        return;
+    }
+    // a file is only loaded when the day can't stream it, or for the visuals
+    let streamed = options
+        .input
+        .as_deref()
+        .filter(|path| *path != Path::new("-"))
+        .zip(visuals.stream);
+    let loaded = match &options.input {
+        Some(path) if streamed.is_none() || options.wants_visuals() => match read_input(path) {
+            Ok(loaded) => Some(loaded),
+            Err(message) => {
+                println!("Day {}, input failed: {}", day, message);
+                return;
+            }
+        },
+        _ => None,
+    };
+    let data = loaded.as_deref().unwrap_or(data);
+
     let now = std::time::Instant::now();
-    println!("Day {}, part 1: {}", day, part_1(data));
-    println!("Day {}, part 2: {}", day, part_2(data));
+    let run_traced_part = |part: usize, run: &dyn Fn() -> Result<i64, String>| {
+        let _span = trace::span(Level::Info, &format!("day {} part {}", day, part));
+        run()
+    };
+    let results = match streamed {
+        Some((path, (stream_1, stream_2))) => [
+            (1, run_traced_part(1, &|| run_stream_part(stream_1, path))),
+            (2, run_traced_part(2, &|| run_stream_part(stream_2, path))),
+        ],
+        None => [
+            (1, run_traced_part(1, &|| run_part(&part_1, data))),
+            (2, run_traced_part(2, &|| run_part(&part_2, data))),
+        ],
+    };
+    for (part, result) in results {
+        match result {
+            Ok(answer) => println!("Day {}, part {}: {}", day, part, answer),
+            Err(message) => println!("Day {}, part {} failed: {}", day, part, message),
+        }
+    }
     println!("Time: {:?}", now.elapsed());
+
+    if let (Some(backend), Some(show)) = (options.show, visuals.show) {
+        match catch_panic(|| show(data, backend)) {
+            Ok(shown) => println!("{}", shown),
+            Err(message) => println!("Day {}, show failed: {}", day, message),
+        }
+    }
+
+    if let (Some(template), Some(image)) = (&options.image, visuals.image) {
+        let path = output_path(template, day);
+        match save_image(options, &path, data, image) {
+            Ok(()) => println!("Day {}, image: {}", day, path.display()),
+            Err(message) => println!("Day {}, image failed: {}", day, message),
+        }
+    }
+
+    if let (Some(template), Some(dot)) = (&options.dot, visuals.dot) {
+        if let Err(message) = write_dot(template, day, data, dot) {
+            println!("Day {}, graph failed: {}", day, message);
+        }
+    }
+
+    if let (Some(template), Some(svg)) = (&options.svg, visuals.svg) {
+        if let Err(message) = write_svgs(options, template, day, data, svg) {
+            println!("Day {}, drawing failed: {}", day, message);
+        }
+    }
+
+    if options.animate || options.gif.is_some() {
+        if let Some(frames) = visuals.frames {
+            if let Err(message) = animate(options, day, data, frames) {
+                println!("Day {}, animation failed: {}", day, message);
+            }
+        }
+    }
 }
 
 macro_rules! execute_day {
-    ($day:expr, $part_1:expr, $part_2:expr) => {
+    ($options:expr, $day:expr, $part_1:expr, $part_2:expr $(, $visual:ident = $function:expr)*) => {
+        // some days have all the visuals
+        #[allow(clippy::needless_update)]
+        let visuals = Visuals {
+            $($visual: Some($function),)*
+            ..Visuals::default()
+        };
         execute_day(
+            $options,
             $day,
             include_str!(concat!("../inputs/day_", $day, ".txt")),
             $part_1,
             $part_2,
+            visuals,
         );
     };
 }
 
 fn main() {
-    execute_day!("01", day_01::day_1_part_1, day_01::day_1_part_2);
-    execute_day!("02", day_02::day_2_part_1, day_02::day_2_part_2);
-    execute_day!("03", day_03::day_3_part_1, day_03::day_3_part_2);
-    execute_day!("04", day_04::day_4_part_1, day_04::day_4_part_2);
-    execute_day!("05", day_05::day_5_part_1, day_05::day_5_part_2);
-    execute_day!("06", day_06::day_6_part_1, day_06::day_6_part_2);
-    execute_day!("07", day_07::day_7_part_1, day_07::day_7_part_2);
-    execute_day!("08", day_08::day_8_part_1, day_08::day_8_part_2);
-    execute_day!("09", day_09::day_9_part_1, day_09::day_9_part_2);
-    execute_day!("10", day_10::day_10_part_1, day_10::day_10_part_2);
-    execute_day!("11", day_11::day_11_part_1, day_11::day_11_part_2);
-    execute_day!("12", day_12::day_12_part_1, day_12::day_12_part_2);
-    execute_day!("13", day_13::day_13_part_1, day_13::day_13_part_2);
-    execute_day!("14", day_14::day_14_part_1, day_14::day_14_part_2);
-    execute_day!("15", day_15::day_15_part_1, day_15::day_15_part_2);
-    execute_day!("16", day_16::day_16_part_1, day_16::day_16_part_2);
-    execute_day!("17", day_17::day_17_part_1, day_17::day_17_part_2);
-    execute_day!("18", day_18::day_18_part_1, day_18::day_18_part_2);
-    execute_day!("19", day_19::day_19_part_1, day_19::day_19_part_2);
+    let options = match Command::parse(std::env::args().skip(1)) {
+        Ok(Command::Run(options)) => options,
+        Ok(Command::Generate {
+            year,
+            day,
+            size,
+            seed,
+        }) => match generate::generate(year, day, size, seed) {
+            Ok(input) => {
+                print!("{}", input);
+                return;
+            }
+            Err(message) => {
+                eprintln!("{}", message);
+                std::process::exit(1);
+            }
+        },
+        Ok(Command::Minimize { day, part, input }) => {
+            if let Err(message) = run_minimize(day, part, &input) {
+                eprintln!("{}", message);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            std::process::exit(1);
+        }
+    };
+    let options = &options;
+
+    if options.trace.is_some() {
+        trace::start_recording();
+    }
+    let default_level = match options.trace {
+        Some(_) => Level::Info,
+        None => Level::Warn,
+    };
+    trace::set_max_level(options.log_level.unwrap_or(default_level));
+
+    execute_day!(
+        options,
+        "01",
+        day_01::day_1_part_1,
+        day_01::day_1_part_2,
+        show = day_01::day_1_show,
+        stream = (day_01::day_1_part_1_stream, day_01::day_1_part_2_stream)
+    );
+    execute_day!(options, "02", day_02::day_2_part_1, day_02::day_2_part_2);
+    execute_day!(
+        options,
+        "03",
+        day_03::day_3_part_1,
+        day_03::day_3_part_2,
+        stream = (day_03::day_3_part_1_stream, day_03::day_3_part_2_stream)
+    );
+    execute_day!(options, "04", day_04::day_4_part_1, day_04::day_4_part_2);
+    execute_day!(options, "05", day_05::day_5_part_1, day_05::day_5_part_2);
+    execute_day!(options, "06", day_06::day_6_part_1, day_06::day_6_part_2);
+    execute_day!(options, "07", day_07::day_7_part_1, day_07::day_7_part_2);
+    execute_day!(
+        options,
+        "08",
+        day_08::day_8_part_1,
+        day_08::day_8_part_2,
+        dot = day_08::day_8_dot
+    );
+    execute_day!(options, "09", day_09::day_9_part_1, day_09::day_9_part_2);
+    execute_day!(
+        options,
+        "10",
+        day_10::day_10_part_1,
+        day_10::day_10_part_2,
+        show = day_10::day_10_show,
+        image = day_10::day_10_image
+    );
+    execute_day!(options, "11", day_11::day_11_part_1, day_11::day_11_part_2);
+    execute_day!(options, "12", day_12::day_12_part_1, day_12::day_12_part_2);
+    execute_day!(
+        options,
+        "13",
+        day_13::day_13_part_1,
+        day_13::day_13_part_2,
+        show = day_13::day_13_show
+    );
+    execute_day!(
+        options,
+        "14",
+        day_14::day_14_part_1,
+        day_14::day_14_part_2,
+        show = day_14::day_14_show,
+        frames = day_14::day_14_frames
+    );
+    execute_day!(options, "15", day_15::day_15_part_1, day_15::day_15_part_2);
+    execute_day!(
+        options,
+        "16",
+        day_16::day_16_part_1,
+        day_16::day_16_part_2,
+        show = day_16::day_16_show,
+        image = day_16::day_16_image,
+        frames = day_16::day_16_frames
+    );
+    execute_day!(options, "17", day_17::day_17_part_1, day_17::day_17_part_2);
+    execute_day!(
+        options,
+        "18",
+        day_18::day_18_part_1,
+        day_18::day_18_part_2,
+        show = day_18::day_18_show,
+        image = day_18::day_18_image,
+        svg = day_18::day_18_svg
+    );
+    execute_day!(
+        options,
+        "19",
+        day_19::day_19_part_1,
+        day_19::day_19_part_2,
+        dot = day_19::day_19_dot
+    );
+
+    if let Some(path) = &options.trace {
+        match trace::write_chrome_trace(path) {
+            Ok(()) => println!("Trace: {}", path.display()),
+            Err(error) => println!("Trace failed: {}", error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_options() {
+        assert_eq!(parse(&[]), Ok(Options::default()));
+        let options = parse(&["10", "--show", "ansi", "16"]).unwrap();
+        assert_eq!(options.days, vec![10, 16]);
+        assert_eq!(options.show, Some(Backend::Ansi));
+        assert!(options.selects("10"));
+        assert!(!options.selects("01"));
+        assert_eq!(parse(&["--show"]).unwrap().show, Some(Backend::Unicode));
+        assert!(parse(&["--show", "html"]).is_err());
+        assert!(parse(&["--verbose"]).is_err());
+
+        let options = parse(&["--image", "day_{day}.png", "--scale", "4"]).unwrap();
+        assert_eq!(options.image, Some(PathBuf::from("day_{day}.png")));
+        assert_eq!(options.scale, 4);
+        assert!(parse(&["--image"]).is_err());
+        assert!(parse(&["--scale", "big"]).is_err());
+        assert!(parse(&["--palette", "#=zzzzzz"]).is_err());
+
+        let options = parse(&["14", "--animate", "--gif", "tilts.gif", "--frames", "50"]).unwrap();
+        assert!(options.animate);
+        assert_eq!(options.gif, Some(PathBuf::from("tilts.gif")));
+        assert_eq!(options.max_frames, 50);
+        assert_eq!(
+            parse(&["8", "--dot", "-"]).unwrap().dot,
+            Some(PathBuf::from("-"))
+        );
+
+        let options = parse(&["18", "--svg", "lagoon.svg", "--fill"]).unwrap();
+        assert!(options.fill);
+        assert_eq!(
+            part_path(options.svg.as_deref().unwrap(), "18", 2),
+            PathBuf::from("lagoon_part_2.svg")
+        );
+        assert_eq!(
+            part_path(Path::new("out/{day}_{part}.svg"), "18", 1),
+            PathBuf::from("out/18_1.svg")
+        );
+        assert_eq!(
+            output_path(Path::new("/tmp/day_{day}.png"), "16"),
+            PathBuf::from("/tmp/day_16.png")
+        );
+
+        let options = parse(&["5", "--trace", "trace.json", "--log-level", "debug"]).unwrap();
+        assert_eq!(options.trace, Some(PathBuf::from("trace.json")));
+        assert_eq!(options.log_level, Some(Level::Debug));
+        assert!(parse(&["--log-level", "loud"]).is_err());
+
+        let options = parse(&["1", "--input", "big.txt"]).unwrap();
+        assert_eq!(options.input, Some(PathBuf::from("big.txt")));
+        assert!(!options.wants_visuals());
+        assert!(parse(&["--input"]).is_err());
+    }
+
+    #[test]
+    fn test_commands() {
+        let parse = |args: &[&str]| Command::parse(args.iter().map(|arg| arg.to_string()));
+        assert_eq!(
+            parse(&["10"]),
+            Ok(Command::Run(Box::new(
+                Options::parse(["10".to_string()].into_iter()).unwrap()
+            )))
+        );
+        assert_eq!(
+            parse(&["generate", "2023", "10", "--size", "140", "--seed", "42"]),
+            Ok(Command::Generate {
+                year: 2023,
+                day: 10,
+                size: Some(140),
+                seed: 42
+            })
+        );
+        assert_eq!(
+            parse(&["generate", "2023", "12"]),
+            Ok(Command::Generate {
+                year: 2023,
+                day: 12,
+                size: None,
+                seed: 0
+            })
+        );
+        assert!(parse(&["generate", "2023"]).is_err());
+        assert!(parse(&["generate", "2023", "5", "--seed"]).is_err());
+        assert_eq!(
+            parse(&["minimize", "7", "broken.txt", "--part", "2"]),
+            Ok(Command::Minimize {
+                day: 7,
+                part: Some(2),
+                input: PathBuf::from("broken.txt")
+            })
+        );
+        assert!(parse(&["minimize", "7"]).is_err());
+        assert!(parse(&["minimize", "7", "-", "--part"]).is_err());
+    }
 }
@@ -0,0 +1,307 @@
+/*
+    Shrinks an input that makes a day fail, until nothing can be removed
+    without losing the failure. It's how the broken cases of day 7 and
+    the example bug of day 17 were found, but by hand.
+
+    A failure is a panic, or for the days with a reference, an error or
+    an answer that disagrees with it on an input it accepts. The shrunk
+    input must fail the same way: any panic for a panic, any wrong answer
+    for a wrong answer, but the same message for an error, otherwise
+    breaking the input would be enough to keep an error.
+
+    An error without a reference accepting the input may be legit, the
+    input can be invalid. It's only shrunk when nothing else fails, and
+    the regression test pins the message instead of expecting an answer.
+
+    The lines are removed first, then the columns of the grids, then
+    the characters of each line, until none of them removes anything.
+*/
+
+use crate::differential::{self, check, remove_chunks, shrink, Outcome, Solution};
+use crate::{
+    day_01, day_02, day_03, day_04, day_05, day_06, day_07, day_08, day_09, day_10, day_11, day_12,
+    day_13, day_14, day_15, day_16, day_17, day_18, day_19,
+};
+
+const SOLUTIONS: [(Solution, Solution); 19] = [
+    (day_01::day_1_part_1, day_01::day_1_part_2),
+    (day_02::day_2_part_1, day_02::day_2_part_2),
+    (day_03::day_3_part_1, day_03::day_3_part_2),
+    (day_04::day_4_part_1, day_04::day_4_part_2),
+    (day_05::day_5_part_1, day_05::day_5_part_2),
+    (day_06::day_6_part_1, day_06::day_6_part_2),
+    (day_07::day_7_part_1, day_07::day_7_part_2),
+    (day_08::day_8_part_1, day_08::day_8_part_2),
+    (day_09::day_9_part_1, day_09::day_9_part_2),
+    (day_10::day_10_part_1, day_10::day_10_part_2),
+    (day_11::day_11_part_1, day_11::day_11_part_2),
+    (day_12::day_12_part_1, day_12::day_12_part_2),
+    (day_13::day_13_part_1, day_13::day_13_part_2),
+    (day_14::day_14_part_1, day_14::day_14_part_2),
+    (day_15::day_15_part_1, day_15::day_15_part_2),
+    (day_16::day_16_part_1, day_16::day_16_part_2),
+    (day_17::day_17_part_1, day_17::day_17_part_2),
+    (day_18::day_18_part_1, day_18::day_18_part_2),
+    (day_19::day_19_part_1, day_19::day_19_part_2),
+];
+
+pub fn solution(day: u32, part: u32) -> Option<Solution> {
+    let (part_1, part_2) = SOLUTIONS.get((day as usize).checked_sub(1)?)?;
+    match part {
+        1 => Some(*part_1),
+        2 => Some(*part_2),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+pub struct Minimized {
+    pub day: u32,
+    pub part: u32,
+    pub original_size: usize,
+    pub input: String,
+    pub outcome: Outcome,
+}
+
+// The reference decides when there is one and it accepts the input,
+// the solution alone can still panic or return an error otherwise.
+fn outcome(day: u32, part: u32, input: &str) -> Outcome {
+    let cases = differential::cases();
+    let case = cases
+        .iter()
+        .find(|case| case.day == day && case.part == part);
+    if let Some(case) = case {
+        let outcome = check(case, input);
+        if outcome != Outcome::Unchecked {
+            return outcome;
+        }
+    }
+    let Some(solution) = solution(day, part) else {
+        return Outcome::Unchecked;
+    };
+    match crate::catch_panic(|| solution(input)) {
+        Ok(Ok(_)) => Outcome::Unchecked,
+        Ok(Err(error)) => Outcome::Rejected(error),
+        Err(message) => Outcome::Panic(message),
+    }
+}
+
+fn same_failure(original: &Outcome, outcome: &Outcome) -> bool {
+    match (original, outcome) {
+        (Outcome::Mismatch { .. }, Outcome::Mismatch { .. }) => true,
+        (Outcome::Panic(_), Outcome::Panic(_)) => true,
+        (Outcome::Error(original), Outcome::Error(error)) => original == error,
+        (Outcome::Rejected(original), Outcome::Rejected(error)) => original == error,
+        _ => false,
+    }
+}
+
+// Removes the same columns from every line, for the grids.
+fn shrink_columns<F>(input: &str, fails: F) -> String
+where
+    F: Fn(&str) -> bool,
+{
+    let lines = input.lines().collect::<Vec<_>>();
+    let width = lines.first().map_or(0, |line| line.chars().count());
+    if lines.len() < 2 || lines.iter().any(|line| line.chars().count() != width) {
+        return input.to_string();
+    }
+    let keep_columns = |columns: &[usize]| {
+        lines
+            .iter()
+            .map(|line| {
+                let chars = line.chars().collect::<Vec<_>>();
+                columns
+                    .iter()
+                    .map(|&column| chars[column])
+                    .collect::<String>()
+                    + "\n"
+            })
+            .collect::<String>()
+    };
+    let columns = remove_chunks((0..width).collect(), |columns| {
+        fails(&keep_columns(columns))
+    });
+    keep_columns(&columns)
+}
+
+// Removes characters of each line, one line at a time.
+fn shrink_cells<F>(input: &str, fails: F) -> String
+where
+    F: Fn(&str) -> bool,
+{
+    let mut lines = input.lines().map(str::to_string).collect::<Vec<_>>();
+    for index in 0..lines.len() {
+        let chars = lines[index].chars().collect::<Vec<_>>();
+        let chars = remove_chunks(chars, |chars| {
+            let mut candidate = lines.clone();
+            candidate[index] = chars.iter().collect();
+            fails(&(candidate.join("\n") + "\n"))
+        });
+        lines[index] = chars.into_iter().collect();
+    }
+    lines.join("\n") + "\n"
+}
+
+// Shrinks the input with the three passes, while it still fails.
+fn shrink_all<F>(input: &str, fails: F) -> String
+where
+    F: Fn(&str) -> bool,
+{
+    let mut input = input.to_string();
+    loop {
+        let shrunk = shrink(&input, &fails);
+        let shrunk = shrink_columns(&shrunk, &fails);
+        let shrunk = shrink_cells(&shrunk, &fails);
+        if shrunk.len() >= input.len() {
+            break;
+        }
+        input = shrunk;
+    }
+    // the tests don't end their inputs with a new line
+    match input.strip_suffix('\n') {
+        Some(trimmed) if fails(trimmed) => trimmed.to_string(),
+        _ => input,
+    }
+}
+
+// Minimises the first failing part, or the given one.
+pub fn minimize(day: u32, part: Option<u32>, input: &str) -> Result<Minimized, String> {
+    solution(day, 1).ok_or_else(|| format!("Day {} is not solved", day))?;
+    let parts = match part {
+        Some(part) => vec![part],
+        None => vec![1, 2],
+    };
+    // the real failures first, the rejected inputs only otherwise
+    let (part, original) = parts
+        .into_iter()
+        .map(|part| (part, outcome(day, part, input)))
+        .filter(|(_, outcome)| outcome.is_failure() || matches!(outcome, Outcome::Rejected(_)))
+        .min_by_key(|(_, outcome)| !outcome.is_failure())
+        .ok_or_else(|| format!("Day {} doesn't fail on this input", day))?;
+
+    let original_size = input.len();
+    let input = shrink_all(input, |input| {
+        same_failure(&original, &outcome(day, part, input))
+    });
+    Ok(Minimized {
+        day,
+        part,
+        original_size,
+        outcome: outcome(day, part, &input),
+        input,
+    })
+}
+
+impl Minimized {
+    // A test to paste in the tests of the day, like test_broken_case in day 7.
+    pub fn regression_test(&self) -> String {
+        let function = format!("day_{}_part_{}", self.day, self.part);
+        let (comment, assertion) = match &self.outcome {
+            Outcome::Mismatch { expected, found } => (
+                format!("found {} instead of {}", found, expected),
+                format!("assert_eq!({}(BROKEN), Ok({}));", function, expected),
+            ),
+            Outcome::Error(error) => (
+                format!("failed with: {}", error),
+                format!("assert!({}(BROKEN).is_ok());", function),
+            ),
+            Outcome::Rejected(error) => (
+                format!("rejected with: {}", error),
+                format!(
+                    "assert_eq!({}(BROKEN), Err({:?}.to_string()));",
+                    function, error
+                ),
+            ),
+            Outcome::Panic(message) => (
+                format!("panicked with: {}", message),
+                format!("let _ = {}(BROKEN);", function),
+            ),
+            Outcome::Agree | Outcome::Unchecked => (
+                "doesn't fail anymore".to_string(),
+                format!("let _ = {}(BROKEN);", function),
+            ),
+        };
+        format!(
+            "    #[test]\n    fn test_minimized_part_{}() {{\n        // {}\n        const BROKEN: &str = {:?};\n        {}\n    }}\n",
+            self.part, comment, self.input, assertion
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shrink_passes() {
+        let fails = |input: &str| input.contains('#');
+        assert_eq!(shrink_columns("..#\n...\n", fails), "#\n.\n");
+        assert_eq!(shrink_columns("..#\n..\n", fails), "..#\n..\n");
+        assert_eq!(shrink_cells("ab#c\nd#\n", fails), "\n#\n");
+    }
+
+    #[test]
+    fn test_shrink_all() {
+        let input = (0..50)
+            .map(|n| match n {
+                13 => "ab#cd\n".to_string(),
+                31 => "x@y\n".to_string(),
+                _ => format!("{}\n", n),
+            })
+            .collect::<String>();
+        let fails = |input: &str| input.contains('#') && input.contains('@');
+        assert_eq!(shrink_all(&input, fails), "#\n@");
+    }
+
+    #[test]
+    fn test_minimize() {
+        // part 1 works, but part 2 needs 4 tiles before turning
+        let minimized = minimize(17, None, "1111\n1111\n1111\n1111\n").unwrap();
+        assert_eq!(minimized.part, 2);
+        assert_eq!(minimized.input, "1");
+        assert_eq!(
+            minimized.outcome,
+            Outcome::Rejected("Failed to find a path".to_string())
+        );
+
+        assert!(minimize(17, Some(1), "1111\n1111\n1111\n1111\n").is_err());
+        assert!(minimize(6, None, "Time: 7\nDistance: 9\n").is_err());
+        assert!(minimize(42, None, "").is_err());
+    }
+
+    #[test]
+    fn test_regression_test() {
+        let minimized = Minimized {
+            day: 7,
+            part: 2,
+            original_size: 20,
+            input: "QQQQ3 2\nQQJJA 1".to_string(),
+            outcome: Outcome::Mismatch {
+                expected: 5,
+                found: 4,
+            },
+        };
+        assert_eq!(
+            minimized.regression_test(),
+            "    #[test]
+    fn test_minimized_part_2() {
+        // found 4 instead of 5
+        const BROKEN: &str = \"QQQQ3 2\\nQQJJA 1\";
+        assert_eq!(day_7_part_2(BROKEN), Ok(5));
+    }
+"
+        );
+
+        let minimized = Minimized {
+            day: 17,
+            part: 2,
+            original_size: 20,
+            input: "1".to_string(),
+            outcome: Outcome::Rejected("Failed to find a path".to_string()),
+        };
+        assert!(minimized.regression_test().contains(
+            "assert_eq!(day_17_part_2(BROKEN), Err(\"Failed to find a path\".to_string()));"
+        ));
+    }
+}
@@ -0,0 +1,224 @@
+/*
+    Number theory helpers.
+
+    Day 8 part 2 was the first time I had to look up the lcm algorithm,
+    so it's now here with its friends. The lcm functions return None
+    instead of silently overflowing, as the puzzle answers get big.
+
+    The Chinese remainder theorem solver accepts moduli that are not
+    coprime, which is what happens with cycle lengths in puzzles.
+
+    checked_sum and checked_product are for the answers themselves,
+    a weird input should give an error and not an overflow.
+*/
+
+macro_rules! gcd_lcm {
+    ($gcd:ident, $lcm:ident, $lcm_list:ident, $type:ty) => {
+        pub fn $gcd(mut a: $type, mut b: $type) -> $type {
+            while b != 0 {
+                let t = b;
+                b = a % b;
+                a = t;
+            }
+            a
+        }
+
+        pub fn $lcm(a: $type, b: $type) -> Option<$type> {
+            if a == 0 || b == 0 {
+                return Some(0);
+            }
+            (a / $gcd(a, b)).checked_mul(b)
+        }
+
+        pub fn $lcm_list(numbers: &[$type]) -> Option<$type> {
+            numbers.iter().try_fold(1, |acc, &number| $lcm(acc, number))
+        }
+    };
+}
+
+gcd_lcm!(gcd, lcm, lcm_list, u64);
+gcd_lcm!(gcd_u128, lcm_u128, lcm_list_u128, u128);
+
+pub fn checked_sum<I: IntoIterator<Item = i64>>(numbers: I) -> Result<i64, String> {
+    numbers
+        .into_iter()
+        .try_fold(0_i64, |sum, number| sum.checked_add(number))
+        .ok_or_else(|| "The sum overflows".to_string())
+}
+
+pub fn checked_product<I: IntoIterator<Item = i64>>(numbers: I) -> Result<i64, String> {
+    numbers
+        .into_iter()
+        .try_fold(1_i64, |product, number| product.checked_mul(number))
+        .ok_or_else(|| "The product overflows".to_string())
+}
+
+// Returns (g, x, y) such as a * x + b * y = g = gcd(a, b).
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1_i128, 0_i128);
+    let (mut old_y, mut y) = (0_i128, 1_i128);
+
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_x, x) = (x, old_x - quotient * x);
+        (old_y, y) = (y, old_y - quotient * y);
+    }
+
+    // keep the gcd positive
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+// The x in [0, modulus) such as a * x = 1 (mod modulus), if it exists.
+pub fn mod_inverse(a: u64, modulus: u64) -> Option<u64> {
+    if modulus == 0 {
+        return None;
+    }
+    let (g, x, _) = extended_gcd(a as i128, modulus as i128);
+    if g != 1 {
+        return None;
+    }
+    Some(x.rem_euclid(modulus as i128) as u64)
+}
+
+// Square and multiply, with u128 intermediate results so it can't overflow.
+pub fn mod_pow(base: u64, exponent: u64, modulus: u64) -> u64 {
+    if modulus == 1 {
+        return 0;
+    }
+    let modulus = modulus as u128;
+    let mut result = 1_u128;
+    let mut base = base as u128 % modulus;
+    let mut exponent = exponent;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result * base % modulus;
+        }
+        base = base * base % modulus;
+        exponent >>= 1;
+    }
+    result as u64
+}
+
+// Solves x = residue (mod modulus) for all the congruences.
+//
+// Returns (x, m) with x the smallest non negative solution and m the lcm of
+// the moduli, so all the solutions are x + k * m. Returns None if the
+// congruences contradict each other, or if m doesn't fit in a u64.
+pub fn chinese_remainder(congruences: &[(u64, u64)]) -> Option<(u64, u64)> {
+    let mut residue = 0_u64;
+    let mut modulus = 1_u64;
+
+    for &(other_residue, other_modulus) in congruences {
+        if other_modulus == 0 {
+            return None;
+        }
+        let other_residue = other_residue % other_modulus;
+
+        // residue + modulus * t = other_residue (mod other_modulus)
+        let g = gcd(modulus, other_modulus);
+        let difference = other_residue as i128 - residue as i128;
+        if difference % g as i128 != 0 {
+            return None;
+        }
+
+        let reduced_modulus = other_modulus / g;
+        let inverse = mod_inverse((modulus / g) % reduced_modulus, reduced_modulus)?;
+        let t = ((difference / g as i128).rem_euclid(reduced_modulus as i128) as u128
+            * inverse as u128)
+            % reduced_modulus as u128;
+
+        let new_modulus = modulus.checked_mul(reduced_modulus)?;
+        residue = ((residue as u128 + modulus as u128 * t) % new_modulus as u128) as u64;
+        modulus = new_modulus;
+    }
+
+    Some((residue, modulus))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gcd_lcm() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(0, 7), 7);
+        assert_eq!(gcd(7, 0), 7);
+        assert_eq!(lcm(4, 6), Some(12));
+        assert_eq!(lcm(0, 6), Some(0));
+        assert_eq!(lcm_list(&[2, 3, 4, 5]), Some(60));
+        assert_eq!(lcm_list(&[]), Some(1));
+
+        // consecutive numbers are coprime
+        assert_eq!(lcm(u64::MAX, u64::MAX - 1), None);
+        assert_eq!(
+            lcm_u128(u64::MAX as u128, u64::MAX as u128 - 1),
+            Some(u64::MAX as u128 * (u64::MAX as u128 - 1))
+        );
+        assert_eq!(gcd_u128(1 << 100, 1 << 70), 1 << 70);
+        assert_eq!(lcm_list_u128(&[u128::MAX, 2]), None);
+    }
+
+    #[test]
+    fn test_checked_sum_product() {
+        assert_eq!(checked_sum([1, 2, 3]), Ok(6));
+        assert_eq!(checked_product([2, 3, 4]), Ok(24));
+        assert_eq!(checked_product([]), Ok(1));
+        assert!(checked_sum([i64::MAX, 1]).is_err());
+        assert!(checked_product([i64::MAX, 2]).is_err());
+    }
+
+    #[test]
+    fn test_extended_gcd() {
+        for (a, b) in [(240, 46), (46, 240), (17, 5), (0, 5), (-12, 18), (7, 0)] {
+            let (g, x, y) = extended_gcd(a, b);
+            assert_eq!(
+                g,
+                gcd(a.unsigned_abs() as u64, b.unsigned_abs() as u64) as i128
+            );
+            assert_eq!(a * x + b * y, g);
+        }
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        assert_eq!(mod_inverse(3, 11), Some(4));
+        assert_eq!(mod_inverse(10, 17), Some(12));
+        assert_eq!(mod_inverse(6, 9), None);
+        assert_eq!(mod_inverse(1, 1), Some(0));
+    }
+
+    #[test]
+    fn test_mod_pow() {
+        assert_eq!(mod_pow(2, 10, 1000), 24);
+        assert_eq!(mod_pow(3, 0, 7), 1);
+        assert_eq!(mod_pow(5, 3, 1), 0);
+        // Fermat's little theorem with a big prime
+        let p = 18446744073709551557;
+        assert_eq!(mod_pow(123456789, p - 1, p), 1);
+    }
+
+    #[test]
+    fn test_chinese_remainder() {
+        // the classic one
+        assert_eq!(
+            chinese_remainder(&[(2, 3), (3, 5), (2, 7)]),
+            Some((23, 105))
+        );
+        // moduli that are not coprime
+        assert_eq!(chinese_remainder(&[(1, 2), (2, 3)]), Some((5, 6)));
+        assert_eq!(chinese_remainder(&[(3, 4), (1, 6)]), Some((7, 12)));
+        assert_eq!(chinese_remainder(&[(3, 4), (2, 6)]), None);
+        // same congruence twice
+        assert_eq!(chinese_remainder(&[(5, 12), (5, 12)]), Some((5, 12)));
+        assert_eq!(chinese_remainder(&[]), Some((0, 1)));
+        // overflow of the combined modulus
+        assert_eq!(chinese_remainder(&[(1, u64::MAX), (2, u64::MAX - 1)]), None);
+    }
+}
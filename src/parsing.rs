@@ -0,0 +1,414 @@
+/*
+    Nom combinators for the shapes that come back every day:
+    grids of cells, lists of numbers separated by spaces, blocks separated
+    by blank lines, and "key: value" headers.
+
+    parse_all is the entry point for the days: it requires the whole input
+    to be consumed, except the trailing whitespace, instead of silently
+    ignoring what the parser couldn't understand.
+
+    When it fails, the nom error is turned into a ParseError that points
+    at the line and column where the parser gave up, like a compiler would:
+
+        expected digits, found "x3" at line 3, column 15
+          |
+        3 | Card 3:  1 21 x3 59 44 | 69 82 63 72 16 21 14  1
+          |               ^
+
+    The nom error of nom only keeps an ErrorKind, so a failed tag would only
+    say "expected a token" without telling which one. The days use the
+    IResult and tag of this module instead, with an error that remembers
+    the expected token or character.
+*/
+
+use std::fmt;
+
+use ndarray::Array2;
+use nom::{
+    character::complete::{char, i64, line_ending, multispace0, space0, space1, u64},
+    combinator::{all_consuming, map_opt, value},
+    error::{ContextError, ErrorKind, FromExternalError},
+    multi::{many1, separated_list1},
+    sequence::{pair, preceded, separated_pair, terminated, tuple},
+    Parser,
+};
+
+use crate::trace::{span, Level};
+
+// Like nom::error::Error, plus what the parser expected when it knows it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Error<I> {
+    pub input: I,
+    pub code: ErrorKind,
+    pub expected: Option<String>,
+}
+
+pub type IResult<I, O, E = Error<I>> = nom::IResult<I, O, E>;
+
+impl<I> Error<I> {
+    pub fn new(input: I, code: ErrorKind) -> Self {
+        Error {
+            input,
+            code,
+            expected: None,
+        }
+    }
+}
+
+impl<I> nom::error::ParseError<I> for Error<I> {
+    fn from_error_kind(input: I, kind: ErrorKind) -> Self {
+        Error::new(input, kind)
+    }
+
+    fn append(_: I, _: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn from_char(input: I, expected: char) -> Self {
+        Error {
+            input,
+            code: ErrorKind::Char,
+            expected: Some(format!("{:?}", expected)),
+        }
+    }
+}
+
+// context("a card", ...) names what was expected when nothing more precise did
+impl<I> ContextError<I> for Error<I> {
+    fn add_context(_: I, context: &'static str, mut other: Self) -> Self {
+        other.expected.get_or_insert_with(|| context.to_string());
+        other
+    }
+}
+
+impl<I, E> FromExternalError<I, E> for Error<I> {
+    fn from_external_error(input: I, kind: ErrorKind, _: E) -> Self {
+        Error::new(input, kind)
+    }
+}
+
+// nom's tag, but the error says which token was expected.
+pub fn tag<'a>(token: &'a str) -> impl Fn(&'a str) -> IResult<&'a str, &'a str> {
+    move |data: &'a str| {
+        nom::bytes::complete::tag(token)(data).map_err(|error: nom::Err<Error<&'a str>>| {
+            error.map(|error| Error {
+                expected: Some(format!("{:?}", token)),
+                ..error
+            })
+        })
+    }
+}
+
+// One or more lines, each made of one or more cells.
+pub fn grid<'a, O, F>(cell: F) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<Vec<O>>>
+where
+    F: Parser<&'a str, O, Error<&'a str>>,
+{
+    separated_list1(line_ending, many1(cell))
+}
+
+// Like grid, but fails if the rows don't have the same length.
+pub fn grid_array2<'a, O, F>(cell: F) -> impl FnMut(&'a str) -> IResult<&'a str, Array2<O>>
+where
+    F: Parser<&'a str, O, Error<&'a str>>,
+{
+    map_opt(grid(cell), |rows: Vec<Vec<O>>| {
+        let nb_rows = rows.len();
+        let nb_cols = rows.first().map_or(0, |row| row.len());
+        if rows.iter().any(|row| row.len() != nb_cols) {
+            return None;
+        }
+        Array2::from_shape_vec((nb_rows, nb_cols), rows.into_iter().flatten().collect()).ok()
+    })
+}
+
+// One or more elements on a line, separated by one or more spaces.
+pub fn spaced_list<'a, O, F>(element: F) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<O>>
+where
+    F: Parser<&'a str, O, Error<&'a str>>,
+{
+    separated_list1(space1, element)
+}
+
+pub fn spaced_u64s(data: &str) -> IResult<&str, Vec<u64>> {
+    spaced_list(u64)(data)
+}
+
+pub fn spaced_i64s(data: &str) -> IResult<&str, Vec<i64>> {
+    spaced_list(i64)(data)
+}
+
+// One or more elements, one per line.
+pub fn lines<'a, O, F>(element: F) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<O>>
+where
+    F: Parser<&'a str, O, Error<&'a str>>,
+{
+    separated_list1(line_ending, element)
+}
+
+pub fn blank_line(data: &str) -> IResult<&str, ()> {
+    value((), pair(line_ending, line_ending))(data)
+}
+
+// One or more blocks, separated by blank lines.
+pub fn blocks<'a, O, F>(block: F) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<O>>
+where
+    F: Parser<&'a str, O, Error<&'a str>>,
+{
+    separated_list1(blank_line, block)
+}
+
+// "key: value", with any number of spaces after the colon.
+pub fn key_value<'a, K, V, F, G>(
+    key: F,
+    value: G,
+) -> impl FnMut(&'a str) -> IResult<&'a str, (K, V)>
+where
+    F: Parser<&'a str, K, Error<&'a str>>,
+    G: Parser<&'a str, V, Error<&'a str>>,
+{
+    separated_pair(key, tuple((char(':'), space0)), value)
+}
+
+// "label: value", when only the value matters.
+pub fn header<'a, V, G>(label: &'a str, value: G) -> impl FnMut(&'a str) -> IResult<&'a str, V>
+where
+    G: Parser<&'a str, V, Error<&'a str>>,
+{
+    preceded(tuple((tag(label), char(':'), space0)), value)
+}
+
+#[derive(PartialEq, Eq, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub line_text: String,
+    pub expected: String,
+    pub found: String,
+}
+
+fn describe_error_kind(kind: ErrorKind) -> String {
+    match kind {
+        ErrorKind::Tag => "a specific token".to_string(),
+        ErrorKind::Char => "a specific character".to_string(),
+        ErrorKind::OneOf | ErrorKind::NoneOf | ErrorKind::Satisfy => {
+            "an allowed character".to_string()
+        }
+        ErrorKind::Digit => "digits".to_string(),
+        ErrorKind::HexDigit => "hexadecimal digits".to_string(),
+        ErrorKind::Alpha => "letters".to_string(),
+        ErrorKind::AlphaNumeric => "letters or digits".to_string(),
+        ErrorKind::Space | ErrorKind::MultiSpace => "spaces".to_string(),
+        ErrorKind::CrLf => "a line ending".to_string(),
+        ErrorKind::Eof => "the end of the input".to_string(),
+        ErrorKind::TakeUntil => "a delimiter".to_string(),
+        ErrorKind::MapRes | ErrorKind::MapOpt | ErrorKind::Verify => "a valid value".to_string(),
+        ErrorKind::Many1 | ErrorKind::SeparatedList | ErrorKind::Count | ErrorKind::ManyTill => {
+            "more elements".to_string()
+        }
+        kind => kind.description().to_lowercase(),
+    }
+}
+
+impl ParseError {
+    // Locates the remaining input of a nom error inside the whole input.
+    pub fn new(data: &str, remaining: &str, kind: ErrorKind) -> ParseError {
+        ParseError::expecting(data, remaining, describe_error_kind(kind))
+    }
+
+    fn expecting(data: &str, remaining: &str, expected: String) -> ParseError {
+        // point at the token, not at the spaces before it
+        let remaining = remaining.trim_start_matches([' ', '\t']);
+        let offset = data.len().saturating_sub(remaining.len());
+        let before = &data[..offset];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        let line_end = data[offset..]
+            .find('\n')
+            .map_or(data.len(), |index| offset + index);
+
+        let found = match remaining.split_whitespace().next() {
+            None => "the end of the input".to_string(),
+            Some(_) if remaining.starts_with(['\n', '\r']) => "a line ending".to_string(),
+            Some(token) => format!("{:?}", token.chars().take(16).collect::<String>()),
+        };
+
+        ParseError {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            line_text: data[line_start..line_end]
+                .trim_end_matches('\r')
+                .to_string(),
+            expected,
+            found,
+        }
+    }
+
+    fn from_nom(data: &str, error: nom::Err<Error<&str>>) -> ParseError {
+        match error {
+            nom::Err::Error(error) | nom::Err::Failure(error) => match error.expected {
+                Some(expected) => ParseError::expecting(data, error.input, expected),
+                None => ParseError::new(data, error.input, error.code),
+            },
+            nom::Err::Incomplete(_) => ParseError::new(data, "", ErrorKind::Complete),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+        writeln!(
+            f,
+            "expected {}, found {} at line {}, column {}",
+            self.expected, self.found, self.line, self.column
+        )?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", self.line, self.line_text)?;
+        write!(f, "{} | {}^", gutter, " ".repeat(self.column - 1))
+    }
+}
+
+// Same as Display, so .expect() on a parse result prints the diagnostic.
+impl fmt::Debug for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f)?;
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// The days return their errors as messages, so ? works on parse_all.
+impl From<ParseError> for String {
+    fn from(error: ParseError) -> String {
+        format!("Failed to parse input data: {:?}", error)
+    }
+}
+
+// Runs the parser on the whole input. Trailing whitespace, like the last
+// line ending of the input files, is fine. Anything else is an error.
+pub fn parse_all<'a, O, F>(parser: F, data: &'a str) -> Result<O, ParseError>
+where
+    F: Parser<&'a str, O, Error<&'a str>>,
+{
+    let _span = span(Level::Info, "parse");
+    all_consuming(terminated(parser, multispace0))(data)
+        .map(|(_, output)| output)
+        .map_err(|error| ParseError::from_nom(data, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use nom::character::complete::{alpha1, one_of};
+
+    #[test]
+    fn test_grid() {
+        let (left, rows) = grid(one_of(".#"))("#.#\n..#\n\nrest").unwrap();
+        assert_eq!(rows, vec![vec!['#', '.', '#'], vec!['.', '.', '#']]);
+        assert_eq!(left, "\n\nrest");
+
+        let (_, array) = grid_array2(one_of(".#"))("#.\n.#").unwrap();
+        assert_eq!(array.dim(), (2, 2));
+        assert_eq!(array[(1, 1)], '#');
+
+        assert!(grid_array2(one_of(".#"))("#.\n.").is_err());
+    }
+
+    #[test]
+    fn test_spaced_lists() {
+        assert_eq!(spaced_u64s("1 22  333"), Ok(("", vec![1, 22, 333])));
+        assert_eq!(spaced_u64s("7 8\n9"), Ok(("\n9", vec![7, 8])));
+        assert_eq!(spaced_i64s("-1 2 -3"), Ok(("", vec![-1, 2, -3])));
+        assert!(spaced_u64s("-1").is_err());
+    }
+
+    #[test]
+    fn test_lines_and_blocks() {
+        assert_eq!(
+            lines(spaced_u64s)("1 2\n3\r\n4 5"),
+            Ok(("", vec![vec![1, 2], vec![3], vec![4, 5]]))
+        );
+
+        let (left, parsed) = blocks(lines(alpha1))("a\nb\n\nc\n\nd\ne\n").unwrap();
+        assert_eq!(parsed, vec![vec!["a", "b"], vec!["c"], vec!["d", "e"]]);
+        assert_eq!(left, "\n");
+    }
+
+    #[test]
+    fn test_key_value_and_header() {
+        assert_eq!(
+            key_value(alpha1, spaced_u64s)("seeds: 79 14"),
+            Ok(("", ("seeds", vec![79, 14])))
+        );
+        assert_eq!(
+            header("Time", spaced_u64s)("Time:      7  15   30"),
+            Ok(("", vec![7, 15, 30]))
+        );
+        assert!(header("Time", spaced_u64s)("Distance: 9").is_err());
+    }
+
+    #[test]
+    fn test_parse_all() {
+        assert_eq!(parse_all(spaced_u64s, "1 2 3\n"), Ok(vec![1, 2, 3]));
+        assert_eq!(
+            parse_all(lines(spaced_u64s), "1\n2\n\n"),
+            Ok(vec![vec![1], vec![2]])
+        );
+        assert!(parse_all(spaced_u64s, "1 2 x").is_err());
+        assert!(parse_all(lines(spaced_u64s), "1\n\n2").is_err());
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        let error = parse_all(lines(spaced_u64s), "1 2\n3 4\n5 x6 7").unwrap_err();
+        assert_eq!(error.line, 3);
+        assert_eq!(error.column, 3);
+        assert_eq!(error.line_text, "5 x6 7");
+        assert_eq!(error.found, "\"x6\"");
+
+        let error = parse_all(lines(spaced_u64s), "1 2\n\n3").unwrap_err();
+        // the blank line is taken as trailing whitespace
+        assert_eq!((error.line, error.column), (3, 1));
+        assert_eq!(error.expected, "the end of the input");
+
+        let error = parse_all(grid_array2(one_of(".#")), "#.\n.\n").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 1));
+        assert_eq!(error.expected, "a valid value");
+
+        let error = parse_all(header("Time", spaced_u64s), "Time: ").unwrap_err();
+        assert_eq!(error.expected, "digits");
+        assert_eq!(error.found, "the end of the input");
+
+        let error = parse_all(header("Time", spaced_u64s), "Tme: 7").unwrap_err();
+        assert_eq!(error.expected, "\"Time\"");
+        assert_eq!(error.found, "\"Tme:\"");
+
+        let error = parse_all(key_value(alpha1, spaced_u64s), "seeds 79").unwrap_err();
+        assert_eq!(error.expected, "':'");
+        assert_eq!(error.column, 7);
+    }
+
+    #[test]
+    fn test_parse_error_display() {
+        let data = "Time: 7 15\nDistance: 9 40 2OO";
+        let error = parse_all(
+            separated_pair(
+                header("Time", spaced_u64s),
+                line_ending,
+                header("Distance", spaced_u64s),
+            ),
+            data,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "expected the end of the input, found \"OO\" at line 2, column 17
+  |
+2 | Distance: 9 40 2OO
+  |                 ^"
+        );
+    }
+}
@@ -0,0 +1,508 @@
+/*
+    Slow but obviously correct solutions, to check the clever ones.
+
+    Some days rest on comments like "by trial and error" or "apparently
+    there is no gap". These references don't: they parse the input with
+    plain string splitting and do what the puzzle says, by brute force.
+    They only work on small inputs, and return None when the input is too
+    big for them or isn't a valid puzzle input, like a dig plan that
+    doesn't close.
+*/
+
+use std::collections::{HashMap, HashSet};
+
+fn numbers(text: &str) -> Option<Vec<u64>> {
+    text.split_whitespace()
+        .map(|number| number.parse().ok())
+        .collect()
+}
+
+// (destination, source, length) of each line of each map.
+type AlmanacMap = Vec<(u64, u64, u64)>;
+
+fn almanac(data: &str) -> Option<(Vec<u64>, Vec<AlmanacMap>)> {
+    let mut blocks = data.trim().split("\n\n");
+    let seeds = numbers(blocks.next()?.strip_prefix("seeds:")?)?;
+    let maps = blocks
+        .map(|block| {
+            block
+                .lines()
+                .skip(1)
+                .map(|line| match numbers(line)?[..] {
+                    [destination, source, length] => Some((destination, source, length)),
+                    _ => None,
+                })
+                .collect()
+        })
+        .collect::<Option<Vec<AlmanacMap>>>()?;
+    Some((seeds, maps))
+}
+
+fn location(maps: &[AlmanacMap], seed: u64) -> u64 {
+    maps.iter().fold(seed, |value, map| {
+        map.iter()
+            .find(|(_, source, length)| *source <= value && value - source < *length)
+            .map_or(value, |(destination, source, _)| {
+                destination + value - source
+            })
+    })
+}
+
+pub fn day_5_part_1(data: &str) -> Option<i64> {
+    let (seeds, maps) = almanac(data)?;
+    seeds.iter().map(|&seed| location(&maps, seed) as i64).min()
+}
+
+// Every single seed of every range.
+pub fn day_5_part_2(data: &str) -> Option<i64> {
+    let (seeds, maps) = almanac(data)?;
+    if seeds
+        .chunks(2)
+        .map(|range| range[range.len() - 1])
+        .sum::<u64>()
+        > 1_000_000
+    {
+        return None;
+    }
+    seeds
+        .chunks_exact(2)
+        .flat_map(|range| range[0]..range[0] + range[1])
+        .map(|seed| location(&maps, seed) as i64)
+        .min()
+}
+
+fn races(data: &str) -> Option<(Vec<&str>, Vec<&str>)> {
+    let mut lines = data.lines();
+    let times = lines.next()?.strip_prefix("Time:")?;
+    let distances = lines.next()?.strip_prefix("Distance:")?;
+    Some((
+        times.split_whitespace().collect(),
+        distances.split_whitespace().collect(),
+    ))
+}
+
+// Tries every duration of the button press.
+fn ways_to_win(time: &str, distance: &str) -> Option<i64> {
+    let (time, distance) = (time.parse::<u64>().ok()?, distance.parse::<u64>().ok()?);
+    if time > 10_000_000 {
+        return None;
+    }
+    Some(
+        (0..=time)
+            .filter(|hold| hold * (time - hold) > distance)
+            .count() as i64,
+    )
+}
+
+pub fn day_6_part_1(data: &str) -> Option<i64> {
+    let (times, distances) = races(data)?;
+    if times.len() != distances.len() {
+        return None;
+    }
+    times
+        .iter()
+        .zip(distances.iter())
+        .map(|(time, distance)| ways_to_win(time, distance))
+        .product()
+}
+
+pub fn day_6_part_2(data: &str) -> Option<i64> {
+    let (times, distances) = races(data)?;
+    ways_to_win(&times.concat(), &distances.concat())
+}
+
+// Every step between two galaxies crosses a row or a column,
+// the empty ones count expansion times.
+fn galaxy_distances(data: &str, expansion: i64) -> Option<i64> {
+    let grid = data
+        .lines()
+        .map(|line| line.chars().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let nb_cols = grid.first()?.len();
+    if grid.iter().any(|row| row.len() != nb_cols) {
+        return None;
+    }
+    let galaxies = (0..grid.len())
+        .flat_map(|row| (0..nb_cols).map(move |col| (row, col)))
+        .filter(|&(row, col)| grid[row][col] == '#')
+        .collect::<Vec<_>>();
+    let row_cost = |row: usize| match grid[row].iter().all(|&c| c == '.') {
+        true => expansion,
+        false => 1,
+    };
+    let col_cost = |col: usize| match grid.iter().all(|row| row[col] == '.') {
+        true => expansion,
+        false => 1,
+    };
+
+    let mut sum = 0;
+    for (index, a) in galaxies.iter().enumerate() {
+        for b in galaxies[index + 1..].iter() {
+            sum += (a.0.min(b.0) + 1..=a.0.max(b.0)).map(row_cost).sum::<i64>();
+            sum += (a.1.min(b.1) + 1..=a.1.max(b.1)).map(col_cost).sum::<i64>();
+        }
+    }
+    Some(sum)
+}
+
+pub fn day_11_part_1(data: &str) -> Option<i64> {
+    galaxy_distances(data, 2)
+}
+
+pub fn day_11_part_2(data: &str) -> Option<i64> {
+    galaxy_distances(data, 1_000_000)
+}
+
+fn spring_rows(data: &str) -> Option<Vec<(String, Vec<usize>)>> {
+    data.lines()
+        .map(|line| {
+            let (springs, groups) = line.split_once(' ')?;
+            let groups = groups
+                .split(',')
+                .map(|group| group.parse().ok())
+                .collect::<Option<Vec<usize>>>()?;
+            Some((springs.to_string(), groups))
+        })
+        .collect()
+}
+
+// Tries every replacement of the unknown springs.
+fn arrangements(springs: &str, groups: &[usize]) -> Option<i64> {
+    let unknowns = springs
+        .char_indices()
+        .filter(|(_, c)| *c == '?')
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    if unknowns.len() > 20 {
+        return None;
+    }
+    let mut count = 0;
+    for replacement in 0..1_u32 << unknowns.len() {
+        let mut row = springs.as_bytes().to_vec();
+        for (bit, &index) in unknowns.iter().enumerate() {
+            row[index] = match replacement >> bit & 1 {
+                1 => b'#',
+                _ => b'.',
+            };
+        }
+        let row_groups = row
+            .split(|&c| c == b'.')
+            .filter(|group| !group.is_empty())
+            .map(|group| group.len())
+            .collect::<Vec<_>>();
+        if row_groups == groups {
+            count += 1;
+        }
+    }
+    Some(count)
+}
+
+pub fn day_12_part_1(data: &str) -> Option<i64> {
+    spring_rows(data)?
+        .iter()
+        .map(|(springs, groups)| arrangements(springs, groups))
+        .sum()
+}
+
+pub fn day_12_part_2(data: &str) -> Option<i64> {
+    spring_rows(data)?
+        .iter()
+        .map(|(springs, groups)| arrangements(&[springs.as_str(); 5].join("?"), &groups.repeat(5)))
+        .sum()
+}
+
+// Digs the trench tile by tile, and floods the outside.
+fn lagoon(instructions: &[(char, i64)]) -> Option<i64> {
+    let mut position = (0_i64, 0_i64);
+    let mut trench = HashSet::new();
+    for &(direction, steps) in instructions {
+        for _ in 0..steps {
+            match direction {
+                'U' => position.0 -= 1,
+                'D' => position.0 += 1,
+                'L' => position.1 -= 1,
+                _ => position.1 += 1,
+            }
+            // the trench doesn't cross itself
+            if !trench.insert(position) {
+                return None;
+            }
+        }
+    }
+    if position != (0, 0) {
+        return None;
+    }
+    // nor touches itself, otherwise the inside is ambiguous
+    let is_touching = trench.iter().any(|&(row, col)| {
+        [
+            (row - 1, col),
+            (row + 1, col),
+            (row, col - 1),
+            (row, col + 1),
+        ]
+        .iter()
+        .filter(|next| trench.contains(next))
+        .count()
+            > 2
+    });
+    if is_touching {
+        return None;
+    }
+
+    let min_row = trench.iter().map(|point| point.0).min()? - 1;
+    let max_row = trench.iter().map(|point| point.0).max()? + 1;
+    let min_col = trench.iter().map(|point| point.1).min()? - 1;
+    let max_col = trench.iter().map(|point| point.1).max()? + 1;
+    let box_area = (max_row - min_row + 1) * (max_col - min_col + 1);
+    if box_area > 4_000_000 {
+        return None;
+    }
+
+    let mut outside = HashSet::from([(min_row, min_col)]);
+    let mut stack = vec![(min_row, min_col)];
+    while let Some((row, col)) = stack.pop() {
+        for next in [
+            (row - 1, col),
+            (row + 1, col),
+            (row, col - 1),
+            (row, col + 1),
+        ] {
+            let in_box =
+                (min_row..=max_row).contains(&next.0) && (min_col..=max_col).contains(&next.1);
+            if in_box && !trench.contains(&next) && outside.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+    Some(box_area - outside.len() as i64)
+}
+
+// (direction, steps, colour) of each line.
+fn dig_plan(data: &str) -> Option<Vec<(char, i64, &str)>> {
+    data.lines()
+        .map(|line| {
+            let mut parts = line.split_whitespace();
+            let direction = parts.next()?.chars().next()?;
+            let steps = parts.next()?.parse().ok()?;
+            let colour = parts.next()?.strip_prefix("(#")?.strip_suffix(')')?;
+            Some((direction, steps, colour))
+        })
+        .collect()
+}
+
+pub fn day_18_part_1(data: &str) -> Option<i64> {
+    let instructions = dig_plan(data)?
+        .iter()
+        .map(|&(direction, steps, _)| (direction, steps))
+        .collect::<Vec<_>>();
+    lagoon(&instructions)
+}
+
+pub fn day_18_part_2(data: &str) -> Option<i64> {
+    let instructions = dig_plan(data)?
+        .iter()
+        .map(|(_, _, colour)| {
+            let steps = i64::from_str_radix(colour.get(..5)?, 16).ok()?;
+            let direction = match colour.get(5..)? {
+                "0" => 'R',
+                "1" => 'D',
+                "2" => 'L',
+                "3" => 'U',
+                _ => return None,
+            };
+            Some((direction, steps))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    lagoon(&instructions)
+}
+
+// (category, is lower than, value) and destination of each rule.
+type WorkflowRule = (Option<(usize, bool, u64)>, String);
+type Workflows = HashMap<String, Vec<WorkflowRule>>;
+
+fn workflows_and_parts(data: &str) -> Option<(Workflows, Vec<[u64; 4]>)> {
+    let (workflows, parts) = data.trim().split_once("\n\n")?;
+    let workflows = workflows
+        .lines()
+        .map(|line| {
+            let (name, rules) = line.strip_suffix('}')?.split_once('{')?;
+            let rules = rules
+                .split(',')
+                .map(|rule| match rule.split_once(':') {
+                    None => Some((None, rule.to_string())),
+                    Some((condition, destination)) => {
+                        let category = "xmas".find(condition.get(..1)?)?;
+                        let is_lower = condition.get(1..2)? == "<";
+                        let value = condition.get(2..)?.parse().ok()?;
+                        Some((Some((category, is_lower, value)), destination.to_string()))
+                    }
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some((name.to_string(), rules))
+        })
+        .collect::<Option<HashMap<_, _>>>()?;
+    let parts = parts
+        .lines()
+        .map(|line| {
+            let mut ratings = [0; 4];
+            for (category, rating) in line
+                .strip_prefix('{')?
+                .strip_suffix('}')?
+                .split(',')
+                .enumerate()
+            {
+                *ratings.get_mut(category)? = rating.get(2..)?.parse().ok()?;
+            }
+            Some(ratings)
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some((workflows, parts))
+}
+
+// Follows the workflows from in, None if they loop or lead nowhere.
+fn is_accepted(workflows: &Workflows, part: &[u64; 4]) -> Option<bool> {
+    let mut name = "in";
+    for _ in 0..=workflows.len() {
+        let (_, destination) = workflows.get(name)?.iter().find(|(condition, _)| {
+            condition.is_none_or(|(category, is_lower, value)| match is_lower {
+                true => part[category] < value,
+                false => part[category] > value,
+            })
+        })?;
+        match destination.as_str() {
+            "A" => return Some(true),
+            "R" => return Some(false),
+            destination => name = destination,
+        }
+    }
+    None
+}
+
+pub fn day_19_part_1(data: &str) -> Option<i64> {
+    let (workflows, parts) = workflows_and_parts(data)?;
+    let mut sum = 0;
+    for part in parts.iter() {
+        if is_accepted(&workflows, part)? {
+            sum += part.iter().sum::<u64>() as i64;
+        }
+    }
+    Some(sum)
+}
+
+// Between two consecutive values of the conditions, all the ratings
+// are handled the same, so one part of each block is enough.
+pub fn day_19_part_2(data: &str) -> Option<i64> {
+    let (workflows, _) = workflows_and_parts(data)?;
+    let mut bounds = vec![vec![1, 4001]; 4];
+    for (category, is_lower, value) in workflows
+        .values()
+        .flatten()
+        .filter_map(|(condition, _)| *condition)
+    {
+        // the first rating handled differently
+        let bound = match is_lower {
+            true => value,
+            false => value + 1,
+        };
+        bounds[category].push(bound.clamp(1, 4001));
+    }
+    for category_bounds in bounds.iter_mut() {
+        category_bounds.sort_unstable();
+        category_bounds.dedup();
+    }
+    if bounds.iter().map(|bounds| bounds.len()).product::<usize>() > 1_000_000 {
+        return None;
+    }
+
+    let mut combinations = 0;
+    for x in bounds[0].windows(2) {
+        for m in bounds[1].windows(2) {
+            for a in bounds[2].windows(2) {
+                for s in bounds[3].windows(2) {
+                    if is_accepted(&workflows, &[x[0], m[0], a[0], s[0]])? {
+                        combinations +=
+                            ((x[1] - x[0]) * (m[1] - m[0]) * (a[1] - a[0]) * (s[1] - s[0])) as i64;
+                    }
+                }
+            }
+        }
+    }
+    Some(combinations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_references_on_examples() {
+        assert_eq!(
+            day_6_part_1("Time:      7  15   30\nDistance:  9  40  200"),
+            Some(288)
+        );
+        assert_eq!(
+            day_6_part_2("Time:      7  15   30\nDistance:  9  40  200"),
+            Some(71503)
+        );
+
+        let galaxies = "...#......
+.......#..
+#.........
+..........
+......#...
+.#........
+.........#
+..........
+.......#..
+#...#.....";
+        assert_eq!(day_11_part_1(galaxies), Some(374));
+        assert_eq!(galaxy_distances(galaxies, 100), Some(8410));
+
+        let springs = "???.### 1,1,3
+.??..??...?##. 1,1,3
+?#?#?#?#?#?#?#? 1,3,1,6
+????.#...#... 4,1,1
+????.######..#####. 1,6,5
+?###???????? 3,2,1";
+        assert_eq!(day_12_part_1(springs), Some(21));
+
+        let dig_plan = "R 6 (#70c710)
+D 5 (#0dc571)
+L 2 (#5713f0)
+D 2 (#d2c081)
+R 2 (#59c680)
+D 2 (#411b91)
+L 5 (#8ceee2)
+U 2 (#caa173)
+L 1 (#1b58a2)
+U 2 (#caa171)
+R 2 (#7807d2)
+U 3 (#a77fa3)
+L 2 (#015232)
+U 2 (#7a21e3)";
+        assert_eq!(day_18_part_1(dig_plan), Some(62));
+        // the example of part 2 is too big
+        assert_eq!(day_18_part_2(dig_plan), None);
+        assert_eq!(day_18_part_1("R 2 (#000000)\nD 1 (#000000)"), None);
+
+        let workflows = "px{a<2006:qkq,m>2090:A,rfg}
+pv{a>1716:R,A}
+lnx{m>1548:A,A}
+rfg{s<537:gd,x>2440:R,A}
+qs{s>3448:A,lnx}
+qkq{x<1416:A,crn}
+crn{x>2662:A,R}
+in{s<1351:px,qqz}
+qqz{s>2770:qs,m<1801:hdj,R}
+gd{a>3333:R,R}
+hdj{m>838:A,pv}
+
+{x=787,m=2655,a=1222,s=2876}
+{x=1679,m=44,a=2067,s=496}
+{x=2036,m=264,a=79,s=2244}
+{x=2461,m=1339,a=466,s=291}
+{x=2127,m=1623,a=2188,s=1013}";
+        assert_eq!(day_19_part_1(workflows), Some(19114));
+        assert_eq!(day_19_part_2(workflows), Some(167409079868000));
+    }
+}
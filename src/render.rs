@@ -0,0 +1,272 @@
+/*
+    Rendering of grid-like states, instead of a pretty_print function per day.
+
+    A state implements Render by giving its dimensions and a glyph per cell.
+    Any Array2 of a type implementing ToGlyph gets it for free.
+
+    There are three back-ends:
+        - plain ASCII, the characters of the puzzle input
+        - Unicode, with box-drawing characters for pipes and the like
+        - ANSI, Unicode with 24-bit colours for the terminal
+
+    An overlay highlights cells on top of the state, for example the
+    main loop of day 10, with a background colour and an optional symbol.
+*/
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use ndarray::{Array2, ArrayView2};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    // From a 0xRRGGBB number, like the colours of day 18.
+    pub fn from_u32(colour: u32) -> Rgb {
+        Rgb((colour >> 16) as u8, (colour >> 8) as u8, colour as u8)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Backend {
+    Ascii,
+    Unicode,
+    Ansi,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Backend, String> {
+        match name {
+            "ascii" => Ok(Backend::Ascii),
+            "unicode" => Ok(Backend::Unicode),
+            "ansi" => Ok(Backend::Ansi),
+            _ => Err(format!(
+                "Unknown render back-end: {}, expected ascii, unicode or ansi",
+                name
+            )),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Glyph {
+    pub ascii: char,
+    pub unicode: char,
+    pub colour: Option<Rgb>,
+}
+
+impl Glyph {
+    pub fn new(ascii: char) -> Glyph {
+        Glyph {
+            ascii,
+            unicode: ascii,
+            colour: None,
+        }
+    }
+
+    pub fn with_unicode(self, unicode: char) -> Glyph {
+        Glyph { unicode, ..self }
+    }
+
+    pub fn with_colour(self, colour: Rgb) -> Glyph {
+        Glyph {
+            colour: Some(colour),
+            ..self
+        }
+    }
+}
+
+pub trait ToGlyph {
+    fn to_glyph(&self) -> Glyph;
+}
+
+// Most boolean grids are rocks or trenches.
+impl ToGlyph for bool {
+    fn to_glyph(&self) -> Glyph {
+        match self {
+            true => Glyph::new('#').with_unicode('█'),
+            false => Glyph::new('.').with_unicode('·'),
+        }
+    }
+}
+
+impl ToGlyph for char {
+    fn to_glyph(&self) -> Glyph {
+        Glyph::new(*self)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Highlight {
+    pub symbol: Option<char>,
+    pub colour: Rgb,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Overlay {
+    highlights: HashMap<(usize, usize), Highlight>,
+}
+
+impl Overlay {
+    pub fn new() -> Overlay {
+        Overlay::default()
+    }
+
+    // Keeps the glyph, only changes the background colour.
+    pub fn highlight(&mut self, position: (usize, usize), colour: Rgb) {
+        self.highlights.insert(
+            position,
+            Highlight {
+                symbol: None,
+                colour,
+            },
+        );
+    }
+
+    // Replaces the glyph by a symbol, in all the back-ends.
+    pub fn mark(&mut self, position: (usize, usize), symbol: char, colour: Rgb) {
+        self.highlights.insert(
+            position,
+            Highlight {
+                symbol: Some(symbol),
+                colour,
+            },
+        );
+    }
+
+    pub fn get(&self, position: (usize, usize)) -> Option<&Highlight> {
+        self.highlights.get(&position)
+    }
+}
+
+fn push_ansi(output: &mut String, symbol: char, foreground: Option<Rgb>, background: Option<Rgb>) {
+    if foreground.is_none() && background.is_none() {
+        output.push(symbol);
+        return;
+    }
+    output.push_str("\x1b[");
+    if let Some(Rgb(r, g, b)) = foreground {
+        write!(output, "38;2;{};{};{}", r, g, b).unwrap();
+    }
+    if let Some(Rgb(r, g, b)) = background {
+        if foreground.is_some() {
+            output.push(';');
+        }
+        write!(output, "48;2;{};{};{}", r, g, b).unwrap();
+    }
+    write!(output, "m{}\x1b[0m", symbol).unwrap();
+}
+
+pub trait Render {
+    // (rows, columns)
+    fn dim(&self) -> (usize, usize);
+    fn glyph(&self, row: usize, col: usize) -> Glyph;
+
+    fn render(&self, backend: Backend) -> String {
+        self.render_with_overlay(backend, &Overlay::new())
+    }
+
+    fn render_with_overlay(&self, backend: Backend, overlay: &Overlay) -> String {
+        let (nb_rows, nb_cols) = self.dim();
+        let mut output = String::new();
+
+        for row in 0..nb_rows {
+            for col in 0..nb_cols {
+                let glyph = self.glyph(row, col);
+                let highlight = overlay.get((row, col));
+                let symbol = highlight.and_then(|highlight| highlight.symbol);
+
+                match backend {
+                    Backend::Ascii => output.push(symbol.unwrap_or(glyph.ascii)),
+                    Backend::Unicode => output.push(symbol.unwrap_or(glyph.unicode)),
+                    Backend::Ansi => push_ansi(
+                        &mut output,
+                        symbol.unwrap_or(glyph.unicode),
+                        glyph.colour,
+                        highlight.map(|highlight| highlight.colour),
+                    ),
+                }
+            }
+            output.push('\n');
+        }
+
+        output
+    }
+}
+
+impl<T: ToGlyph> Render for Array2<T> {
+    fn dim(&self) -> (usize, usize) {
+        Array2::dim(self)
+    }
+
+    fn glyph(&self, row: usize, col: usize) -> Glyph {
+        self[(row, col)].to_glyph()
+    }
+}
+
+impl<T: ToGlyph> Render for ArrayView2<'_, T> {
+    fn dim(&self) -> (usize, usize) {
+        ArrayView2::dim(self)
+    }
+
+    fn glyph(&self, row: usize, col: usize) -> Glyph {
+        self[(row, col)].to_glyph()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ndarray::array;
+
+    #[test]
+    fn test_render_backends() {
+        let grid = array![[true, false], [false, true]];
+        assert_eq!(grid.render(Backend::Ascii), "#.\n.#\n");
+        assert_eq!(grid.render(Backend::Unicode), "█·\n·█\n");
+        // no colours, so no escape codes
+        assert_eq!(grid.render(Backend::Ansi), "█·\n·█\n");
+        assert_eq!(grid.view().render(Backend::Ascii), "#.\n.#\n");
+    }
+
+    #[test]
+    fn test_render_overlay() {
+        let grid = array![['a', 'b', 'c']];
+        let mut overlay = Overlay::new();
+        overlay.highlight((0, 0), Rgb(255, 0, 0));
+        overlay.mark((0, 2), 'X', Rgb(0, 0, 255));
+
+        assert_eq!(grid.render_with_overlay(Backend::Ascii, &overlay), "abX\n");
+        assert_eq!(
+            grid.render_with_overlay(Backend::Ansi, &overlay),
+            "\x1b[48;2;255;0;0ma\x1b[0mb\x1b[48;2;0;0;255mX\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_glyph_colour() {
+        struct Coloured;
+        impl ToGlyph for Coloured {
+            fn to_glyph(&self) -> Glyph {
+                Glyph::new('#').with_colour(Rgb::from_u32(0x70c710))
+            }
+        }
+        let grid = Array2::from_shape_fn((1, 1), |_| Coloured);
+        assert_eq!(grid.render(Backend::Ascii), "#\n");
+        assert_eq!(
+            grid.render(Backend::Ansi),
+            "\x1b[38;2;112;199;16m#\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_backend_from_str() {
+        assert_eq!("unicode".parse::<Backend>(), Ok(Backend::Unicode));
+        assert!("html".parse::<Backend>().is_err());
+    }
+}
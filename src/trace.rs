@@ -0,0 +1,322 @@
+/*
+    Tracing, instead of commenting and uncommenting println! calls.
+
+    Two things are traced:
+        - spans, the time spent in a phase: parsing, each part, and the
+          heavy phases inside the parts like the cycle search of day 14
+        - events, the messages that used to be printed while debugging
+
+    The events at or below the log level are printed on stderr, like:
+
+        [DEBUG] composed the almanac from seed to location into 9 pieces
+
+    When recording, the spans and the events are also kept in memory and
+    can be written as a Chrome trace JSON file, that chrome://tracing and
+    https://ui.perfetto.dev open. Each thread gets its own track,
+    which helps to see what rayon is doing.
+
+    Nothing is recorded and trace_event! doesn't even format its message
+    when the level is disabled, so the instrumentation can stay in the
+    hot loops.
+*/
+
+use std::cell::Cell;
+use std::fmt::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Level, String> {
+        match name.to_lowercase().as_str() {
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            "trace" => Ok(Level::Trace),
+            _ => Err(format!(
+                "Unknown log level: {}, expected error, warn, info, debug or trace",
+                name
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TraceEvent {
+    // a span, with its start and duration in microseconds
+    Complete {
+        name: String,
+        thread: u64,
+        start: f64,
+        duration: f64,
+    },
+    Instant {
+        name: String,
+        level: Level,
+        thread: u64,
+        time: f64,
+    },
+}
+
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(Level::Warn as u8);
+static RECORDING: AtomicBool = AtomicBool::new(false);
+static EVENTS: Mutex<Vec<TraceEvent>> = Mutex::new(Vec::new());
+static NEXT_THREAD: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD: Cell<u64> = const { Cell::new(0) };
+}
+
+fn epoch() -> Instant {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    *EPOCH.get_or_init(Instant::now)
+}
+
+fn microseconds_since_epoch(instant: Instant) -> f64 {
+    instant.duration_since(epoch()).as_secs_f64() * 1_000_000.0
+}
+
+// Small numbers are easier to read in the trace viewers than the OS ids.
+fn thread_id() -> u64 {
+    THREAD.with(|thread| {
+        if thread.get() == 0 {
+            thread.set(NEXT_THREAD.fetch_add(1, Ordering::Relaxed));
+        }
+        thread.get()
+    })
+}
+
+fn record(event: TraceEvent) {
+    if let Ok(mut events) = EVENTS.lock() {
+        events.push(event);
+    }
+}
+
+pub fn set_max_level(level: Level) {
+    MAX_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= MAX_LEVEL.load(Ordering::Relaxed)
+}
+
+pub fn start_recording() {
+    // the timestamps are relative to this point
+    epoch();
+    RECORDING.store(true, Ordering::Relaxed);
+}
+
+pub fn is_recording() -> bool {
+    RECORDING.load(Ordering::Relaxed)
+}
+
+// Prints the event and records it. Use trace_event! instead,
+// it skips the formatting when the level is disabled.
+pub fn event(level: Level, message: String) {
+    if !enabled(level) {
+        return;
+    }
+    eprintln!("[{}] {}", level, message);
+    if is_recording() {
+        record(TraceEvent::Instant {
+            name: message,
+            level,
+            thread: thread_id(),
+            time: microseconds_since_epoch(Instant::now()),
+        });
+    }
+}
+
+macro_rules! trace_event {
+    ($level:expr, $($arg:tt)+) => {
+        if $crate::trace::enabled($level) {
+            $crate::trace::event($level, format!($($arg)+));
+        }
+    };
+}
+pub(crate) use trace_event;
+
+// Recorded when dropped, so a span lasts until the end of its scope,
+// even when the part panics.
+#[must_use = "the span ends when it is dropped"]
+pub struct Span {
+    name: String,
+    start: Option<Instant>,
+}
+
+pub fn span(level: Level, name: &str) -> Span {
+    let active = is_recording() && enabled(level);
+    Span {
+        name: match active {
+            true => name.to_string(),
+            false => String::new(),
+        },
+        start: match active {
+            true => Some(Instant::now()),
+            false => None,
+        },
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if let Some(start) = self.start {
+            record(TraceEvent::Complete {
+                name: std::mem::take(&mut self.name),
+                thread: thread_id(),
+                start: microseconds_since_epoch(start),
+                duration: start.elapsed().as_secs_f64() * 1_000_000.0,
+            });
+        }
+    }
+}
+
+pub fn recorded_events() -> Vec<TraceEvent> {
+    EVENTS
+        .lock()
+        .map(|events| events.clone())
+        .unwrap_or_default()
+}
+
+fn json_string(text: &str) -> String {
+    let mut output = String::with_capacity(text.len() + 2);
+    output.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(output, "\\u{:04x}", c as u32).unwrap(),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+// The JSON object format of the Chrome trace event format.
+pub fn chrome_trace_json(events: &[TraceEvent]) -> String {
+    let events = events
+        .iter()
+        .map(|event| match event {
+            TraceEvent::Complete {
+                name,
+                thread,
+                start,
+                duration,
+            } => format!(
+                "{{\"name\":{},\"cat\":\"span\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":1,\"tid\":{}}}",
+                json_string(name),
+                start,
+                duration,
+                thread
+            ),
+            TraceEvent::Instant {
+                name,
+                level,
+                thread,
+                time,
+            } => format!(
+                "{{\"name\":{},\"cat\":{},\"ph\":\"i\",\"s\":\"t\",\"ts\":{:.3},\"pid\":1,\"tid\":{}}}",
+                json_string(name),
+                json_string(&level.to_string().to_lowercase()),
+                time,
+                thread
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join(",\n");
+    format!(
+        "{{\"traceEvents\":[\n{}\n],\"displayTimeUnit\":\"ms\"}}\n",
+        events
+    )
+}
+
+pub fn write_chrome_trace(path: &Path) -> std::io::Result<()> {
+    std::fs::write(path, chrome_trace_json(&recorded_events()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level() {
+        assert_eq!("debug".parse::<Level>(), Ok(Level::Debug));
+        assert_eq!("WARN".parse::<Level>(), Ok(Level::Warn));
+        assert!("verbose".parse::<Level>().is_err());
+        assert!(Level::Error < Level::Trace);
+        assert_eq!(Level::Info.to_string(), "INFO");
+    }
+
+    #[test]
+    fn test_chrome_trace_json() {
+        let events = vec![
+            TraceEvent::Complete {
+                name: "day 05 part 1".to_string(),
+                thread: 1,
+                start: 0.5,
+                duration: 12.25,
+            },
+            TraceEvent::Instant {
+                name: "found \"it\"".to_string(),
+                level: Level::Debug,
+                thread: 2,
+                time: 3.0,
+            },
+        ];
+        assert_eq!(
+            chrome_trace_json(&events),
+            "{\"traceEvents\":[
+{\"name\":\"day 05 part 1\",\"cat\":\"span\",\"ph\":\"X\",\"ts\":0.500,\"dur\":12.250,\"pid\":1,\"tid\":1},
+{\"name\":\"found \\\"it\\\"\",\"cat\":\"debug\",\"ph\":\"i\",\"s\":\"t\",\"ts\":3.000,\"pid\":1,\"tid\":2}
+],\"displayTimeUnit\":\"ms\"}
+"
+        );
+        assert_eq!(json_string("a\nb\u{1}"), "\"a\\nb\\u0001\"");
+    }
+
+    #[test]
+    fn test_span_recording() {
+        // the other tests may record too, so only look for this span
+        start_recording();
+        {
+            let _span = span(Level::Error, "test_span_recording");
+        }
+        let events = recorded_events();
+        assert!(events.iter().any(|event| matches!(
+            event,
+            TraceEvent::Complete { name, duration, .. }
+                if name == "test_span_recording" && *duration >= 0.0
+        )));
+    }
+}